@@ -0,0 +1,249 @@
+/**
+ * driver_verifier.h - Declarations shared between the C parts of the module
+ */
+#ifndef _DRIVER_VERIFIER_H
+#define _DRIVER_VERIFIER_H
+
+#include <linux/types.h>
+#include <linux/bitops.h>
+#include <linux/input.h>
+#include <linux/mutex.h>
+
+/* driver_verifier_core.c */
+extern struct mutex dv_verifier_lock;
+int dv_verify_all(void);
+const char *dv_verify_status_name(int status);
+struct dv_config;
+int dv_set_config(const struct dv_config *config);
+
+struct dv_dir_entry;
+struct dv_handler_entry;
+struct dv_fw_resources;
+struct dv_evdev_layout;
+struct dv_input_device;
+
+/**
+ * struct dv_kernel_ops - Kernel operations passed to rust_init()
+ * @size: Size of the table, sizeof(struct dv_kernel_ops)
+ *
+ * Mirrors shim::KernelOps on the Rust side, which calls into C through this
+ * table only. Operations are only ever appended, so Rust accepts an older,
+ * shorter table. The operations up to @monitor_detach are required; the
+ * others may be NULL, which disables the features that need them.
+ */
+struct dv_kernel_ops {
+    size_t size;
+
+    /* Required */
+    void (*print)(const char *msg, size_t len);
+    int (*read_file)(const char *path, size_t path_len, char *buffer,
+                     size_t buffer_size, size_t *bytes_read);
+    u64 (*get_time_ms)(void);
+    u64 (*get_time_us)(void);
+    void (*msleep)(u32 ms);
+    int (*check_node)(const char *path, size_t path_len, bool inject);
+    int (*monitor_attach)(const char *node, size_t node_len, u32 slot);
+    void (*monitor_detach)(u32 slot);
+
+    /* Optional */
+    int (*get_device_capabilities)(const char *path, size_t path_len, u64 *abs_support,
+                                   u32 *rel_support, u32 *key_support);
+    int (*get_abs_info)(const char *path, size_t path_len, u32 code,
+                        s32 *minimum, s32 *maximum, s32 *resolution);
+    int (*read_directory)(const char *path, size_t path_len, struct dv_dir_entry *entries,
+                          size_t capacity, size_t *count);
+    int (*get_handlers)(const char *path, size_t path_len, struct dv_handler_entry *entries,
+                        size_t capacity, size_t *count);
+    int (*get_grab)(const char *path, size_t path_len, char *buffer,
+                    size_t buffer_size, size_t *holder_len);
+    int (*grab_node)(const char *path, size_t path_len, int grab);
+    int (*kmsg_line)(u64 *seq, char *buffer, size_t buffer_size, size_t *line_len);
+    int (*replay_create)(const char *path, size_t path_len);
+    void (*replay_event)(u32 type, u32 code, s32 value);
+    void (*replay_destroy)(void);
+    void (*trace_scan_start)(void);
+    void (*trace_scan_end)(u32 devices, int result);
+    void (*trace_phase)(const char *path, size_t path_len, u32 phase, int outcome);
+    void (*trace_classify)(const char *path, size_t path_len, u32 device_type, u32 method);
+    int (*notify)(u32 event, const char *subject, size_t subject_len, s32 value);
+    int (*request_module)(const char *name, size_t name_len);
+    int (*resolve_path)(const char *path, size_t path_len, char *buffer,
+                        size_t buffer_size, size_t *resolved_len);
+    int (*write_file)(const char *path, size_t path_len, const char *buffer,
+                      size_t buffer_len);
+    int (*get_hypervisor)(char *buffer, size_t buffer_size, size_t *name_len);
+    int (*get_fw_resources)(const char *path, size_t path_len, struct dv_fw_resources *res);
+    int (*reset_fw_device)(const char *path, size_t path_len, u32 assert_ms);
+    void (*get_evdev_layout)(struct dv_evdev_layout *layout);
+    int (*next_input_device)(u32 *cursor, struct dv_input_device *entry);
+    void (*notify_syn_dropped)(u32 slot);
+};
+
+/* Results of rust_verify_touchpad() */
+#define DV_VERIFY_WORKING      1
+#define DV_VERIFY_NOT_WORKING  0
+#define DV_VERIFY_ERROR       -1
+#define DV_VERIFY_DEVICE_GONE -2
+#define DV_VERIFY_NOT_EXPECTED -3
+#define DV_VERIFY_ENVIRONMENT_UNSUPPORTED -4
+
+/* Longest handler and handle name copied, must match handlers::HANDLER_NAME_MAX */
+#define DV_HANDLER_NAME_MAX 32
+
+/**
+ * struct dv_handler_entry - An input handler filled in by kernel_get_handlers()
+ * @open: Non-zero if the handler has the device open
+ * @handler_len: Length of @handler
+ * @handle_len: Length of @handle
+ * @handler: Handler name, e.g. "evdev" (not NUL terminated, truncated)
+ * @handle: Handle name, e.g. "event5" (not NUL terminated, truncated)
+ */
+struct dv_handler_entry {
+    u32 open;
+    u32 handler_len;
+    u32 handle_len;
+    char handler[DV_HANDLER_NAME_MAX];
+    char handle[DV_HANDLER_NAME_MAX];
+};
+
+/* Firmware node kinds of struct dv_fw_resources, must match fwnode::FwKind */
+#define DV_FW_NONE 0
+#define DV_FW_DT   1
+#define DV_FW_ACPI 2
+
+/* States of a described resource, must match fwnode::ResourceState */
+#define DV_RES_ABSENT    0
+#define DV_RES_MISSING   1
+#define DV_RES_UNCLAIMED 2
+#define DV_RES_CLAIMED   3
+
+/* Longest compatible string or ACPI HID copied, must match fwnode::FW_ID_MAX */
+#define DV_FW_ID_MAX 32
+
+/**
+ * struct dv_fw_resources - Firmware description filled in by kernel_get_fw_resources()
+ * @kind: DV_FW_* kind of the firmware node describing the device
+ * @id_len: Length of @id
+ * @id: First DT compatible string or ACPI hardware id (not NUL terminated, truncated)
+ * @irq: Linux IRQ number of the described interrupt, 0 if none is mapped
+ * @irq_state: DV_RES_* state of the interrupt
+ * @irq_gpio: DV_RES_* state of the interrupt GPIO
+ * @reset_gpio: DV_RES_* state of the reset GPIO
+ */
+struct dv_fw_resources {
+    u32 kind;
+    u32 id_len;
+    char id[DV_FW_ID_MAX];
+    u32 irq;
+    u32 irq_state;
+    u32 irq_gpio;
+    u32 reset_gpio;
+};
+
+/**
+ * struct dv_evdev_layout - evdev event layout filled in by kernel_get_evdev_layout()
+ * @event_size: sizeof(struct input_event) for native clients
+ * @time_size: Size of each of the seconds and microseconds fields
+ * @type_offset: Offset of the type field
+ * @code_offset: Offset of the code field
+ * @value_offset: Offset of the value field
+ * @big_endian: Non-zero on big endian kernels
+ * @version: EV_VERSION
+ * @compat_event_size: Size of the events 32-bit clients read, 0 without CONFIG_COMPAT
+ * @x32: Non-zero if x32 clients are supported
+ */
+struct dv_evdev_layout {
+    u32 event_size;
+    u32 time_size;
+    u32 type_offset;
+    u32 code_offset;
+    u32 value_offset;
+    u32 big_endian;
+    u32 version;
+    u32 compat_event_size;
+    u32 x32;
+};
+
+/* Longest node and device name copied, must match input_core::NODE_NAME_MAX and DEVICE_NAME_MAX */
+#define DV_INPUT_NODE_MAX 16
+#define DV_INPUT_NAME_MAX 128
+
+/**
+ * struct dv_input_device - An input device filled in by kernel_next_input_device()
+ * @node_len: Length of @node
+ * @name_len: Length of @name
+ * @node: Name of the evdev node, e.g. "event5" (not NUL terminated, truncated)
+ * @name: Device name (not NUL terminated, truncated)
+ * @bustype: Bus type of the input id
+ * @vendor: Vendor of the input id
+ * @product: Product of the input id
+ * @version: Version of the input id
+ * @evbit: Event types the device supports
+ * @keybit: Keys and buttons the device has
+ * @relbit: Relative axes the device has
+ * @absbit: Absolute axes the device has
+ * @propbit: INPUT_PROP_* properties of the device
+ *
+ * The bitmaps have the layout of those in struct input_dev.
+ */
+struct dv_input_device {
+    u32 node_len;
+    u32 name_len;
+    char node[DV_INPUT_NODE_MAX];
+    char name[DV_INPUT_NAME_MAX];
+    u16 bustype;
+    u16 vendor;
+    u16 product;
+    u16 version;
+    unsigned long evbit[BITS_TO_LONGS(EV_CNT)];
+    unsigned long keybit[BITS_TO_LONGS(KEY_CNT)];
+    unsigned long relbit[BITS_TO_LONGS(REL_CNT)];
+    unsigned long absbit[BITS_TO_LONGS(ABS_CNT)];
+    unsigned long propbit[BITS_TO_LONGS(INPUT_PROP_CNT)];
+};
+
+/* driver_verifier_fs.c */
+
+/* Longest directory entry name copied, must match dir::DIR_NAME_MAX */
+#define DV_DIR_NAME_MAX 64
+
+/**
+ * struct dv_dir_entry - A directory entry filled in by kernel_read_directory()
+ * @len: Length of the full name; larger than DV_DIR_NAME_MAX if @name is truncated
+ * @name: Entry name (not NUL terminated)
+ */
+struct dv_dir_entry {
+    u32 len;
+    char name[DV_DIR_NAME_MAX];
+};
+
+int kernel_read_directory(const char *path, size_t path_len, struct dv_dir_entry *entries,
+                          size_t capacity, size_t *count);
+int kernel_read_sysfs_file(const char *path, size_t path_len, char *buffer,
+                           size_t buffer_size, size_t *bytes_read);
+int kernel_resolve_path(const char *path, size_t path_len, char *buffer,
+                        size_t buffer_size, size_t *resolved_len);
+int kernel_write_file(const char *path, size_t path_len, const char *buffer,
+                      size_t buffer_len);
+
+/* driver_verifier_netlink.c */
+int dv_netlink_init(void);
+void dv_netlink_exit(void);
+int kernel_notify(u32 event, const char *subject, size_t subject_len, s32 value);
+void kernel_notify_syn_dropped(u32 slot);
+
+/* driver_verifier_trace.c */
+void kernel_trace_scan_start(void);
+void kernel_trace_scan_end(u32 devices, int result);
+void kernel_trace_phase(const char *path, size_t path_len, u32 phase, int outcome);
+void kernel_trace_classify(const char *path, size_t path_len, u32 device_type, u32 method);
+
+/* driver_verifier_sysfs.c */
+int dv_sysfs_init(void);
+void dv_sysfs_exit(void);
+
+/* driver_verifier_chardev.c */
+int dv_chardev_init(void);
+void dv_chardev_exit(void);
+
+#endif /* _DRIVER_VERIFIER_H */
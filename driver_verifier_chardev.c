@@ -0,0 +1,344 @@
+/**
+ * driver_verifier_chardev.c - Character device of the driver verifier
+ *
+ * Registers the /dev/driver_verifier misc device. Its ioctls control the
+ * event recorder: recording the touchpad, reading out and loading traces,
+ * and replaying a trace through a virtual touchpad. They also select the
+ * verification profile, verify every scanned device, clear the incident
+ * log, read out verification reports, update the configuration, select
+ * the safety policy and force a verification of the touchpads at runtime. The ioctl numbers and structures are defined in driver_verifier_ioctl.h.
+ */
+#include <linux/kernel.h>
+#include <linux/module.h>
+#include <linux/capability.h>
+#include <linux/fs.h>
+#include <linux/miscdevice.h>
+#include <linux/mm.h>
+#include <linux/slab.h>
+#include <linux/uaccess.h>
+
+#include "driver_verifier.h"
+#include "driver_verifier_ioctl.h"
+
+extern int rust_recorder_command(u32 command);
+extern ssize_t rust_recorder_read(struct dv_event_record *events, size_t capacity);
+extern int rust_recorder_load(const struct dv_event_record *events, size_t count);
+extern int rust_configure_profile(u32 profile);
+extern int rust_configure_safety(u32 policy);
+extern int rust_incidents_clear(void);
+extern ssize_t rust_get_report(u32 device_index, u8 *buf, size_t buf_len);
+extern int rust_verify_touchpad(int force);
+
+/* Largest report copied to userspace */
+#define DV_REPORT_MAX_SIZE (4 * PAGE_SIZE)
+
+/* Recorder commands, must match recorder::RecorderCommand on the Rust side */
+#define DV_RECORDER_START  1
+#define DV_RECORDER_STOP   2
+#define DV_RECORDER_REPLAY 3
+
+/**
+ * dv_recorder_command - Runs a recorder command under the verifier lock
+ */
+static long dv_recorder_command(u32 command)
+{
+    int ret;
+
+    mutex_lock(&dv_verifier_lock);
+    ret = rust_recorder_command(command);
+    mutex_unlock(&dv_verifier_lock);
+
+    return ret < 0 ? -EBUSY : ret;
+}
+
+/**
+ * dv_get_trace - Copies the recorded events to userspace
+ */
+static long dv_get_trace(struct dv_event_trace __user *utrace)
+{
+    struct dv_event_trace trace;
+    struct dv_event_record *events;
+    ssize_t count;
+    long ret = 0;
+
+    if (copy_from_user(&trace, utrace, sizeof(trace)))
+        return -EFAULT;
+    if (trace.reserved)
+        return -EINVAL;
+
+    trace.count = min_t(u32, trace.count, DV_RECORDER_CAPACITY);
+    events = kvmalloc_array(trace.count, sizeof(*events), GFP_KERNEL);
+    if (!events)
+        return -ENOMEM;
+
+    mutex_lock(&dv_verifier_lock);
+    count = rust_recorder_read(events, trace.count);
+    mutex_unlock(&dv_verifier_lock);
+    if (count < 0) {
+        ret = -EBUSY;
+        goto out;
+    }
+
+    if (copy_to_user(u64_to_user_ptr(trace.events), events, count * sizeof(*events))) {
+        ret = -EFAULT;
+        goto out;
+    }
+
+    trace.count = count;
+    if (copy_to_user(utrace, &trace, sizeof(trace)))
+        ret = -EFAULT;
+
+out:
+    kvfree(events);
+    return ret;
+}
+
+/**
+ * dv_set_trace - Loads a trace from userspace into the recorder
+ */
+static long dv_set_trace(struct dv_event_trace __user *utrace)
+{
+    struct dv_event_trace trace;
+    struct dv_event_record *events;
+    long ret = 0;
+
+    if (copy_from_user(&trace, utrace, sizeof(trace)))
+        return -EFAULT;
+    if (trace.reserved || trace.count > DV_RECORDER_CAPACITY)
+        return -EINVAL;
+
+    events = vmemdup_array_user(u64_to_user_ptr(trace.events), trace.count, sizeof(*events));
+    if (IS_ERR(events))
+        return PTR_ERR(events);
+
+    /* Serialized with the recorder commands, a load must not race a start or a replay */
+    mutex_lock(&dv_verifier_lock);
+    if (rust_recorder_load(events, trace.count) < 0)
+        ret = -EBUSY;
+    mutex_unlock(&dv_verifier_lock);
+
+    kvfree(events);
+    return ret;
+}
+
+/**
+ * dv_set_profile - Selects the verification profile
+ */
+static long dv_set_profile(u32 __user *uprofile)
+{
+    u32 profile;
+    int ret;
+
+    if (get_user(profile, uprofile))
+        return -EFAULT;
+
+    mutex_lock(&dv_verifier_lock);
+    ret = rust_configure_profile(profile);
+    mutex_unlock(&dv_verifier_lock);
+
+    return ret < 0 ? -EINVAL : 0;
+}
+
+/**
+ * dv_set_safety - Selects the safety policy
+ *
+ * Opening the device is enough for every other ioctl. Relaxing the policy
+ * would undo a read-only deployment, so this one also requires CAP_SYS_ADMIN.
+ */
+static long dv_set_safety(u32 __user *upolicy)
+{
+    u32 policy;
+    int ret;
+
+    if (!capable(CAP_SYS_ADMIN))
+        return -EPERM;
+    if (get_user(policy, upolicy))
+        return -EFAULT;
+
+    mutex_lock(&dv_verifier_lock);
+    ret = rust_configure_safety(policy);
+    mutex_unlock(&dv_verifier_lock);
+
+    return ret < 0 ? -EINVAL : 0;
+}
+
+/**
+ * dv_verify_touchpads - Verifies the touchpads again under the safety policy
+ *
+ * Unlike a read of touchpad_status, which reuses a fresh result and never
+ * repairs, this always verifies and may run the recovery actions the policy
+ * allows.
+ *
+ * Return: 1 if every touchpad is working, 0 if one is not, -ENODEV if every
+ * touchpad is gone, -ENXIO if none is expected in a virtual machine,
+ * -EOPNOTSUPP without /sys/class/input, -EIO if the verification failed
+ */
+static long dv_verify_touchpads(void)
+{
+    int status;
+
+    mutex_lock(&dv_verifier_lock);
+    status = rust_verify_touchpad(1);
+    mutex_unlock(&dv_verifier_lock);
+
+    switch (status) {
+    case DV_VERIFY_WORKING:
+        return 1;
+    case DV_VERIFY_NOT_WORKING:
+        return 0;
+    case DV_VERIFY_DEVICE_GONE:
+        return -ENODEV;
+    case DV_VERIFY_NOT_EXPECTED:
+        return -ENXIO;
+    case DV_VERIFY_ENVIRONMENT_UNSUPPORTED:
+        return -EOPNOTSUPP;
+    default:
+        return -EIO;
+    }
+}
+
+/**
+ * dv_clear_incidents - Clears the incident log
+ */
+static long dv_clear_incidents(void)
+{
+    int ret;
+
+    mutex_lock(&dv_verifier_lock);
+    ret = rust_incidents_clear();
+    mutex_unlock(&dv_verifier_lock);
+
+    return ret < 0 ? -ENODEV : 0;
+}
+
+/**
+ * dv_get_report - Copies the latest verification report of a touchpad to userspace
+ */
+static long dv_get_report(struct dv_report_buffer __user *ureport)
+{
+    struct dv_report_buffer report;
+    u8 *buf;
+    ssize_t len;
+    long ret = 0;
+
+    if (copy_from_user(&report, ureport, sizeof(report)))
+        return -EFAULT;
+
+    report.size = min_t(u32, report.size, DV_REPORT_MAX_SIZE);
+    buf = kvmalloc(report.size, GFP_KERNEL);
+    if (!buf)
+        return -ENOMEM;
+
+    mutex_lock(&dv_verifier_lock);
+    len = rust_get_report(report.index, buf, report.size);
+    mutex_unlock(&dv_verifier_lock);
+
+    if (len < 0) {
+        ret = -ENOENT;
+        goto out;
+    }
+
+    if (len > report.size) {
+        ret = -ENOSPC;
+    } else if (copy_to_user(u64_to_user_ptr(report.data), buf, len)) {
+        ret = -EFAULT;
+        goto out;
+    }
+
+    report.size = len;
+    if (copy_to_user(ureport, &report, sizeof(report)))
+        ret = -EFAULT;
+
+out:
+    kvfree(buf);
+    return ret;
+}
+
+/**
+ * dv_set_config_ioctl - Applies a configuration update from userspace
+ */
+static long dv_set_config_ioctl(struct dv_config __user *uconfig)
+{
+    struct dv_config config;
+
+    if (copy_from_user(&config, uconfig, sizeof(config)))
+        return -EFAULT;
+
+    return dv_set_config(&config);
+}
+
+static long dv_chardev_ioctl(struct file *file, unsigned int cmd, unsigned long arg)
+{
+    void __user *uarg = (void __user *)arg;
+
+    switch (cmd) {
+    case DV_IOC_RECORD_START:
+        return dv_recorder_command(DV_RECORDER_START);
+    case DV_IOC_RECORD_STOP:
+        return dv_recorder_command(DV_RECORDER_STOP);
+    case DV_IOC_GET_TRACE:
+        return dv_get_trace(uarg);
+    case DV_IOC_SET_TRACE:
+        return dv_set_trace(uarg);
+    case DV_IOC_REPLAY:
+        return dv_recorder_command(DV_RECORDER_REPLAY);
+    case DV_IOC_SET_PROFILE:
+        return dv_set_profile(uarg);
+    case DV_IOC_VERIFY_ALL:
+        return dv_verify_all();
+    case DV_IOC_CLEAR_INCIDENTS:
+        return dv_clear_incidents();
+    case DV_IOC_GET_REPORT:
+        return dv_get_report(uarg);
+    case DV_IOC_SET_CONFIG:
+        return dv_set_config_ioctl(uarg);
+    case DV_IOC_SET_SAFETY:
+        return dv_set_safety(uarg);
+    case DV_IOC_VERIFY_TOUCHPADS:
+        return dv_verify_touchpads();
+    default:
+        return -ENOTTY;
+    }
+}
+
+static const struct file_operations dv_chardev_fops = {
+    .owner          = THIS_MODULE,
+    .unlocked_ioctl = dv_chardev_ioctl,
+    .compat_ioctl   = compat_ptr_ioctl,
+    .llseek         = noop_llseek,
+};
+
+static struct miscdevice dv_chardev = {
+    .minor = MISC_DYNAMIC_MINOR,
+    .name  = "driver_verifier",
+    .fops  = &dv_chardev_fops,
+    .mode  = 0600,
+};
+
+static bool dv_chardev_registered;
+
+/**
+ * dv_chardev_init - Registers /dev/driver_verifier
+ *
+ * Return: 0 on success, negative error code on failure
+ */
+int dv_chardev_init(void)
+{
+    int error;
+
+    error = misc_register(&dv_chardev);
+    dv_chardev_registered = !error;
+
+    return error;
+}
+
+/**
+ * dv_chardev_exit - Unregisters /dev/driver_verifier
+ */
+void dv_chardev_exit(void)
+{
+    if (dv_chardev_registered) {
+        misc_deregister(&dv_chardev);
+        dv_chardev_registered = false;
+    }
+}
@@ -11,28 +11,1824 @@
 #include <linux/kernel.h>
 #include <linux/device.h>
 #include <linux/input.h>
+#include <linux/input/mt.h>
+#include <linux/slab.h>
+#include <linux/mutex.h>
+#include <linux/list.h>
+#include <linux/bitmap.h>
+#include <linux/delay.h>
+#include <linux/ktime.h>
+#include <linux/debugfs.h>
+#include <linux/fs.h>
+#include <linux/uaccess.h>
+#include <linux/workqueue.h>
+#include <linux/completion.h>
+#include <linux/atomic.h>
+#include <linux/kmod.h>
+#include <linux/kmsg_dump.h>
+#include <linux/mm.h>
+#include <linux/string.h>
+#include <linux/dmi.h>
+#include <linux/property.h>
+#include <linux/acpi.h>
+#include <linux/gpio/consumer.h>
+#include <linux/interrupt.h>
+#include <linux/compat.h>
+#ifdef CONFIG_X86
+#include <asm/cpufeature.h>
+#endif
+
+#include "driver_verifier.h"
+#include "driver_verifier_ioctl.h"
 
 MODULE_LICENSE("GPL");
 MODULE_AUTHOR("Giorgio Saldana");
 MODULE_DESCRIPTION("A kernel module to verify driver functionality");
 
 /**
- * External function declarations for Rust code
+ * External function declarations for Rust code
+ *
+ * These functions are implemented in the Rust part of the module and
+ * compiled into a static library that gets linked with this C code.
+ */
+extern int rust_init(const struct dv_kernel_ops *ops);
+extern void rust_exit(void);
+extern int rust_scan_devices(void);
+extern int rust_verify_touchpad(int force);
+extern int rust_verify_all_begin(void);
+extern int rust_verify_device(u32 index);
+extern int rust_verify_all_end(void);
+extern int rust_touchpad_count(void);
+extern int rust_touchpad_status(u32 index);
+extern ssize_t rust_touchpads_read(char *buf, size_t buf_len);
+extern int rust_configure_event_check(u32 window_ms, int interactive);
+extern int rust_configure_profile(u32 profile);
+extern int rust_configure_safety(u32 policy);
+extern int rust_configure_enumeration(u32 enumeration);
+extern int rust_configure_cache(u32 ttl_ms);
+extern int rust_configure_exclusive_grab(int enabled);
+extern int rust_configure_pool(u32 bytes, u32 entries);
+extern void rust_input_event(u32 slot, unsigned int type, unsigned int code, int value);
+extern void rust_input_device_changed(const char *name, size_t name_len, int added);
+extern int rust_configure_recovery(int enabled);
+extern int rust_configure_reset_gpio(int enabled);
+extern int rust_configure_module_loading(int enabled);
+extern int rust_configure_seat(const char *seat, size_t seat_len);
+extern int rust_configure_device_filter(const char *ignore, size_t ignore_len,
+                                        const char *only, size_t only_len);
+extern int rust_configure_watchdog(int enabled, u32 freeze_ms, int reverify);
+extern int rust_watchdog_tick(void);
+extern int rust_configure_boot_verify(u32 retry_ms);
+extern int rust_configure_lazy_scan(void);
+extern int rust_set_config(const struct dv_config *config);
+extern int rust_boot_verify(void);
+extern int rust_selftest_command(u32 command);
+extern ssize_t rust_selftest_read(char *buf, size_t buf_len);
+extern ssize_t rust_snapshot(char *buf, size_t buf_len);
+extern int rust_compare_snapshot(const char *blob, size_t blob_len);
+extern ssize_t rust_comparison_read(char *buf, size_t buf_len);
+extern ssize_t rust_incidents_read(char *buf, size_t buf_len);
+extern ssize_t rust_metrics_read(char *buf, size_t buf_len);
+
+/*
+ * Serializes all calls into the Rust verifier once the module is loaded,
+ * since debugfs, sysfs and the character device can call in concurrently.
+ */
+DEFINE_MUTEX(dv_verifier_lock);
+
+static struct dentry *dv_debugfs_dir;
+
+/**
+ * Module parameters controlling the input event check
+ *
+ * In interactive mode the user is asked to touch the touchpad during load and
+ * the verifier waits up to event_window_ms for events. Otherwise only the
+ * device capabilities are validated.
+ */
+static unsigned int event_window_ms = 5000;
+module_param(event_window_ms, uint, 0444);
+MODULE_PARM_DESC(event_window_ms, "How long to wait for touchpad events in interactive mode (ms)");
+
+static bool interactive;
+module_param(interactive, bool, 0444);
+MODULE_PARM_DESC(interactive, "Prompt to touch the touchpad and wait for real events");
+
+static bool exclusive_grab;
+module_param(exclusive_grab, bool, 0444);
+MODULE_PARM_DESC(exclusive_grab, "Grab the touchpad while waiting for events, so no other client receives them");
+
+/*
+ * Verification profile selecting the phases that run and the event window.
+ * When unset, interactive selects "interactive" and otherwise "standard".
+ */
+static char *profile;
+module_param(profile, charp, 0444);
+MODULE_PARM_DESC(profile, "Verification profile: quick, standard, deep or interactive");
+
+static const char * const dv_profile_names[] = {
+    [DV_PROFILE_QUICK]       = "quick",
+    [DV_PROFILE_STANDARD]    = "standard",
+    [DV_PROFILE_DEEP]        = "deep",
+    [DV_PROFILE_INTERACTIVE] = "interactive",
+};
+
+/*
+ * How long a verification result is reused when the status is polled through
+ * sysfs. The verification during load always runs.
+ */
+static unsigned int cache_ttl_ms = 2000;
+module_param(cache_ttl_ms, uint, 0444);
+MODULE_PARM_DESC(cache_ttl_ms, "How long a polled verification result is reused, 0 to always verify (ms)");
+
+/*
+ * Size of the preallocated string pool holding the directory listing of a
+ * scan. The high-water marks under stats/ show how much of it is used.
+ */
+static unsigned int scan_pool_bytes = 8192;
+module_param(scan_pool_bytes, uint, 0444);
+MODULE_PARM_DESC(scan_pool_bytes, "Bytes of string data preallocated for scan results");
+
+static unsigned int scan_pool_entries = 256;
+module_param(scan_pool_entries, uint, 0444);
+MODULE_PARM_DESC(scan_pool_entries, "Number of strings preallocated for scan results");
+
+/*
+ * Safety policy limiting the actions that change device state: read-only
+ * refuses all of them, diagnose allows the exclusive grab and replay, repair
+ * also allows the recovery options. Only the DV_IOC_SET_SAFETY ioctl, which
+ * requires CAP_SYS_ADMIN, changes it after load.
+ */
+static char *safety;
+module_param(safety, charp, 0444);
+MODULE_PARM_DESC(safety, "Safety policy: read-only, diagnose or repair (default)");
+
+static const char * const dv_safety_names[] = {
+    [DV_SAFETY_READ_ONLY] = "read-only",
+    [DV_SAFETY_DIAGNOSE]  = "diagnose",
+    [DV_SAFETY_REPAIR]    = "repair",
+};
+
+/*
+ * Backend the scan enumerates the devices with: sysfs lists /sys/class/input
+ * and parses the attributes of each node, input-core walks the devices
+ * registered with the input core. The scan falls back to the input core on
+ * its own when /sys/class/input is unavailable.
+ */
+static char *enumeration;
+module_param(enumeration, charp, 0444);
+MODULE_PARM_DESC(enumeration, "Device enumeration backend: sysfs (default) or input-core");
+
+static const char * const dv_enumeration_names[] = {
+    [DV_ENUM_SYSFS]      = "sysfs",
+    [DV_ENUM_INPUT_CORE] = "input-core",
+};
+
+static bool recover;
+module_param(recover, bool, 0444);
+MODULE_PARM_DESC(recover, "Rebind the driver of a touchpad that is bound but unresponsive");
+
+static bool reset_gpio;
+module_param(reset_gpio, bool, 0444);
+MODULE_PARM_DESC(reset_gpio, "Pulse the reset GPIO of a touchpad that stays unresponsive and probe it again");
+
+static bool load_modules;
+module_param(load_modules, bool, 0444);
+MODULE_PARM_DESC(load_modules, "Load missing touchpad modules and verify again");
+
+/**
+ * Module parameters limiting which devices are scanned
+ *
+ * Both take comma separated patterns, each either a vendor:product pair in
+ * hex, a case-insensitive substring of the device name or a case-insensitive
+ * glob (* and ?) matching the whole name.
+ */
+static char *ignore_devices = "";
+module_param(ignore_devices, charp, 0444);
+MODULE_PARM_DESC(ignore_devices, "Devices to skip (comma separated names, globs or vendor:product)");
+
+static char *only_devices = "";
+module_param(only_devices, charp, 0444);
+MODULE_PARM_DESC(only_devices, "Only consider these devices (comma separated names, globs or vendor:product)");
+
+/*
+ * Seat the scan is limited to on multi-seat machines, read from the ID_SEAT
+ * property in the udev database. Devices udev has no record of count as seat0.
+ */
+static char *seat = "";
+module_param(seat, charp, 0444);
+MODULE_PARM_DESC(seat, "Only consider the devices of this seat, e.g. seat1");
+
+/**
+ * Module parameters controlling the touchpad freeze watchdog
+ *
+ * When enabled, a freeze is suspected if the touchpad has been silent for
+ * watchdog_freeze_ms while a keyboard is still in use.
+ */
+static bool watchdog;
+module_param(watchdog, bool, 0444);
+MODULE_PARM_DESC(watchdog, "Detect suspected touchpad freezes while the keyboard is in use");
+
+static unsigned int watchdog_freeze_ms = 30000;
+module_param(watchdog_freeze_ms, uint, 0444);
+MODULE_PARM_DESC(watchdog_freeze_ms, "Touchpad silence after which a freeze is suspected (ms)");
+
+static bool watchdog_reverify;
+module_param(watchdog_reverify, bool, 0444);
+MODULE_PARM_DESC(watchdog_reverify, "Re-verify the touchpad when a freeze is suspected");
+
+/**
+ * Module parameters controlling the deferred boot-time verification
+ *
+ * In boot mode the scan and verification run from a work item shortly after
+ * load, and a touchpad that isn't probed yet is retried with backoff for
+ * boot_retry_ms before the final verdict is logged.
+ */
+static bool boot_verify;
+module_param(boot_verify, bool, 0444);
+MODULE_PARM_DESC(boot_verify, "Verify from a work item after load and retry while the touchpad is missing");
+
+static unsigned int boot_retry_ms = 60000;
+module_param(boot_retry_ms, uint, 0444);
+MODULE_PARM_DESC(boot_retry_ms, "How long a missing touchpad is retried in boot mode (ms)");
+
+/*
+ * Skip the scan at load; the first status query scans and verifies, and its
+ * result is cached. Keeps a built-in module off the boot path.
+ */
+static bool lazy_scan;
+module_param(lazy_scan, bool, 0444);
+MODULE_PARM_DESC(lazy_scan, "Defer the scan and verification to the first status query");
+
+/* Delay between module load and the first boot-time attempt */
+#define DV_BOOT_INITIAL_DELAY_MS 1000
+
+static void dv_boot_verify_fn(struct work_struct *work);
+static DECLARE_DELAYED_WORK(dv_boot_verify_work, dv_boot_verify_fn);
+
+/**
+ * dv_boot_verify_fn - One attempt of the boot-time verification
+ */
+static void dv_boot_verify_fn(struct work_struct *work)
+{
+    int delay_ms;
+
+    mutex_lock(&dv_verifier_lock);
+    delay_ms = rust_boot_verify();
+    mutex_unlock(&dv_verifier_lock);
+
+    if (delay_ms > 0)
+        schedule_delayed_work(&dv_boot_verify_work, msecs_to_jiffies(delay_ms));
+}
+
+/* Interval of the watchdog checks, changed at runtime by dv_set_config() */
+static unsigned int dv_watchdog_interval_ms = 1000;
+
+static void dv_watchdog_fn(struct work_struct *work);
+static DECLARE_DELAYED_WORK(dv_watchdog_work, dv_watchdog_fn);
+
+/**
+ * dv_watchdog_fn - Periodic watchdog check
+ */
+static void dv_watchdog_fn(struct work_struct *work)
+{
+    mutex_lock(&dv_verifier_lock);
+    rust_watchdog_tick();
+    mutex_unlock(&dv_verifier_lock);
+
+    schedule_delayed_work(&dv_watchdog_work, msecs_to_jiffies(READ_ONCE(dv_watchdog_interval_ms)));
+}
+
+/**
+ * dv_set_config - Applies a runtime configuration update
+ * @config: Configuration update
+ *
+ * The Rust side validates the whole update and applies it under the verifier
+ * lock; the watchdog interval is applied here, rescheduling a running watchdog.
+ *
+ * Return: 0 on success, -EINVAL if the update is invalid
+ */
+int dv_set_config(const struct dv_config *config)
+{
+    int ret;
+
+    mutex_lock(&dv_verifier_lock);
+    ret = rust_set_config(config);
+    if (!ret && (config->fields & DV_CONFIG_MONITOR_INTERVAL)) {
+        WRITE_ONCE(dv_watchdog_interval_ms, config->monitor_interval_ms);
+        if (watchdog)
+            mod_delayed_work(system_wq, &dv_watchdog_work, msecs_to_jiffies(config->monitor_interval_ms));
+    }
+    mutex_unlock(&dv_verifier_lock);
+
+    return ret < 0 ? -EINVAL : 0;
+}
+
+/*
+ * Number of devices verified in parallel by dv_verify_all(), used as the
+ * max_active limit of the verification workqueue.
+ */
+static unsigned int verify_concurrency = 4;
+module_param(verify_concurrency, uint, 0444);
+MODULE_PARM_DESC(verify_concurrency, "How many devices are verified in parallel when verifying all devices");
+
+static struct workqueue_struct *dv_verify_wq;
+
+/**
+ * struct dv_device_work - Verification of one device by dv_verify_all()
+ * @work: Work item queued on dv_verify_wq
+ * @index: Index of the device in the Rust registry
+ * @pending: Number of work items of the run that have not finished yet
+ * @done: Completed by the last work item of the run
+ */
+struct dv_device_work {
+    struct work_struct work;
+    u32 index;
+    atomic_t *pending;
+    struct completion *done;
+};
+
+/**
+ * dv_device_work_fn - Verifies one device
+ *
+ * Runs without the verifier lock, which dv_verify_all() holds on behalf of
+ * all work items of the run.
+ */
+static void dv_device_work_fn(struct work_struct *work)
+{
+    struct dv_device_work *device_work = container_of(work, struct dv_device_work, work);
+
+    rust_verify_device(device_work->index);
+
+    if (atomic_dec_and_test(device_work->pending))
+        complete(device_work->done);
+}
+
+/**
+ * dv_verify_all - Verifies every scanned device concurrently
+ *
+ * Queues one work item per device and waits until all of them finished.
+ * The verifier lock is held for the whole run so the registry can't change
+ * underneath the work items.
+ *
+ * Return: Number of devices not working, or a negative error code
+ */
+int dv_verify_all(void)
+{
+    DECLARE_COMPLETION_ONSTACK(done);
+    struct dv_device_work *works;
+    atomic_t pending;
+    int count, ret;
+
+    if (!dv_verify_wq)
+        return -ENODEV;
+
+    mutex_lock(&dv_verifier_lock);
+
+    count = rust_verify_all_begin();
+    if (count < 0) {
+        ret = -ENODEV;
+        goto out_unlock;
+    }
+
+    if (count > 0) {
+        works = kcalloc(count, sizeof(*works), GFP_KERNEL);
+        if (!works) {
+            rust_verify_all_end();
+            ret = -ENOMEM;
+            goto out_unlock;
+        }
+
+        atomic_set(&pending, count);
+        for (int i = 0; i < count; i++) {
+            INIT_WORK(&works[i].work, dv_device_work_fn);
+            works[i].index = i;
+            works[i].pending = &pending;
+            works[i].done = &done;
+            queue_work(dv_verify_wq, &works[i].work);
+        }
+
+        wait_for_completion(&done);
+        kfree(works);
+    }
+
+    ret = rust_verify_all_end();
+
+out_unlock:
+    mutex_unlock(&dv_verifier_lock);
+    return ret;
+}
+
+#define DV_MAX_SLOTS 32
+
+/**
+ * struct dv_monitor_handle - Connection of the event monitor to one input device
+ * @handle: Input handle registered with the input core
+ * @slots: Bitmask of Rust monitor slots the device is bound to. The handle is
+ *         open exactly while this is non-zero, so the monitor doesn't keep idle
+ *         devices powered up
+ * @syn_probes: Number of SYN_CONFIG probes injected by kernel_check_node() seen
+ * @probing: Set while kernel_check_node() injects its probe frame
+ * @probe_frame: The last event seen was the SYN_CONFIG of a probe, so a
+ *               SYN_REPORT following it closes the probe frame. Only touched
+ *               by dv_monitor_event() under the device event lock
+ * @probe_lock: Held by kernel_check_node() for the whole check, so checks of
+ *              the same device don't mix their probes, and the handle is not
+ *              unregistered under a running check
+ * @node: Entry in dv_monitor_handles
+ */
+struct dv_monitor_handle {
+    struct input_handle handle;
+    unsigned long slots;
+    atomic_t syn_probes;
+    bool probing;
+    bool probe_frame;
+    struct mutex probe_lock;
+    struct list_head node;
+};
+
+static LIST_HEAD(dv_monitor_handles);
+static DEFINE_MUTEX(dv_monitor_lock);
+
+/**
+ * dv_monitor_event - Forwards an input event to the Rust event monitor
+ *
+ * Called by the input core with the device event lock held and interrupts
+ * disabled, so the Rust side must not sleep here. SYN_CONFIG is not sent by
+ * real devices and is never forwarded. While kernel_check_node() probes the
+ * device its SYN_CONFIG is counted, and the SYN_REPORT closing the probe frame
+ * is dropped too, so the probe never shows up as a frame or as activity in the
+ * monitor slots. A SYN_REPORT of the device itself is preceded by its own
+ * events and still forwarded.
+ */
+static void dv_monitor_event(struct input_handle *handle, unsigned int type,
+                             unsigned int code, int value)
+{
+    struct dv_monitor_handle *mh = container_of(handle, struct dv_monitor_handle, handle);
+    unsigned long slots = READ_ONCE(mh->slots);
+    unsigned int slot;
+
+    if (type == EV_SYN && READ_ONCE(mh->probing)) {
+        if (code == SYN_CONFIG) {
+            atomic_inc(&mh->syn_probes);
+            mh->probe_frame = true;
+            return;
+        }
+        if (code == SYN_REPORT && mh->probe_frame) {
+            mh->probe_frame = false;
+            return;
+        }
+    }
+    mh->probe_frame = false;
+
+    if (type == EV_SYN && code == SYN_CONFIG)
+        return;
+
+    for_each_set_bit(slot, &slots, DV_MAX_SLOTS)
+        rust_input_event(slot, type, code, value);
+}
+
+static int dv_monitor_connect(struct input_handler *handler, struct input_dev *dev,
+                              const struct input_device_id *id)
+{
+    struct dv_monitor_handle *mh;
+    int error;
+
+    mh = kzalloc(sizeof(*mh), GFP_KERNEL);
+    if (!mh)
+        return -ENOMEM;
+
+    mh->handle.dev = dev;
+    mh->handle.handler = handler;
+    mh->handle.name = "driver_verifier";
+    mh->slots = 0;
+    atomic_set(&mh->syn_probes, 0);
+    mutex_init(&mh->probe_lock);
+
+    error = input_register_handle(&mh->handle);
+    if (error)
+        goto err_free;
+
+    mutex_lock(&dv_monitor_lock);
+    list_add_tail(&mh->node, &dv_monitor_handles);
+    mutex_unlock(&dv_monitor_lock);
+
+    if (dev->name)
+        rust_input_device_changed(dev->name, strlen(dev->name), 1);
+    return 0;
+
+err_free:
+    kfree(mh);
+    return error;
+}
+
+static void dv_monitor_disconnect(struct input_handle *handle)
+{
+    struct dv_monitor_handle *mh = container_of(handle, struct dv_monitor_handle, handle);
+    bool open;
+
+    mutex_lock(&dv_monitor_lock);
+    list_del(&mh->node);
+    open = mh->slots != 0;
+    WRITE_ONCE(mh->slots, 0);
+    mutex_unlock(&dv_monitor_lock);
+
+    /* No new check can find the handle now, wait for a running one */
+    mutex_lock(&mh->probe_lock);
+    mutex_unlock(&mh->probe_lock);
+
+    if (handle->dev->name)
+        rust_input_device_changed(handle->dev->name, strlen(handle->dev->name), 0);
+
+    if (open)
+        input_close_device(handle);
+    input_unregister_handle(handle);
+    kfree(mh);
+}
+
+static const struct input_device_id dv_monitor_ids[] = {
+    { .driver_info = 1 },  /* Matches all devices */
+    { },
+};
+
+static struct input_handler dv_monitor_handler = {
+    .event      = dv_monitor_event,
+    .connect    = dv_monitor_connect,
+    .disconnect = dv_monitor_disconnect,
+    .name       = "driver_verifier",
+    .id_table   = dv_monitor_ids,
+};
+
+/**
+ * dv_device_has_node - Checks if an evdev node with the given name belongs to a device
+ *
+ * The evdev handler names its handle after the node it creates (e.g. "event5"),
+ * which lets a /dev/input path be mapped back to its input device.
+ */
+static bool dv_device_has_node(struct input_dev *dev, const char *node, size_t node_len)
+{
+    struct input_handle *h;
+    bool found = false;
+
+    mutex_lock(&dev->mutex);
+    list_for_each_entry(h, &dev->h_list, d_node) {
+        if (h->name && strlen(h->name) == node_len && !strncmp(h->name, node, node_len)) {
+            found = true;
+            break;
+        }
+    }
+    mutex_unlock(&dev->mutex);
+
+    return found;
+}
+
+/**
+ * kernel_monitor_attach - Binds an evdev node to a Rust monitor slot
+ *
+ * A device may be bound to several slots, and several devices may be bound
+ * to the same slot. The device is opened when its first slot is bound, which
+ * powers it up if nothing else has it open.
+ * @node: Node name without the /dev/input prefix (not NUL terminated)
+ * @node_len: Length of @node
+ * @slot: Monitor slot to bind
+ *
+ * Return: 0 on success, -ENODEV if no connected device owns the node, or the
+ * error of input_open_device()
+ */
+static int kernel_monitor_attach(const char *node, size_t node_len, u32 slot)
+{
+    struct dv_monitor_handle *mh;
+    int ret = -ENODEV;
+
+    if (slot >= DV_MAX_SLOTS)
+        return -EINVAL;
+
+    mutex_lock(&dv_monitor_lock);
+    list_for_each_entry(mh, &dv_monitor_handles, node) {
+        if (dv_device_has_node(mh->handle.dev, node, node_len)) {
+            ret = mh->slots ? 0 : input_open_device(&mh->handle);
+            if (!ret)
+                WRITE_ONCE(mh->slots, mh->slots | BIT(slot));
+            break;
+        }
+    }
+    mutex_unlock(&dv_monitor_lock);
+
+    return ret;
+}
+
+/**
+ * dv_node_name - Strips the directory part from a device node path
+ * @path: Device node path, e.g. "/dev/input/event5" (not NUL terminated)
+ * @path_len: Length of @path, updated to the length of the node name
+ *
+ * Return: Pointer to the node name within @path
+ */
+static const char *dv_node_name(const char *path, size_t *path_len)
+{
+    size_t i;
+
+    for (i = *path_len; i > 0; i--) {
+        if (path[i - 1] == '/') {
+            *path_len -= i;
+            return path + i;
+        }
+    }
+    return path;
+}
+
+/**
+ * kernel_get_abs_info - Reads the range of an absolute axis of a device
+ * @path: Device node path (not NUL terminated)
+ * @path_len: Length of @path
+ * @code: Absolute axis code
+ * @minimum: Receives the axis minimum
+ * @maximum: Receives the axis maximum
+ * @resolution: Receives the axis resolution
+ *
+ * Return: 0 on success, -ENODEV if the device is unknown, -EINVAL if the
+ * axis is not supported
+ */
+static int kernel_get_abs_info(const char *path, size_t path_len, u32 code,
+                               s32 *minimum, s32 *maximum, s32 *resolution)
+{
+    struct dv_monitor_handle *mh;
+    struct input_dev *dev;
+    const char *node;
+    int ret = -ENODEV;
+
+    node = dv_node_name(path, &path_len);
+
+    mutex_lock(&dv_monitor_lock);
+    list_for_each_entry(mh, &dv_monitor_handles, node) {
+        dev = mh->handle.dev;
+        if (!dv_device_has_node(dev, node, path_len))
+            continue;
+
+        ret = -EINVAL;
+        if (code <= ABS_MAX && dev->absinfo && test_bit(code, dev->absbit)) {
+            *minimum = dev->absinfo[code].minimum;
+            *maximum = dev->absinfo[code].maximum;
+            *resolution = dev->absinfo[code].resolution;
+            ret = 0;
+        }
+        break;
+    }
+    mutex_unlock(&dv_monitor_lock);
+
+    return ret;
+}
+
+/**
+ * kernel_get_device_capabilities - Reads the capability bitmaps of a device
+ * @path: Device node path (not NUL terminated)
+ * @path_len: Length of @path
+ * @abs_support: Receives the supported absolute axes (all ABS_* codes)
+ * @rel_support: Receives the first 32 relative axes
+ * @key_support: Receives the first 32 key codes
+ *
+ * Return: 0 on success, -ENODEV if the device is unknown
+ */
+static int kernel_get_device_capabilities(const char *path, size_t path_len, u64 *abs_support,
+                                          u32 *rel_support, u32 *key_support)
+{
+    struct dv_monitor_handle *mh;
+    struct input_dev *dev;
+    const char *node;
+    int ret = -ENODEV;
+
+    node = dv_node_name(path, &path_len);
+
+    mutex_lock(&dv_monitor_lock);
+    list_for_each_entry(mh, &dv_monitor_handles, node) {
+        dev = mh->handle.dev;
+        if (!dv_device_has_node(dev, node, path_len))
+            continue;
+
+        bitmap_to_arr64(abs_support, dev->absbit, ABS_CNT);
+        bitmap_to_arr32(rel_support, dev->relbit, 32);
+        bitmap_to_arr32(key_support, dev->keybit, 32);
+        ret = 0;
+        break;
+    }
+    mutex_unlock(&dv_monitor_lock);
+
+    return ret;
+}
+
+/* Steps of kernel_check_node() that passed, must match check_device_responsive() */
+#define DV_NODE_OPENED        BIT(0)
+#define DV_NODE_CAPABILITIES  BIT(1)
+#define DV_NODE_SYN_ROUNDTRIP BIT(2)
+/* The device was already open, so opening it did not reach the driver */
+#define DV_NODE_OPEN_SHARED   BIT(3)
+
+/**
+ * kernel_check_node - Checks that a device node responds
+ * @path: Device node path (not NUL terminated)
+ * @path_len: Length of @path
+ * @inject: Whether the EV_SYN roundtrip may inject its probe frame
+ *
+ * Runs through the monitor's input handle, stopping at the first failing step:
+ * opens the device, checks that it reports its event types including EV_SYN,
+ * and injects an empty SYN_CONFIG/SYN_REPORT frame, which the input core must
+ * deliver back to the monitor. The open only reaches the driver's open
+ * callback, which powers up most touchpads, when nothing else has the device
+ * open; otherwise DV_NODE_OPEN_SHARED is set as well. The monitor drops the
+ * probe frame, other handlers of the device see an empty frame. The roundtrip
+ * fails while another client grabs the device. Without @inject the roundtrip
+ * is skipped and nothing reaches the other handlers.
+ *
+ * dv_monitor_lock is only held to look the device up; the check itself runs
+ * under the handle's probe_lock, so devices are checked in parallel.
+ *
+ * Return: Bitmask of DV_NODE_* steps that passed, -ENODEV if no connected
+ * device owns the node
+ */
+static int kernel_check_node(const char *path, size_t path_len, bool inject)
+{
+    struct dv_monitor_handle *mh, *found = NULL;
+    struct input_dev *dev;
+    const char *node;
+    int probes;
+    int ret = 0;
+
+    node = dv_node_name(path, &path_len);
+
+    mutex_lock(&dv_monitor_lock);
+    list_for_each_entry(mh, &dv_monitor_handles, node) {
+        if (dv_device_has_node(mh->handle.dev, node, path_len)) {
+            found = mh;
+            mutex_lock(&found->probe_lock);
+            break;
+        }
+    }
+    mutex_unlock(&dv_monitor_lock);
+
+    if (!found)
+        return -ENODEV;
+
+    dev = found->handle.dev;
+    if (READ_ONCE(dev->users))
+        ret |= DV_NODE_OPEN_SHARED;
+    if (input_open_device(&found->handle))
+        goto out_unlock;
+    ret |= DV_NODE_OPENED;
+
+    if (test_bit(EV_SYN, dev->evbit) &&
+        find_next_bit(dev->evbit, EV_CNT, EV_SYN + 1) < EV_CNT) {
+        ret |= DV_NODE_CAPABILITIES;
+        if (!inject)
+            goto out_close;
+
+        /* Delivery is synchronous, the probe is counted before inject returns */
+        probes = atomic_read(&found->syn_probes);
+        WRITE_ONCE(found->probing, true);
+        input_inject_event(&found->handle, EV_SYN, SYN_CONFIG, 0);
+        input_inject_event(&found->handle, EV_SYN, SYN_REPORT, 0);
+        WRITE_ONCE(found->probing, false);
+        if (atomic_read(&found->syn_probes) != probes)
+            ret |= DV_NODE_SYN_ROUNDTRIP;
+    }
+
+out_close:
+    input_close_device(&found->handle);
+out_unlock:
+    mutex_unlock(&found->probe_lock);
+    return ret;
+}
+
+/**
+ * kernel_grab_node - Grabs or releases a device through the monitor's handle
+ * @path: Device node path (not NUL terminated)
+ * @path_len: Length of @path
+ * @grab: Non-zero to grab the device, zero to release it
+ *
+ * While grabbed, the input core delivers the device's events to the monitor
+ * only, like EVIOCGRAB does for an evdev client. Releasing a device the
+ * monitor doesn't grab is a no-op.
+ *
+ * Return: 0 on success, -ENODEV if no connected device owns the node,
+ * -EBUSY if another handle holds a grab
+ */
+static int kernel_grab_node(const char *path, size_t path_len, int grab)
+{
+    struct dv_monitor_handle *mh;
+    const char *node;
+    int ret = -ENODEV;
+
+    node = dv_node_name(path, &path_len);
+
+    mutex_lock(&dv_monitor_lock);
+    list_for_each_entry(mh, &dv_monitor_handles, node) {
+        if (!dv_device_has_node(mh->handle.dev, node, path_len))
+            continue;
+
+        if (grab) {
+            ret = input_grab_device(&mh->handle);
+        } else {
+            input_release_device(&mh->handle);
+            ret = 0;
+        }
+        break;
+    }
+    mutex_unlock(&dv_monitor_lock);
+
+    return ret;
+}
+
+/**
+ * kernel_get_grab - Reports which handler grabs a device
+ * @path: Device node path (not NUL terminated)
+ * @path_len: Length of @path
+ * @buffer: Receives the name of the grabbing handler (not NUL terminated)
+ * @buffer_size: Size of @buffer
+ * @holder_len: Receives the number of bytes written to @buffer
+ *
+ * Grabs by the monitor itself are not reported.
+ *
+ * Return: 1 if another handler grabs the device, 0 if it isn't grabbed,
+ * -ENODEV if no connected device owns the node
+ */
+static int kernel_get_grab(const char *path, size_t path_len, char *buffer,
+                           size_t buffer_size, size_t *holder_len)
+{
+    struct dv_monitor_handle *mh;
+    struct input_handle *grab;
+    const char *node;
+    int ret = -ENODEV;
+
+    node = dv_node_name(path, &path_len);
+    *holder_len = 0;
+
+    mutex_lock(&dv_monitor_lock);
+    list_for_each_entry(mh, &dv_monitor_handles, node) {
+        if (!dv_device_has_node(mh->handle.dev, node, path_len))
+            continue;
+
+        ret = 0;
+        rcu_read_lock();
+        grab = rcu_dereference(mh->handle.dev->grab);
+        if (grab && grab != &mh->handle) {
+            *holder_len = min(strlen(grab->handler->name), buffer_size);
+            memcpy(buffer, grab->handler->name, *holder_len);
+            ret = 1;
+        }
+        rcu_read_unlock();
+        break;
+    }
+    mutex_unlock(&dv_monitor_lock);
+
+    return ret;
+}
+
+/**
+ * dv_copy_name - Copies a possibly NULL name into a handler entry field
+ *
+ * Return: Number of bytes copied
+ */
+static u32 dv_copy_name(char *dst, const char *name)
+{
+    size_t len;
+
+    if (!name)
+        return 0;
+    len = min_t(size_t, strlen(name), DV_HANDLER_NAME_MAX);
+    memcpy(dst, name, len);
+    return len;
+}
+
+/**
+ * kernel_get_handlers - Lists the input handlers connected to a device
+ * @path: Device node path (not NUL terminated)
+ * @path_len: Length of @path
+ * @entries: Receives up to @capacity handlers
+ * @capacity: Number of entries in @entries
+ * @count: Receives the number of entries filled in
+ *
+ * The monitor's own handle is not listed. A handle is open while its handler
+ * has the device open, which for evdev means at least one client holds the
+ * node open.
+ *
+ * Return: 0 on success, -ENODEV if no connected device owns the node
+ */
+static int kernel_get_handlers(const char *path, size_t path_len,
+                               struct dv_handler_entry *entries, size_t capacity, size_t *count)
+{
+    struct dv_monitor_handle *mh;
+    struct input_handle *h;
+    struct input_dev *dev;
+    const char *node;
+    int ret = -ENODEV;
+
+    node = dv_node_name(path, &path_len);
+    *count = 0;
+
+    mutex_lock(&dv_monitor_lock);
+    list_for_each_entry(mh, &dv_monitor_handles, node) {
+        dev = mh->handle.dev;
+        if (!dv_device_has_node(dev, node, path_len))
+            continue;
+
+        mutex_lock(&dev->mutex);
+        list_for_each_entry(h, &dev->h_list, d_node) {
+            if (h == &mh->handle || *count >= capacity)
+                continue;
+
+            entries[*count].open = h->open > 0;
+            entries[*count].handler_len = dv_copy_name(entries[*count].handler, h->handler->name);
+            entries[*count].handle_len = dv_copy_name(entries[*count].handle, h->name);
+            (*count)++;
+        }
+        mutex_unlock(&dev->mutex);
+        ret = 0;
+        break;
+    }
+    mutex_unlock(&dv_monitor_lock);
+
+    return ret;
+}
+
+/**
+ * kernel_next_input_device - Iterates over the input devices that have an evdev node
+ * @cursor: Position of the walk, 0 to start, advanced past the device filled in
+ * @entry: Receives the next device
+ *
+ * Walks the devices the event monitor is connected to, which are all the
+ * devices registered with the input core, so the devices can be listed
+ * without sysfs. The capabilities are copied from the input_dev, they are
+ * fixed once the device is registered. Devices connected or removed between two calls may be
+ * skipped or listed twice.
+ *
+ * Return: 1 if @entry was filled in, 0 at the end of the list
+ */
+static int kernel_next_input_device(u32 *cursor, struct dv_input_device *entry)
+{
+    struct dv_monitor_handle *mh;
+    struct input_handle *h;
+    struct input_dev *dev;
+    size_t node_len;
+    u32 index = 0;
+    int ret = 0;
+
+    memset(entry, 0, sizeof(*entry));
+
+    mutex_lock(&dv_monitor_lock);
+    list_for_each_entry(mh, &dv_monitor_handles, node) {
+        if (index++ < *cursor)
+            continue;
+
+        dev = mh->handle.dev;
+        node_len = 0;
+        mutex_lock(&dev->mutex);
+        list_for_each_entry(h, &dev->h_list, d_node) {
+            if (h->name && !strncmp(h->name, "event", 5)) {
+                node_len = min_t(size_t, strlen(h->name), DV_INPUT_NODE_MAX);
+                memcpy(entry->node, h->name, node_len);
+                break;
+            }
+        }
+        mutex_unlock(&dev->mutex);
+        if (!node_len)
+            continue;
+
+        entry->node_len = node_len;
+        if (dev->name) {
+            entry->name_len = min_t(size_t, strlen(dev->name), DV_INPUT_NAME_MAX);
+            memcpy(entry->name, dev->name, entry->name_len);
+        }
+        entry->bustype = dev->id.bustype;
+        entry->vendor = dev->id.vendor;
+        entry->product = dev->id.product;
+        entry->version = dev->id.version;
+        bitmap_copy(entry->evbit, dev->evbit, EV_CNT);
+        bitmap_copy(entry->keybit, dev->keybit, KEY_CNT);
+        bitmap_copy(entry->relbit, dev->relbit, REL_CNT);
+        bitmap_copy(entry->absbit, dev->absbit, ABS_CNT);
+        bitmap_copy(entry->propbit, dev->propbit, INPUT_PROP_CNT);
+        *cursor = index;
+        ret = 1;
+        break;
+    }
+    mutex_unlock(&dv_monitor_lock);
+
+    return ret;
+}
+
+/**
+ * kernel_monitor_detach - Releases a Rust monitor slot
+ * @slot: Monitor slot to release from all devices bound to it
+ *
+ * Devices left without a bound slot are closed again.
+ */
+static void kernel_monitor_detach(u32 slot)
+{
+    struct dv_monitor_handle *mh;
+
+    if (slot >= DV_MAX_SLOTS)
+        return;
+
+    mutex_lock(&dv_monitor_lock);
+    list_for_each_entry(mh, &dv_monitor_handles, node) {
+        if (!(mh->slots & BIT(slot)))
+            continue;
+        WRITE_ONCE(mh->slots, mh->slots & ~BIT(slot));
+        if (!mh->slots)
+            input_close_device(&mh->handle);
+    }
+    mutex_unlock(&dv_monitor_lock);
+}
+
+/**
+ * kernel_print - Writes a message from the Rust side to the kernel log
+ * @msg: Message, usually ending in a newline (not NUL terminated)
+ * @len: Length of @msg
+ *
+ * Messages carry the "Driver Verifier:" prefix of the C side, which the
+ * kernel log collector relies on to skip the verifier's own lines.
+ */
+static void kernel_print(const char *msg, size_t len)
+{
+    printk(KERN_INFO "Driver Verifier: %.*s", (int)len, msg);
+}
+
+/**
+ * kernel_get_time_ms - Returns monotonic time in milliseconds
+ */
+static u64 kernel_get_time_ms(void)
+{
+    return ktime_to_ms(ktime_get());
+}
+
+/**
+ * kernel_get_time_us - Returns monotonic time in microseconds
  *
- * These functions are implemented in the Rust part of the module and
- * compiled into a static library that gets linked with this C code.
+ * Safe to call from the input event path.
  */
-extern int rust_init(void);
-extern void rust_exit(void);
-extern int rust_scan_devices(void);
-extern int rust_verify_touchpad(void);
+static u64 kernel_get_time_us(void)
+{
+    return ktime_to_us(ktime_get());
+}
+
+/**
+ * kernel_msleep - Sleeps for the given number of milliseconds
+ */
+static void kernel_msleep(u32 ms)
+{
+    msleep(ms);
+}
+
+/**
+ * kernel_request_module - Loads a kernel module through modprobe
+ * @name: Module name (not NUL terminated)
+ * @name_len: Length of @name
+ *
+ * Return: 0 on success, negative error code or modprobe exit status otherwise
+ */
+static int kernel_request_module(const char *name, size_t name_len)
+{
+    char *kname;
+    int ret;
+
+    kname = kmemdup_nul(name, name_len, GFP_KERNEL);
+    if (!kname)
+        return -ENOMEM;
+
+    ret = request_module("%s", kname);
+    kfree(kname);
+
+    return ret > 0 ? -ENOENT : ret;
+}
+
+/**
+ * kernel_kmsg_line - Reads the kernel log line following a sequence number
+ * @seq: Sequence number to start at; updated to the one after the line read
+ * @buffer: Receives the line in syslog format, "<level>[time] text" (not NUL terminated)
+ * @buffer_size: Size of @buffer
+ * @line_len: Receives the length of the line, truncated to fit @buffer
+ *
+ * Lines that were already overwritten in the ring buffer are skipped. Every
+ * call rewinds a fresh iterator, so no state is kept between calls.
+ *
+ * Return: 1 if a line was read, 0 at the end of the log
+ */
+static int kernel_kmsg_line(u64 *seq, char *buffer, size_t buffer_size, size_t *line_len)
+{
+    struct kmsg_dump_iter iter;
+
+    kmsg_dump_rewind(&iter);
+    if (*seq > iter.cur_seq)
+        iter.cur_seq = *seq;
+
+    if (!kmsg_dump_get_line(&iter, true, buffer, buffer_size, line_len))
+        return 0;
+
+    *seq = iter.cur_seq;
+    return 1;
+}
+
+/* DMI vendor or product fragments of common hypervisors */
+static const char * const dv_hypervisor_dmi[] = {
+    "QEMU", "VMware", "innotek GmbH", "VirtualBox", "Xen", "Parallels",
+    "Bochs", "Virtual Machine", "KVM",
+};
+
+/**
+ * kernel_get_hypervisor - Reports whether the kernel runs under a hypervisor
+ * @buffer: Receives the system vendor or product name (not NUL terminated)
+ * @buffer_size: Size of @buffer
+ * @name_len: Receives the length of the name
+ *
+ * The CPU hypervisor flag is checked where available; otherwise the DMI
+ * vendor and product strings are matched against well-known hypervisors.
+ *
+ * Return: 1 under a hypervisor, 0 on bare metal
+ */
+static int kernel_get_hypervisor(char *buffer, size_t buffer_size, size_t *name_len)
+{
+    const char *vendor = dmi_get_system_info(DMI_SYS_VENDOR);
+    const char *product = dmi_get_system_info(DMI_PRODUCT_NAME);
+    const char *name;
+    bool virtual = false;
+    int i;
+
+#ifdef CONFIG_X86
+    virtual = boot_cpu_has(X86_FEATURE_HYPERVISOR);
+#endif
+    for (i = 0; !virtual && i < ARRAY_SIZE(dv_hypervisor_dmi); i++)
+        virtual = (vendor && strstr(vendor, dv_hypervisor_dmi[i])) ||
+                  (product && strstr(product, dv_hypervisor_dmi[i]));
+
+    if (!virtual)
+        return 0;
+
+    name = vendor ? vendor : product ? product : "unknown hypervisor";
+    *name_len = min(strlen(name), buffer_size);
+    memcpy(buffer, name, *name_len);
+    return 1;
+}
+
+/**
+ * dv_fw_gpio_state - Checks a GPIO described by a firmware node
+ * @fwnode: Firmware node describing the device
+ * @con_id: GPIO function, e.g. "reset" for a "reset-gpios" property
+ *
+ * The GPIO is requested as-is without changing its direction or value and
+ * released right away. The request fails with -EBUSY while a driver holds it.
+ *
+ * Return: DV_RES_* state of the GPIO
+ */
+static u32 dv_fw_gpio_state(struct fwnode_handle *fwnode, const char *con_id)
+{
+    struct gpio_desc *desc;
+
+    desc = fwnode_gpiod_get_index(fwnode, con_id, 0, GPIOD_ASIS, "driver_verifier");
+    if (!IS_ERR(desc)) {
+        gpiod_put(desc);
+        return DV_RES_UNCLAIMED;
+    }
+
+    switch (PTR_ERR(desc)) {
+    case -EBUSY:
+        return DV_RES_CLAIMED;
+    case -ENOENT:
+    case -ENOSYS:
+        return DV_RES_ABSENT;
+    default:
+        return DV_RES_MISSING;
+    }
+}
+
+/**
+ * kernel_get_fw_resources - Reads the firmware description of a device
+ * @path: Device node path (not NUL terminated)
+ * @path_len: Length of @path
+ * @res: Receives the description
+ *
+ * The description is read from the closest parent of the input device with
+ * a DT or ACPI firmware node, e.g. the i2c client of an i2c-hid touchpad.
+ * The interrupt is claimed once a handler is requested for it.
+ *
+ * Return: 0 on success, -ENODEV if no connected device owns the node
+ */
+static int kernel_get_fw_resources(const char *path, size_t path_len, struct dv_fw_resources *res)
+{
+    struct dv_monitor_handle *mh;
+    struct fwnode_handle *fwnode = NULL;
+    struct device *parent = NULL;
+    const char *node;
+    const char *id = NULL;
+    int irq;
+    int ret = -ENODEV;
+
+    node = dv_node_name(path, &path_len);
+    memset(res, 0, sizeof(*res));
+
+    mutex_lock(&dv_monitor_lock);
+    list_for_each_entry(mh, &dv_monitor_handles, node) {
+        if (!dv_device_has_node(mh->handle.dev, node, path_len))
+            continue;
+
+        for (parent = mh->handle.dev->dev.parent; parent; parent = parent->parent) {
+            fwnode = dev_fwnode(parent);
+            if (fwnode)
+                break;
+        }
+        if (parent)
+            get_device(parent);
+        ret = 0;
+        break;
+    }
+    mutex_unlock(&dv_monitor_lock);
+
+    if (ret || !fwnode)
+        goto out;
+
+    if (is_of_node(fwnode)) {
+        res->kind = DV_FW_DT;
+        fwnode_property_read_string(fwnode, "compatible", &id);
+    } else if (is_acpi_device_node(fwnode)) {
+        res->kind = DV_FW_ACPI;
+        id = acpi_device_hid(to_acpi_device_node(fwnode));
+    }
+    if (id) {
+        res->id_len = min(strlen(id), sizeof(res->id));
+        memcpy(res->id, id, res->id_len);
+    }
+
+    irq = fwnode_irq_get(fwnode, 0);
+    if (irq > 0) {
+        res->irq = irq;
+        res->irq_state = irq_has_action(irq) ? DV_RES_CLAIMED : DV_RES_UNCLAIMED;
+    } else {
+        res->irq_state = irq == -EPROBE_DEFER ? DV_RES_MISSING : DV_RES_ABSENT;
+    }
+
+    res->irq_gpio = dv_fw_gpio_state(fwnode, "irq");
+    if (res->irq_gpio == DV_RES_ABSENT)
+        res->irq_gpio = dv_fw_gpio_state(fwnode, "interrupt");
+    res->reset_gpio = dv_fw_gpio_state(fwnode, "reset");
+
+out:
+    if (parent)
+        put_device(parent);
+    return ret;
+}
+
+/**
+ * kernel_reset_fw_device - Pulses the reset GPIO of a device and probes it again
+ * @path: Device node path (not NUL terminated)
+ * @path_len: Length of @path
+ * @assert_ms: How long the reset line is held asserted
+ *
+ * Works on the closest parent of the input device with a firmware node, like
+ * kernel_get_fw_resources(). Its driver is unbound first so the reset GPIO is
+ * free to request; the line is requested asserted, released after @assert_ms
+ * and the device probed again. The input device is unregistered meanwhile.
+ *
+ * Return: 0 on success, -ENODEV if no connected device owns the node or it
+ * has no firmware node, -ENOENT if no reset GPIO is described, other negative
+ * error codes if the GPIO can't be requested
+ */
+static int kernel_reset_fw_device(const char *path, size_t path_len, u32 assert_ms)
+{
+    struct dv_monitor_handle *mh;
+    struct fwnode_handle *fwnode = NULL;
+    struct device *parent = NULL;
+    struct gpio_desc *desc;
+    const char *node;
+    int ret = -ENODEV;
+
+    node = dv_node_name(path, &path_len);
+
+    mutex_lock(&dv_monitor_lock);
+    list_for_each_entry(mh, &dv_monitor_handles, node) {
+        if (!dv_device_has_node(mh->handle.dev, node, path_len))
+            continue;
+
+        for (parent = mh->handle.dev->dev.parent; parent; parent = parent->parent) {
+            fwnode = dev_fwnode(parent);
+            if (fwnode)
+                break;
+        }
+        if (parent)
+            get_device(parent);
+        break;
+    }
+    mutex_unlock(&dv_monitor_lock);
+
+    if (!parent)
+        return -ENODEV;
+
+    printk(KERN_INFO "Driver Verifier: Resetting %s through its reset GPIO\n", dev_name(parent));
+    device_release_driver(parent);
+
+    /* GPIOD_OUT_HIGH asserts the line, active-low lines are inverted by gpiolib */
+    desc = fwnode_gpiod_get_index(fwnode, "reset", 0, GPIOD_OUT_HIGH, "driver_verifier");
+    if (IS_ERR(desc)) {
+        ret = PTR_ERR(desc);
+    } else {
+        msleep(assert_ms);
+        gpiod_set_value_cansleep(desc, 0);
+        gpiod_put(desc);
+        ret = 0;
+    }
+
+    /* Probe again even if the reset failed, so the device isn't left unbound */
+    if (device_attach(parent) < 0)
+        printk(KERN_WARNING "Driver Verifier: Failed to probe %s again\n", dev_name(parent));
+
+    put_device(parent);
+    return ret;
+}
+
+#ifdef CONFIG_COMPAT
+/* Layout of struct input_event_compat from drivers/input/input-compat.h, which is private */
+struct dv_input_event_compat {
+    compat_ulong_t sec;
+    compat_ulong_t usec;
+    __u16 type;
+    __u16 code;
+    __s32 value;
+};
+#endif
+
+/**
+ * kernel_get_evdev_layout - Reports the evdev event layout of the running kernel
+ * @layout: Receives the layout
+ */
+static void kernel_get_evdev_layout(struct dv_evdev_layout *layout)
+{
+    memset(layout, 0, sizeof(*layout));
+    layout->event_size = sizeof(struct input_event);
+    layout->time_size = sizeof_field(struct input_event, input_event_sec);
+    layout->type_offset = offsetof(struct input_event, type);
+    layout->code_offset = offsetof(struct input_event, code);
+    layout->value_offset = offsetof(struct input_event, value);
+    layout->big_endian = IS_ENABLED(CONFIG_CPU_BIG_ENDIAN);
+    layout->version = EV_VERSION;
+#ifdef CONFIG_COMPAT
+    layout->compat_event_size = sizeof(struct dv_input_event_compat);
+#endif
+    layout->x32 = IS_ENABLED(CONFIG_X86_X32_ABI);
+}
+
+/* Virtual touchpad the event recorder replays traces through */
+static struct input_dev *dv_replay_dev;
+
+/**
+ * kernel_replay_create - Creates a virtual input device for replaying events
+ * @path: Node path of the device whose capabilities are cloned (not NUL terminated)
+ * @path_len: Length of @path
+ *
+ * Return: 0 on success, -EBUSY if a replay device exists, -ENODEV if the
+ * device is unknown, other negative error codes on failure
+ */
+static int kernel_replay_create(const char *path, size_t path_len)
+{
+    struct dv_monitor_handle *mh;
+    struct input_dev *src, *dev;
+    const char *node;
+    int error = -ENODEV;
+
+    if (dv_replay_dev)
+        return -EBUSY;
+
+    dev = input_allocate_device();
+    if (!dev)
+        return -ENOMEM;
+
+    dev->name = "Driver Verifier Replay";
+    dev->phys = "driver_verifier/replay";
+    dev->id.bustype = BUS_VIRTUAL;
+
+    node = dv_node_name(path, &path_len);
+
+    mutex_lock(&dv_monitor_lock);
+    list_for_each_entry(mh, &dv_monitor_handles, node) {
+        src = mh->handle.dev;
+        if (!dv_device_has_node(src, node, path_len))
+            continue;
+
+        bitmap_copy(dev->evbit, src->evbit, EV_CNT);
+        bitmap_copy(dev->keybit, src->keybit, KEY_CNT);
+        bitmap_copy(dev->relbit, src->relbit, REL_CNT);
+        bitmap_copy(dev->absbit, src->absbit, ABS_CNT);
+        bitmap_copy(dev->mscbit, src->mscbit, MSC_CNT);
+        bitmap_copy(dev->propbit, src->propbit, INPUT_PROP_CNT);
+
+        error = 0;
+        if (src->absinfo) {
+            input_alloc_absinfo(dev);
+            if (dev->absinfo)
+                memcpy(dev->absinfo, src->absinfo, ABS_CNT * sizeof(*src->absinfo));
+            else
+                error = -ENOMEM;
+        }
+        /* Replayed traces already contain any pointer emulation, so no flags */
+        if (!error && src->mt)
+            error = input_mt_init_slots(dev, src->mt->num_slots, 0);
+        break;
+    }
+    mutex_unlock(&dv_monitor_lock);
+
+    if (!error)
+        error = input_register_device(dev);
+    if (error) {
+        input_free_device(dev);
+        return error;
+    }
+
+    dv_replay_dev = dev;
+    return 0;
+}
+
+/**
+ * kernel_replay_event - Injects an event through the replay device
+ */
+static void kernel_replay_event(u32 type, u32 code, s32 value)
+{
+    if (dv_replay_dev)
+        input_event(dv_replay_dev, type, code, value);
+}
+
+/**
+ * kernel_replay_destroy - Removes the replay device
+ */
+static void kernel_replay_destroy(void)
+{
+    if (dv_replay_dev) {
+        input_unregister_device(dv_replay_dev);
+        dv_replay_dev = NULL;
+    }
+}
+
+#define DV_SELFTEST_START 1
+#define DV_SELFTEST_SKIP  2
+#define DV_SELFTEST_ABORT 3
+
+/**
+ * dv_selftest_read - Reads the self test prompt and per-gesture results
+ *
+ * Each read advances the self test state machine, so polling the file
+ * (e.g. with watch cat) drives the test forward.
+ */
+static ssize_t dv_selftest_read(struct file *file, char __user *ubuf,
+                                size_t count, loff_t *ppos)
+{
+    char *buf;
+    ssize_t len;
+
+    buf = kmalloc(PAGE_SIZE, GFP_KERNEL);
+    if (!buf)
+        return -ENOMEM;
+
+    mutex_lock(&dv_verifier_lock);
+    len = rust_selftest_read(buf, PAGE_SIZE);
+    mutex_unlock(&dv_verifier_lock);
+
+    if (len >= 0)
+        len = simple_read_from_buffer(ubuf, count, ppos, buf, len);
+    else
+        len = -ENODEV;
+
+    kfree(buf);
+    return len;
+}
+
+/**
+ * dv_selftest_write - Controls the self test
+ *
+ * Accepts "start", "skip" and "abort".
+ */
+static ssize_t dv_selftest_write(struct file *file, const char __user *ubuf,
+                                 size_t count, loff_t *ppos)
+{
+    char cmd[16];
+    u32 command;
+    int ret;
+
+    if (count == 0 || count >= sizeof(cmd))
+        return -EINVAL;
+    if (copy_from_user(cmd, ubuf, count))
+        return -EFAULT;
+    cmd[count] = '\0';
+
+    if (sysfs_streq(cmd, "start"))
+        command = DV_SELFTEST_START;
+    else if (sysfs_streq(cmd, "skip"))
+        command = DV_SELFTEST_SKIP;
+    else if (sysfs_streq(cmd, "abort"))
+        command = DV_SELFTEST_ABORT;
+    else
+        return -EINVAL;
+
+    mutex_lock(&dv_verifier_lock);
+    ret = rust_selftest_command(command);
+    mutex_unlock(&dv_verifier_lock);
+
+    return ret < 0 ? -ENODEV : count;
+}
+
+static const struct file_operations dv_selftest_fops = {
+    .owner = THIS_MODULE,
+    .read  = dv_selftest_read,
+    .write = dv_selftest_write,
+    .llseek = default_llseek,
+};
+
+/**
+ * dv_touchpads_read - Lists the touchpads with their verification status
+ */
+static ssize_t dv_touchpads_read(struct file *file, char __user *ubuf,
+                                 size_t count, loff_t *ppos)
+{
+    char *buf;
+    ssize_t len;
+
+    buf = kmalloc(PAGE_SIZE, GFP_KERNEL);
+    if (!buf)
+        return -ENOMEM;
+
+    mutex_lock(&dv_verifier_lock);
+    len = rust_touchpads_read(buf, PAGE_SIZE);
+    mutex_unlock(&dv_verifier_lock);
+
+    if (len >= 0)
+        len = simple_read_from_buffer(ubuf, count, ppos, buf, len);
+    else
+        len = -ENODEV;
+
+    kfree(buf);
+    return len;
+}
+
+static const struct file_operations dv_touchpads_fops = {
+    .owner = THIS_MODULE,
+    .read  = dv_touchpads_read,
+    .llseek = default_llseek,
+};
+
+/* Largest rendered incident log, enough for its 64 entries */
+#define DV_INCIDENTS_SIZE (4 * PAGE_SIZE)
+
+/**
+ * dv_incidents_read - Reads the incident log, oldest incident first
+ */
+static ssize_t dv_incidents_read(struct file *file, char __user *ubuf,
+                                 size_t count, loff_t *ppos)
+{
+    char *buf;
+    ssize_t len;
+
+    buf = kvmalloc(DV_INCIDENTS_SIZE, GFP_KERNEL);
+    if (!buf)
+        return -ENOMEM;
+
+    mutex_lock(&dv_verifier_lock);
+    len = rust_incidents_read(buf, DV_INCIDENTS_SIZE);
+    mutex_unlock(&dv_verifier_lock);
+
+    if (len >= 0)
+        len = simple_read_from_buffer(ubuf, count, ppos, buf, len);
+    else
+        len = -ENODEV;
+
+    kvfree(buf);
+    return len;
+}
+
+static const struct file_operations dv_incidents_fops = {
+    .owner = THIS_MODULE,
+    .read  = dv_incidents_read,
+    .llseek = default_llseek,
+};
+
+/* Largest rendered metrics, enough for the counters and a few touchpads */
+#define DV_METRICS_SIZE (4 * PAGE_SIZE)
+
+/**
+ * dv_metrics_read - Reads the statistics and touchpad health in the Prometheus text format
+ */
+static ssize_t dv_metrics_read(struct file *file, char __user *ubuf,
+                               size_t count, loff_t *ppos)
+{
+    char *buf;
+    ssize_t len;
+
+    buf = kvmalloc(DV_METRICS_SIZE, GFP_KERNEL);
+    if (!buf)
+        return -ENOMEM;
+
+    mutex_lock(&dv_verifier_lock);
+    len = rust_metrics_read(buf, DV_METRICS_SIZE);
+    mutex_unlock(&dv_verifier_lock);
+
+    if (len >= 0)
+        len = simple_read_from_buffer(ubuf, count, ppos, buf, len);
+    else
+        len = -ENODEV;
+
+    kvfree(buf);
+    return len;
+}
+
+static const struct file_operations dv_metrics_fops = {
+    .owner = THIS_MODULE,
+    .read  = dv_metrics_read,
+    .llseek = default_llseek,
+};
+
+/* Largest serialized snapshot, enough for a few hundred devices */
+#define DV_SNAPSHOT_SIZE (4 * PAGE_SIZE)
+
+/**
+ * dv_snapshot_read - Reads a snapshot of the devices and verification results
+ *
+ * The snapshot is meant to be saved before a driver or kernel update and
+ * written to the compare file afterwards.
+ */
+static ssize_t dv_snapshot_read(struct file *file, char __user *ubuf,
+                                size_t count, loff_t *ppos)
+{
+    char *buf;
+    ssize_t len;
+
+    buf = kvmalloc(DV_SNAPSHOT_SIZE, GFP_KERNEL);
+    if (!buf)
+        return -ENOMEM;
+
+    mutex_lock(&dv_verifier_lock);
+    len = rust_snapshot(buf, DV_SNAPSHOT_SIZE);
+    mutex_unlock(&dv_verifier_lock);
+
+    if (len >= 0)
+        len = simple_read_from_buffer(ubuf, count, ppos, buf, len);
+    else
+        len = -ENOSPC;
+
+    kvfree(buf);
+    return len;
+}
+
+static const struct file_operations dv_snapshot_fops = {
+    .owner = THIS_MODULE,
+    .read  = dv_snapshot_read,
+    .llseek = default_llseek,
+};
+
+/**
+ * dv_compare_read - Reads the differences found by the last comparison
+ */
+static ssize_t dv_compare_read(struct file *file, char __user *ubuf,
+                               size_t count, loff_t *ppos)
+{
+    char *buf;
+    ssize_t len;
+
+    buf = kmalloc(PAGE_SIZE, GFP_KERNEL);
+    if (!buf)
+        return -ENOMEM;
+
+    mutex_lock(&dv_verifier_lock);
+    len = rust_comparison_read(buf, PAGE_SIZE);
+    mutex_unlock(&dv_verifier_lock);
+
+    if (len >= 0)
+        len = simple_read_from_buffer(ubuf, count, ppos, buf, len);
+    else
+        len = -ENODEV;
+
+    kfree(buf);
+    return len;
+}
+
+/**
+ * dv_compare_write - Compares a saved snapshot with the current state
+ *
+ * The whole snapshot must be written in a single write.
+ */
+static ssize_t dv_compare_write(struct file *file, const char __user *ubuf,
+                                size_t count, loff_t *ppos)
+{
+    char *blob;
+    int ret;
+
+    if (*ppos != 0 || count == 0 || count > DV_SNAPSHOT_SIZE)
+        return -EINVAL;
+
+    blob = vmemdup_user(ubuf, count);
+    if (IS_ERR(blob))
+        return PTR_ERR(blob);
+
+    mutex_lock(&dv_verifier_lock);
+    ret = rust_compare_snapshot(blob, count);
+    mutex_unlock(&dv_verifier_lock);
+
+    kvfree(blob);
+    return ret < 0 ? -EINVAL : count;
+}
+
+static const struct file_operations dv_compare_fops = {
+    .owner = THIS_MODULE,
+    .read  = dv_compare_read,
+    .write = dv_compare_write,
+    .llseek = default_llseek,
+};
+
+/*
+ * Kernel operations the Rust side calls. Optional operations relying on
+ * kernel features that are configured out are left NULL, so Rust disables
+ * what needs them.
+ */
+static const struct dv_kernel_ops dv_kernel_ops = {
+    .size                    = sizeof(struct dv_kernel_ops),
+    .print                   = kernel_print,
+    .read_file               = kernel_read_sysfs_file,
+    .get_time_ms             = kernel_get_time_ms,
+    .get_time_us             = kernel_get_time_us,
+    .msleep                  = kernel_msleep,
+    .check_node              = kernel_check_node,
+    .monitor_attach          = kernel_monitor_attach,
+    .monitor_detach          = kernel_monitor_detach,
+    .get_device_capabilities = kernel_get_device_capabilities,
+    .get_abs_info            = kernel_get_abs_info,
+    .read_directory          = kernel_read_directory,
+    .get_handlers            = kernel_get_handlers,
+    .get_grab                = kernel_get_grab,
+    .grab_node               = kernel_grab_node,
+    .kmsg_line               = IS_ENABLED(CONFIG_PRINTK) ? kernel_kmsg_line : NULL,
+    .replay_create           = kernel_replay_create,
+    .replay_event            = kernel_replay_event,
+    .replay_destroy          = kernel_replay_destroy,
+    .trace_scan_start        = IS_ENABLED(CONFIG_TRACEPOINTS) ? kernel_trace_scan_start : NULL,
+    .trace_scan_end          = IS_ENABLED(CONFIG_TRACEPOINTS) ? kernel_trace_scan_end : NULL,
+    .trace_phase             = IS_ENABLED(CONFIG_TRACEPOINTS) ? kernel_trace_phase : NULL,
+    .trace_classify          = IS_ENABLED(CONFIG_TRACEPOINTS) ? kernel_trace_classify : NULL,
+    .notify                  = kernel_notify,
+    .request_module          = IS_ENABLED(CONFIG_MODULES) ? kernel_request_module : NULL,
+    .resolve_path            = kernel_resolve_path,
+    .write_file              = kernel_write_file,
+    .get_hypervisor          = IS_ENABLED(CONFIG_DMI) || IS_ENABLED(CONFIG_X86) ? kernel_get_hypervisor : NULL,
+    .get_fw_resources        = kernel_get_fw_resources,
+    .reset_fw_device         = kernel_reset_fw_device,
+    .get_evdev_layout        = kernel_get_evdev_layout,
+    .next_input_device       = kernel_next_input_device,
+    .notify_syn_dropped      = kernel_notify_syn_dropped,
+};
+
+/**
+ * dv_verify_status_name - Returns the name of a verification status
+ * @status: DV_VERIFY_* status of rust_verify_touchpad() or rust_touchpad_status()
+ *
+ * Return: The name touchpad_status reads as; "error" for a verification that
+ * could not run or a touchpad that was not verified
+ */
+const char *dv_verify_status_name(int status)
+{
+    switch (status) {
+    case DV_VERIFY_WORKING:
+        return "working";
+    case DV_VERIFY_NOT_WORKING:
+        return "not working";
+    case DV_VERIFY_DEVICE_GONE:
+        return "device gone";
+    case DV_VERIFY_NOT_EXPECTED:
+        return "not expected";
+    case DV_VERIFY_ENVIRONMENT_UNSUPPORTED:
+        return "environment unsupported";
+    default:
+        return "error";
+    }
+}
+
+/**
+ * dv_verify_now - Scans and verifies during load and logs the result
+ */
+static void __init dv_verify_now(void)
+{
+    rust_scan_devices();
+    
+    int touchpad_status = rust_verify_touchpad(1);
+    printk(KERN_INFO "Driver Verifier: Touchpad status: %s\n", 
+           touchpad_status == DV_VERIFY_WORKING ? "working" :
+           touchpad_status == DV_VERIFY_DEVICE_GONE ? "device gone" :
+           touchpad_status == DV_VERIFY_NOT_EXPECTED ? "not expected (virtual environment)" :
+           touchpad_status == DV_VERIFY_ENVIRONMENT_UNSUPPORTED ? "environment unsupported (no /sys/class/input)" :
+           "not working or not found");
+    
+    int touchpads = rust_touchpad_count();
+    for (int i = 0; touchpads > 1 && i < touchpads; i++)
+        printk(KERN_INFO "Driver Verifier: Touchpad %d: %s\n", i,
+               dv_verify_status_name(rust_touchpad_status(i)));
+}
 
 /**
  * driver_verifier_init - Module initialization function
  *
  * Called when the module is loaded into the kernel. This function
- * initializes the Rust component, triggers device scanning, and
- * verifies touchpad functionality.
+ * initializes the Rust component, registers the event monitor input
+ * handler, triggers device scanning, and verifies touchpad functionality,
+ * or defers the scan and verification to a work item in boot mode.
  *
  * Return: 0 on success, negative error code on failure
  */
@@ -40,17 +1836,94 @@ static int __init driver_verifier_init(void)
 {
     printk(KERN_INFO "Driver Verifier: initializing\n");
     
-    int result = rust_init();
+    int result = rust_init(&dv_kernel_ops);
     if (result != 0) {
         printk(KERN_ERR "Driver Verifier: Failed to initialize Rust component\n");
         return -EINVAL;
     }
     
-    rust_scan_devices();
+    result = dv_netlink_init();
+    if (result) {
+        printk(KERN_ERR "Driver Verifier: Failed to register netlink family\n");
+        rust_exit();
+        return result;
+    }
     
-    int touchpad_status = rust_verify_touchpad();
-    printk(KERN_INFO "Driver Verifier: Touchpad status: %s\n", 
-           touchpad_status ? "working" : "not working or not found");
+    result = input_register_handler(&dv_monitor_handler);
+    if (result) {
+        printk(KERN_ERR "Driver Verifier: Failed to register input handler\n");
+        dv_netlink_exit();
+        rust_exit();
+        return result;
+    }
+    
+    rust_configure_event_check(event_window_ms, interactive);
+    if (profile) {
+        int profile_id = sysfs_match_string(dv_profile_names, profile);
+        if (profile_id < 0)
+            printk(KERN_WARNING "Driver Verifier: Unknown profile %s, using default\n", profile);
+        else
+            rust_configure_profile(profile_id);
+    }
+    if (safety) {
+        int policy = sysfs_match_string(dv_safety_names, safety);
+        /* Fall back to the strictest policy, a typo must not enable repairs */
+        if (policy < 0) {
+            printk(KERN_WARNING "Driver Verifier: Unknown safety policy %s, using read-only\n", safety);
+            policy = DV_SAFETY_READ_ONLY;
+        }
+        rust_configure_safety(policy);
+    }
+    if (enumeration) {
+        int backend = sysfs_match_string(dv_enumeration_names, enumeration);
+        if (backend < 0)
+            printk(KERN_WARNING "Driver Verifier: Unknown enumeration %s, using sysfs\n", enumeration);
+        else if (rust_configure_enumeration(backend))
+            printk(KERN_WARNING "Driver Verifier: Enumeration %s unsupported, using sysfs\n", enumeration);
+    }
+    rust_configure_watchdog(watchdog, watchdog_freeze_ms, watchdog_reverify);
+    rust_configure_recovery(recover);
+    rust_configure_reset_gpio(reset_gpio);
+    rust_configure_module_loading(load_modules);
+    rust_configure_cache(cache_ttl_ms);
+    rust_configure_exclusive_grab(exclusive_grab);
+    if (rust_configure_pool(scan_pool_bytes, scan_pool_entries))
+        printk(KERN_WARNING "Driver Verifier: Failed to allocate scan pool, using default size\n");
+    rust_configure_device_filter(ignore_devices, strlen(ignore_devices),
+                                 only_devices, strlen(only_devices));
+    if (rust_configure_seat(seat, strlen(seat)))
+        printk(KERN_WARNING "Driver Verifier: Invalid seat %s, considering every seat\n", seat);
+    
+    if (boot_verify)
+        rust_configure_boot_verify(boot_retry_ms);
+    else if (lazy_scan)
+        rust_configure_lazy_scan();
+    else
+        dv_verify_now();
+    
+    dv_debugfs_dir = debugfs_create_dir("driver_verifier", NULL);
+    debugfs_create_file("selftest", 0600, dv_debugfs_dir, NULL, &dv_selftest_fops);
+    debugfs_create_file("touchpads", 0400, dv_debugfs_dir, NULL, &dv_touchpads_fops);
+    debugfs_create_file("snapshot", 0400, dv_debugfs_dir, NULL, &dv_snapshot_fops);
+    debugfs_create_file("compare", 0600, dv_debugfs_dir, NULL, &dv_compare_fops);
+    debugfs_create_file("incidents", 0400, dv_debugfs_dir, NULL, &dv_incidents_fops);
+    debugfs_create_file("metrics", 0444, dv_debugfs_dir, NULL, &dv_metrics_fops);
+    
+    if (dv_sysfs_init())
+        printk(KERN_WARNING "Driver Verifier: Failed to create sysfs attributes\n");
+    
+    dv_verify_wq = alloc_workqueue("driver_verifier", WQ_UNBOUND, max(verify_concurrency, 1U));
+    if (!dv_verify_wq)
+        printk(KERN_WARNING "Driver Verifier: Failed to allocate verification workqueue\n");
+    
+    if (dv_chardev_init())
+        printk(KERN_WARNING "Driver Verifier: Failed to register character device\n");
+    
+    if (boot_verify)
+        schedule_delayed_work(&dv_boot_verify_work, msecs_to_jiffies(DV_BOOT_INITIAL_DELAY_MS));
+    
+    if (watchdog)
+        schedule_delayed_work(&dv_watchdog_work, msecs_to_jiffies(dv_watchdog_interval_ms));
     
     printk(KERN_INFO "Driver Verifier: Module loaded successfully\n");
     return 0;
@@ -65,6 +1938,15 @@ static int __init driver_verifier_init(void)
 static void __exit driver_verifier_exit(void)
 {
     printk(KERN_INFO "Driver Verifier: cleaning up\n");
+    cancel_delayed_work_sync(&dv_boot_verify_work);
+    cancel_delayed_work_sync(&dv_watchdog_work);
+    dv_chardev_exit();
+    if (dv_verify_wq)
+        destroy_workqueue(dv_verify_wq);
+    dv_sysfs_exit();
+    debugfs_remove_recursive(dv_debugfs_dir);
+    input_unregister_handler(&dv_monitor_handler);
+    dv_netlink_exit();
     rust_exit();
     printk(KERN_INFO "Driver Verifier: Module unloaded\n");
 }
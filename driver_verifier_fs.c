@@ -0,0 +1,216 @@
+/**
+ * driver_verifier_fs.c - Filesystem shims called from Rust
+ *
+ * Small helpers for the Rust side to resolve sysfs links, list directories
+ * and read and write sysfs attributes. Paths are passed as (pointer, length) pairs
+ * without a NUL terminator and copied into a kernel string here.
+ */
+#include <linux/kernel.h>
+#include <linux/fs.h>
+#include <linux/namei.h>
+#include <linux/path.h>
+#include <linux/dcache.h>
+#include <linux/slab.h>
+#include <linux/string.h>
+
+#include "driver_verifier.h"
+
+/**
+ * kernel_resolve_path - Resolves a path to its canonical form
+ * @path: Path to resolve (not NUL terminated)
+ * @path_len: Length of @path
+ * @buffer: Receives the canonical path (not NUL terminated)
+ * @buffer_size: Size of @buffer
+ * @resolved_len: Receives the length of the canonical path
+ *
+ * Return: 0 on success, negative error code on failure
+ */
+int kernel_resolve_path(const char *path, size_t path_len, char *buffer,
+                        size_t buffer_size, size_t *resolved_len)
+{
+    struct path resolved;
+    char *kpath, *page, *name;
+    int error;
+
+    kpath = kmemdup_nul(path, path_len, GFP_KERNEL);
+    if (!kpath)
+        return -ENOMEM;
+
+    error = kern_path(kpath, LOOKUP_FOLLOW, &resolved);
+    kfree(kpath);
+    if (error)
+        return error;
+
+    page = (char *)__get_free_page(GFP_KERNEL);
+    if (!page) {
+        path_put(&resolved);
+        return -ENOMEM;
+    }
+
+    name = d_path(&resolved, page, PAGE_SIZE);
+    path_put(&resolved);
+    if (IS_ERR(name)) {
+        free_page((unsigned long)page);
+        return PTR_ERR(name);
+    }
+
+    *resolved_len = strlen(name);
+    if (*resolved_len > buffer_size) {
+        free_page((unsigned long)page);
+        return -ENAMETOOLONG;
+    }
+
+    memcpy(buffer, name, *resolved_len);
+    free_page((unsigned long)page);
+    return 0;
+}
+
+/**
+ * struct dv_dir_context - State of a directory listing
+ * @ctx: Context passed to iterate_dir()
+ * @entries: Entries to fill, NULL when only counting
+ * @capacity: Number of entries @entries holds
+ * @count: Number of entries seen so far
+ */
+struct dv_dir_context {
+    struct dir_context ctx;
+    struct dv_dir_entry *entries;
+    size_t capacity;
+    size_t count;
+};
+
+static bool dv_fill_dir_entry(struct dir_context *ctx, const char *name, int namelen,
+                              loff_t offset, u64 ino, unsigned int d_type)
+{
+    struct dv_dir_context *dir = container_of(ctx, struct dv_dir_context, ctx);
+
+    /* Skip ".", ".." and hidden entries */
+    if (namelen > 0 && name[0] == '.')
+        return true;
+
+    if (dir->count < dir->capacity) {
+        struct dv_dir_entry *entry = &dir->entries[dir->count];
+
+        entry->len = namelen;
+        memcpy(entry->name, name, min_t(int, namelen, DV_DIR_NAME_MAX));
+    }
+    dir->count++;
+    return true;
+}
+
+/**
+ * kernel_read_directory - Lists the entries of a directory
+ * @path: Directory path (not NUL terminated)
+ * @path_len: Length of @path
+ * @entries: Entries to fill, or NULL to only count the entries
+ * @capacity: Number of entries @entries holds
+ * @count: Receives the number of entries in the directory, which may exceed
+ *         @capacity if the directory grew since it was counted
+ *
+ * Meant to be called twice: once with a NULL @entries to size the buffer and
+ * once to fill it. Hidden entries are skipped.
+ *
+ * Return: 0 on success, negative error code on failure
+ */
+int kernel_read_directory(const char *path, size_t path_len, struct dv_dir_entry *entries,
+                          size_t capacity, size_t *count)
+{
+    struct dv_dir_context dir = {
+        .ctx.actor = dv_fill_dir_entry,
+        .entries = entries,
+        .capacity = entries ? capacity : 0,
+    };
+    struct file *file;
+    char *kpath;
+    int error;
+
+    kpath = kmemdup_nul(path, path_len, GFP_KERNEL);
+    if (!kpath)
+        return -ENOMEM;
+
+    file = filp_open(kpath, O_RDONLY | O_DIRECTORY, 0);
+    kfree(kpath);
+    if (IS_ERR(file))
+        return PTR_ERR(file);
+
+    error = iterate_dir(file, &dir.ctx);
+    filp_close(file, NULL);
+    if (error)
+        return error;
+
+    *count = dir.count;
+    return 0;
+}
+
+/**
+ * kernel_read_sysfs_file - Reads a small file, typically a sysfs attribute
+ * @path: File path (not NUL terminated)
+ * @path_len: Length of @path
+ * @buffer: Receives the contents (not NUL terminated)
+ * @buffer_size: Size of @buffer
+ * @bytes_read: Receives the number of bytes read
+ *
+ * Longer files are truncated to @buffer_size. Not named kernel_read_file(),
+ * which the kernel exports with a different signature.
+ *
+ * Return: 0 on success, negative error code on failure
+ */
+int kernel_read_sysfs_file(const char *path, size_t path_len, char *buffer,
+                           size_t buffer_size, size_t *bytes_read)
+{
+    struct file *file;
+    loff_t pos = 0;
+    char *kpath;
+    ssize_t count;
+
+    kpath = kmemdup_nul(path, path_len, GFP_KERNEL);
+    if (!kpath)
+        return -ENOMEM;
+
+    file = filp_open(kpath, O_RDONLY, 0);
+    kfree(kpath);
+    if (IS_ERR(file))
+        return PTR_ERR(file);
+
+    count = kernel_read(file, buffer, buffer_size, &pos);
+    filp_close(file, NULL);
+
+    if (count < 0)
+        return count;
+    *bytes_read = count;
+    return 0;
+}
+
+/**
+ * kernel_write_file - Writes a buffer to a file, typically a sysfs attribute
+ * @path: File path (not NUL terminated)
+ * @path_len: Length of @path
+ * @buffer: Data to write
+ * @buffer_len: Length of @buffer
+ *
+ * Return: 0 if the whole buffer was written, negative error code otherwise
+ */
+int kernel_write_file(const char *path, size_t path_len, const char *buffer,
+                      size_t buffer_len)
+{
+    struct file *file;
+    loff_t pos = 0;
+    char *kpath;
+    ssize_t written;
+
+    kpath = kmemdup_nul(path, path_len, GFP_KERNEL);
+    if (!kpath)
+        return -ENOMEM;
+
+    file = filp_open(kpath, O_WRONLY, 0);
+    kfree(kpath);
+    if (IS_ERR(file))
+        return PTR_ERR(file);
+
+    written = kernel_write(file, buffer, buffer_len, &pos);
+    filp_close(file, NULL);
+
+    if (written < 0)
+        return written;
+    return written == buffer_len ? 0 : -EIO;
+}
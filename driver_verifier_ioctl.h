@@ -0,0 +1,150 @@
+/* SPDX-License-Identifier: GPL-2.0 WITH Linux-syscall-note */
+/**
+ * driver_verifier_ioctl.h - ioctl interface of /dev/driver_verifier
+ *
+ * Shared with userspace tools. Structures have a fixed layout on 32 and 64
+ * bit userspace.
+ */
+#ifndef _DRIVER_VERIFIER_IOCTL_H
+#define _DRIVER_VERIFIER_IOCTL_H
+
+#include <linux/ioctl.h>
+#include <linux/types.h>
+
+/* Number of events the recorder holds, must match recorder::RECORDER_CAPACITY */
+#define DV_RECORDER_CAPACITY 4096
+
+/**
+ * struct dv_event_record - A recorded input event
+ * @time_us: Time since the recording started, in microseconds
+ * @type: Event type (EV_*)
+ * @code: Event code
+ * @value: Event value
+ */
+struct dv_event_record {
+    __u64 time_us;
+    __u16 type;
+    __u16 code;
+    __s32 value;
+};
+
+/**
+ * struct dv_event_trace - A user buffer of recorded events
+ * @events: User pointer to an array of struct dv_event_record
+ * @count: Number of records; for DV_IOC_GET_TRACE the capacity of @events on
+ *         input and the number of records copied on output
+ * @reserved: Must be zero
+ */
+struct dv_event_trace {
+    __u64 events;
+    __u32 count;
+    __u32 reserved;
+};
+
+/**
+ * struct dv_report_buffer - A user buffer receiving a verification report
+ * @data: User pointer to the buffer
+ * @size: Size of @data on input; size of the report on output, also when the
+ *        buffer was too small and -ENOSPC is returned
+ * @index: Index of the touchpad in scan order
+ *
+ * The report is a text blob of "key<TAB>value" lines starting with
+ * "dv-report 2", not NUL terminated.
+ */
+struct dv_report_buffer {
+    __u64 data;
+    __u32 size;
+    __u32 index;
+};
+
+/* Fields of struct dv_config to apply, must match config::CONFIG_* on the Rust side */
+#define DV_CONFIG_EVENT_WINDOW     (1 << 0)
+#define DV_CONFIG_FREEZE           (1 << 1)
+#define DV_CONFIG_CACHE_TTL        (1 << 2)
+#define DV_CONFIG_MONITOR_INTERVAL (1 << 3)
+#define DV_CONFIG_VERBOSITY        (1 << 4)
+#define DV_CONFIG_PHASES           (1 << 5)
+#define DV_CONFIG_ENUMERATION      (1 << 6)
+
+/* Phase bits of struct dv_config, must match trace::Phase on the Rust side */
+#define DV_PHASE_MODULES    (1 << 0)
+#define DV_PHASE_RESPONSIVE (1 << 1)
+#define DV_PHASE_EVENTS     (1 << 2)
+#define DV_PHASE_PALM       (1 << 3)
+#define DV_PHASE_MULTITOUCH (1 << 4)
+#define DV_PHASE_FN_KEYS    (1 << 5)
+
+/* Verbosity levels, must match config::Verbosity on the Rust side */
+#define DV_VERBOSITY_QUIET   0
+#define DV_VERBOSITY_NORMAL  1
+#define DV_VERBOSITY_VERBOSE 2
+
+/* Scan backends, must match input_core::Enumeration on the Rust side */
+#define DV_ENUM_SYSFS      0
+#define DV_ENUM_INPUT_CORE 1
+
+/**
+ * struct dv_config - A runtime configuration update
+ * @fields: DV_CONFIG_* bits of the fields to apply, the others are ignored
+ * @event_window_ms: How long the interactive event check waits, 1 to 60000
+ * @freeze_ms: Touchpad silence after which the watchdog suspects a freeze
+ * @cache_ttl_ms: Time a verification result is reused, 0 to always verify
+ * @monitor_interval_ms: Interval of the watchdog checks, 100 to 60000
+ * @verbosity: DV_VERBOSITY_* level
+ * @phases: DV_PHASE_* bits of the phases to run on top of the profile; the
+ *          modules and responsiveness phases always run
+ * @enumeration: DV_ENUM_* backend of the following scans; DV_ENUM_INPUT_CORE
+ *               is rejected if the shim can't walk the input core
+ *
+ * The update is validated as a whole and applied entirely or not at all.
+ */
+struct dv_config {
+    __u32 fields;
+    __u32 event_window_ms;
+    __u32 freeze_ms;
+    __u32 cache_ttl_ms;
+    __u32 monitor_interval_ms;
+    __u32 verbosity;
+    __u32 phases;
+    __u32 enumeration;
+};
+
+/* Verification profiles, must match profile::Profile on the Rust side */
+#define DV_PROFILE_QUICK       0
+#define DV_PROFILE_STANDARD    1
+#define DV_PROFILE_DEEP        2
+#define DV_PROFILE_INTERACTIVE 3
+
+/* Safety policies, must match safety::SafetyPolicy on the Rust side */
+#define DV_SAFETY_READ_ONLY 0
+#define DV_SAFETY_DIAGNOSE  1
+#define DV_SAFETY_REPAIR    2
+
+#define DV_IOC_MAGIC 'V'
+
+/* Start recording the events of the touchpad */
+#define DV_IOC_RECORD_START _IO(DV_IOC_MAGIC, 1)
+/* Stop recording; returns the number of events recorded */
+#define DV_IOC_RECORD_STOP  _IO(DV_IOC_MAGIC, 2)
+/* Copy the recorded events, oldest first */
+#define DV_IOC_GET_TRACE    _IOWR(DV_IOC_MAGIC, 3, struct dv_event_trace)
+/* Replace the recorded events with a stored trace */
+#define DV_IOC_SET_TRACE    _IOW(DV_IOC_MAGIC, 4, struct dv_event_trace)
+/* Replay the recorded events through a virtual touchpad; returns the number replayed */
+#define DV_IOC_REPLAY       _IO(DV_IOC_MAGIC, 5)
+/* Select the verification profile (DV_PROFILE_*) of the next verifications */
+#define DV_IOC_SET_PROFILE  _IOW(DV_IOC_MAGIC, 6, __u32)
+/* Verify every scanned device concurrently; returns the number not working */
+#define DV_IOC_VERIFY_ALL   _IO(DV_IOC_MAGIC, 7)
+/* Clear the incident log */
+#define DV_IOC_CLEAR_INCIDENTS _IO(DV_IOC_MAGIC, 8)
+/* Copy the latest verification report of a touchpad */
+#define DV_IOC_GET_REPORT   _IOWR(DV_IOC_MAGIC, 9, struct dv_report_buffer)
+/* Apply a configuration update to the next verifications */
+#define DV_IOC_SET_CONFIG   _IOW(DV_IOC_MAGIC, 10, struct dv_config)
+/* Select the safety policy (DV_SAFETY_*); requires CAP_SYS_ADMIN */
+#define DV_IOC_SET_SAFETY   _IOW(DV_IOC_MAGIC, 11, __u32)
+/* Verify the touchpads again, ignoring the cached result; returns 1 if all are working, 0 if not */
+#define DV_IOC_VERIFY_TOUCHPADS _IO(DV_IOC_MAGIC, 12)
+
+#endif /* _DRIVER_VERIFIER_IOCTL_H */
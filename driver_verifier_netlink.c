@@ -0,0 +1,151 @@
+/**
+ * driver_verifier_netlink.c - Generic netlink event stream to userspace
+ *
+ * Registers the "driver_verifier" generic netlink family with a single
+ * "events" multicast group. The Rust side pushes notifications through
+ * kernel_notify(); userspace daemons subscribe to the group to react to
+ * device and verification changes without polling. SYN_DROPPED is detected
+ * on the event path, where no message can be allocated, so it only flags the
+ * monitor slot and a work item sends the notifications.
+ */
+#include <linux/kernel.h>
+#include <linux/module.h>
+#include <linux/workqueue.h>
+#include <net/genetlink.h>
+
+#include "driver_verifier.h"
+
+/* Attributes of a notification message */
+enum {
+    DV_NL_ATTR_UNSPEC,
+    DV_NL_ATTR_EVENT,    /* u32: notification kind */
+    DV_NL_ATTR_SUBJECT,  /* string: device name or path */
+    DV_NL_ATTR_VALUE,    /* s32: event specific value */
+    __DV_NL_ATTR_MAX,
+};
+#define DV_NL_ATTR_MAX (__DV_NL_ATTR_MAX - 1)
+
+/* Commands */
+enum {
+    DV_NL_CMD_UNSPEC,
+    DV_NL_CMD_EVENT,
+};
+
+/* Notification kind of a SYN_DROPPED, must match Notification::SynDropped in notify.rs */
+#define DV_NOTIFY_SYN_DROPPED 6
+
+/* Monitor slots that reported SYN_DROPPED since the work item last ran */
+static unsigned long dv_syn_dropped_slots;
+
+static const struct genl_multicast_group dv_nl_mcgrps[] = {
+    { .name = "events" },
+};
+
+static struct genl_family dv_nl_family = {
+    .name     = "driver_verifier",
+    .version  = 1,
+    .maxattr  = DV_NL_ATTR_MAX,
+    .module   = THIS_MODULE,
+    .mcgrps   = dv_nl_mcgrps,
+    .n_mcgrps = ARRAY_SIZE(dv_nl_mcgrps),
+};
+
+/**
+ * kernel_notify - Multicasts a notification to userspace
+ * @event: Notification kind
+ * @subject: Device name or path (not NUL terminated, may be empty)
+ * @subject_len: Length of @subject
+ * @value: Event specific value
+ *
+ * May be called from atomic context.
+ *
+ * Return: 0 on success or if nobody is listening, negative error code otherwise
+ */
+int kernel_notify(u32 event, const char *subject, size_t subject_len, s32 value)
+{
+    struct sk_buff *skb;
+    struct nlattr *attr;
+    void *hdr;
+
+    if (!genl_has_listeners(&dv_nl_family, &init_net, 0))
+        return 0;
+
+    skb = genlmsg_new(NLMSG_GOODSIZE, GFP_ATOMIC);
+    if (!skb)
+        return -ENOMEM;
+
+    hdr = genlmsg_put(skb, 0, 0, &dv_nl_family, 0, DV_NL_CMD_EVENT);
+    if (!hdr)
+        goto err_free;
+
+    if (nla_put_u32(skb, DV_NL_ATTR_EVENT, event))
+        goto err_free;
+
+    attr = nla_reserve(skb, DV_NL_ATTR_SUBJECT, subject_len + 1);
+    if (!attr)
+        goto err_free;
+    memcpy(nla_data(attr), subject, subject_len);
+    ((char *)nla_data(attr))[subject_len] = '\0';
+
+    if (nla_put_s32(skb, DV_NL_ATTR_VALUE, value))
+        goto err_free;
+
+    genlmsg_end(skb, hdr);
+    return genlmsg_multicast(&dv_nl_family, skb, 0, 0, GFP_ATOMIC);
+
+err_free:
+    nlmsg_free(skb);
+    return -EMSGSIZE;
+}
+
+/**
+ * dv_syn_dropped_fn - Sends the SYN_DROPPED notifications flagged so far
+ * @work: Work item
+ *
+ * One notification is sent per flagged slot, however many drops it saw.
+ */
+static void dv_syn_dropped_fn(struct work_struct *work)
+{
+    unsigned long slots = xchg(&dv_syn_dropped_slots, 0);
+    unsigned int slot;
+
+    for_each_set_bit(slot, &slots, BITS_PER_LONG)
+        kernel_notify(DV_NOTIFY_SYN_DROPPED, "", 0, slot);
+}
+
+static DECLARE_WORK(dv_syn_dropped_work, dv_syn_dropped_fn);
+
+/**
+ * kernel_notify_syn_dropped - Queues a SYN_DROPPED notification
+ * @slot: Monitor slot that reported SYN_DROPPED
+ *
+ * Safe to call from the event path: it neither allocates nor sleeps. Drops
+ * reported before the work item runs are coalesced.
+ */
+void kernel_notify_syn_dropped(u32 slot)
+{
+    if (slot >= BITS_PER_LONG)
+        return;
+
+    set_bit(slot, &dv_syn_dropped_slots);
+    schedule_work(&dv_syn_dropped_work);
+}
+
+/**
+ * dv_netlink_init - Registers the generic netlink family
+ *
+ * Return: 0 on success, negative error code on failure
+ */
+int dv_netlink_init(void)
+{
+    return genl_register_family(&dv_nl_family);
+}
+
+/**
+ * dv_netlink_exit - Unregisters the generic netlink family
+ */
+void dv_netlink_exit(void)
+{
+    cancel_work_sync(&dv_syn_dropped_work);
+    genl_unregister_family(&dv_nl_family);
+}
@@ -0,0 +1,305 @@
+/**
+ * driver_verifier_sysfs.c - sysfs interface of the driver verifier
+ *
+ * Creates /sys/kernel/driver_verifier/ with a "stats" group exposing the
+ * Rust statistics counters as read-only attributes. Writing anything to
+ * stats/reset clears all counters. touchpad_status reports the verification
+ * result, reusing the last one while it is fresh (see cache_ttl_ms); it is
+ * readable by root only, since a read may verify and recover the touchpad.
+ * expectations holds the declared expected state and device annotations, and
+ * deviations lists how the last verification deviated from it. seat limits
+ * the scan to the devices of one seat, writing an empty line lifts the limit.
+ */
+#include <linux/kernel.h>
+#include <linux/kobject.h>
+#include <linux/sysfs.h>
+
+#include "driver_verifier.h"
+
+extern u64 rust_stats_read(u32 counter);
+extern void rust_stats_reset(void);
+extern int rust_verify_touchpad(int force);
+extern int rust_set_expectations(const char *text, size_t text_len);
+extern ssize_t rust_expectations_read(char *buf, size_t buf_len);
+extern ssize_t rust_deviations_read(char *buf, size_t buf_len);
+extern int rust_configure_seat(const char *seat, size_t seat_len);
+extern ssize_t rust_seat_read(char *buf, size_t buf_len);
+extern ssize_t rust_reason_codes_read(char *buf, size_t buf_len);
+
+/* Counter ids, must match stats::Counter on the Rust side */
+#define DV_STAT_SCANS_PERFORMED            0
+#define DV_STAT_TOUCHPADS_FOUND            1
+#define DV_STAT_KEYBOARDS_FOUND            2
+#define DV_STAT_MICE_FOUND                 3
+#define DV_STAT_UNKNOWN_FOUND              4
+#define DV_STAT_VERIFICATIONS_RUN          5
+#define DV_STAT_FAILURES_MODULES_MISSING   6
+#define DV_STAT_FAILURES_NODE_UNRESPONSIVE 7
+#define DV_STAT_FAILURES_NO_EVENTS         8
+#define DV_STAT_FAILURES_NOT_CAPABLE       9
+#define DV_STAT_FAILURES_ERROR             10
+#define DV_STAT_EVENTS_OBSERVED            11
+#define DV_STAT_SENSORS_FOUND              12
+#define DV_STAT_POOL_HIGH_WATER_BYTES      13
+#define DV_STAT_POOL_HIGH_WATER_ENTRIES    14
+#define DV_STAT_POOL_EXHAUSTED             15
+#define DV_STAT_POINTING_STICKS_FOUND      16
+#define DV_STAT_TOUCHSCREENS_FOUND         17
+#define DV_STAT_DEGRADED_MULTITOUCH        18
+#define DV_STAT_PHASE_MODULES_US           19
+#define DV_STAT_PHASE_RESPONSIVE_US        20
+#define DV_STAT_PHASE_EVENTS_US            21
+#define DV_STAT_PHASE_PALM_US              22
+#define DV_STAT_PHASE_MULTITOUCH_US        23
+#define DV_STAT_PHASE_MODULES_MAX_US       24
+#define DV_STAT_PHASE_RESPONSIVE_MAX_US    25
+#define DV_STAT_PHASE_EVENTS_MAX_US        26
+#define DV_STAT_PHASE_PALM_MAX_US          27
+#define DV_STAT_PHASE_MULTITOUCH_MAX_US    28
+#define DV_STAT_PHASE_FN_KEYS_US           29
+#define DV_STAT_PHASE_FN_KEYS_MAX_US       30
+
+static struct kobject *dv_kobj;
+
+/**
+ * DV_STAT_ATTR - Defines a read-only attribute showing one counter
+ */
+#define DV_STAT_ATTR(_name, _id)                                          \
+static ssize_t _name##_show(struct kobject *kobj,                         \
+                            struct kobj_attribute *attr, char *buf)       \
+{                                                                         \
+    return sysfs_emit(buf, "%llu\n", rust_stats_read(_id));               \
+}                                                                         \
+static struct kobj_attribute dv_stat_##_name = __ATTR_RO(_name)
+
+DV_STAT_ATTR(scans_performed, DV_STAT_SCANS_PERFORMED);
+DV_STAT_ATTR(touchpads_found, DV_STAT_TOUCHPADS_FOUND);
+DV_STAT_ATTR(keyboards_found, DV_STAT_KEYBOARDS_FOUND);
+DV_STAT_ATTR(mice_found, DV_STAT_MICE_FOUND);
+DV_STAT_ATTR(unknown_found, DV_STAT_UNKNOWN_FOUND);
+DV_STAT_ATTR(sensors_found, DV_STAT_SENSORS_FOUND);
+DV_STAT_ATTR(pointing_sticks_found, DV_STAT_POINTING_STICKS_FOUND);
+DV_STAT_ATTR(touchscreens_found, DV_STAT_TOUCHSCREENS_FOUND);
+DV_STAT_ATTR(verifications_run, DV_STAT_VERIFICATIONS_RUN);
+DV_STAT_ATTR(failures_modules_missing, DV_STAT_FAILURES_MODULES_MISSING);
+DV_STAT_ATTR(failures_node_unresponsive, DV_STAT_FAILURES_NODE_UNRESPONSIVE);
+DV_STAT_ATTR(failures_no_events, DV_STAT_FAILURES_NO_EVENTS);
+DV_STAT_ATTR(failures_not_capable, DV_STAT_FAILURES_NOT_CAPABLE);
+DV_STAT_ATTR(failures_error, DV_STAT_FAILURES_ERROR);
+DV_STAT_ATTR(events_observed, DV_STAT_EVENTS_OBSERVED);
+DV_STAT_ATTR(degraded_multitouch, DV_STAT_DEGRADED_MULTITOUCH);
+DV_STAT_ATTR(pool_high_water_bytes, DV_STAT_POOL_HIGH_WATER_BYTES);
+DV_STAT_ATTR(pool_high_water_entries, DV_STAT_POOL_HIGH_WATER_ENTRIES);
+DV_STAT_ATTR(pool_exhausted, DV_STAT_POOL_EXHAUSTED);
+DV_STAT_ATTR(phase_modules_us, DV_STAT_PHASE_MODULES_US);
+DV_STAT_ATTR(phase_responsive_us, DV_STAT_PHASE_RESPONSIVE_US);
+DV_STAT_ATTR(phase_events_us, DV_STAT_PHASE_EVENTS_US);
+DV_STAT_ATTR(phase_palm_us, DV_STAT_PHASE_PALM_US);
+DV_STAT_ATTR(phase_multitouch_us, DV_STAT_PHASE_MULTITOUCH_US);
+DV_STAT_ATTR(phase_fn_keys_us, DV_STAT_PHASE_FN_KEYS_US);
+DV_STAT_ATTR(phase_modules_max_us, DV_STAT_PHASE_MODULES_MAX_US);
+DV_STAT_ATTR(phase_responsive_max_us, DV_STAT_PHASE_RESPONSIVE_MAX_US);
+DV_STAT_ATTR(phase_events_max_us, DV_STAT_PHASE_EVENTS_MAX_US);
+DV_STAT_ATTR(phase_palm_max_us, DV_STAT_PHASE_PALM_MAX_US);
+DV_STAT_ATTR(phase_multitouch_max_us, DV_STAT_PHASE_MULTITOUCH_MAX_US);
+DV_STAT_ATTR(phase_fn_keys_max_us, DV_STAT_PHASE_FN_KEYS_MAX_US);
+
+static ssize_t reset_store(struct kobject *kobj, struct kobj_attribute *attr,
+                           const char *buf, size_t count)
+{
+    rust_stats_reset();
+    return count;
+}
+static struct kobj_attribute dv_stat_reset = __ATTR_WO(reset);
+
+static struct attribute *dv_stats_attrs[] = {
+    &dv_stat_scans_performed.attr,
+    &dv_stat_touchpads_found.attr,
+    &dv_stat_keyboards_found.attr,
+    &dv_stat_mice_found.attr,
+    &dv_stat_unknown_found.attr,
+    &dv_stat_sensors_found.attr,
+    &dv_stat_pointing_sticks_found.attr,
+    &dv_stat_touchscreens_found.attr,
+    &dv_stat_verifications_run.attr,
+    &dv_stat_failures_modules_missing.attr,
+    &dv_stat_failures_node_unresponsive.attr,
+    &dv_stat_failures_no_events.attr,
+    &dv_stat_failures_not_capable.attr,
+    &dv_stat_failures_error.attr,
+    &dv_stat_events_observed.attr,
+    &dv_stat_degraded_multitouch.attr,
+    &dv_stat_pool_high_water_bytes.attr,
+    &dv_stat_pool_high_water_entries.attr,
+    &dv_stat_pool_exhausted.attr,
+    &dv_stat_phase_modules_us.attr,
+    &dv_stat_phase_responsive_us.attr,
+    &dv_stat_phase_events_us.attr,
+    &dv_stat_phase_palm_us.attr,
+    &dv_stat_phase_multitouch_us.attr,
+    &dv_stat_phase_fn_keys_us.attr,
+    &dv_stat_phase_modules_max_us.attr,
+    &dv_stat_phase_responsive_max_us.attr,
+    &dv_stat_phase_events_max_us.attr,
+    &dv_stat_phase_palm_max_us.attr,
+    &dv_stat_phase_multitouch_max_us.attr,
+    &dv_stat_phase_fn_keys_max_us.attr,
+    &dv_stat_reset.attr,
+    NULL,
+};
+
+static const struct attribute_group dv_stats_group = {
+    .name  = "stats",
+    .attrs = dv_stats_attrs,
+};
+
+static ssize_t touchpad_status_show(struct kobject *kobj,
+                                    struct kobj_attribute *attr, char *buf)
+{
+    int status;
+
+    mutex_lock(&dv_verifier_lock);
+    status = rust_verify_touchpad(0);
+    mutex_unlock(&dv_verifier_lock);
+
+    return sysfs_emit(buf, "%s\n", dv_verify_status_name(status));
+}
+/*
+ * Root only: a read past the cache TTL holds dv_verifier_lock for the whole
+ * verification. The read never repairs: it verifies under at most the
+ * diagnose policy, and recovery is left to DV_IOC_VERIFY_TOUCHPADS. The debugfs
+ * touchpads list shows the last results without verifying.
+ */
+static struct kobj_attribute dv_touchpad_status = __ATTR(touchpad_status, 0400,
+                                                         touchpad_status_show, NULL);
+
+static ssize_t expectations_show(struct kobject *kobj,
+                                 struct kobj_attribute *attr, char *buf)
+{
+    ssize_t len;
+
+    mutex_lock(&dv_verifier_lock);
+    len = rust_expectations_read(buf, PAGE_SIZE);
+    mutex_unlock(&dv_verifier_lock);
+
+    return len < 0 ? -ENODEV : len;
+}
+
+static ssize_t expectations_store(struct kobject *kobj, struct kobj_attribute *attr,
+                                  const char *buf, size_t count)
+{
+    int ret;
+
+    mutex_lock(&dv_verifier_lock);
+    ret = rust_set_expectations(buf, count);
+    mutex_unlock(&dv_verifier_lock);
+
+    if (ret > 0)
+        printk(KERN_WARNING "Driver Verifier: Invalid expectation on line %d\n", ret);
+    return ret ? -EINVAL : count;
+}
+static struct kobj_attribute dv_expectations = __ATTR(expectations, 0644,
+                                                      expectations_show, expectations_store);
+
+static ssize_t deviations_show(struct kobject *kobj,
+                               struct kobj_attribute *attr, char *buf)
+{
+    ssize_t len;
+
+    mutex_lock(&dv_verifier_lock);
+    len = rust_deviations_read(buf, PAGE_SIZE);
+    mutex_unlock(&dv_verifier_lock);
+
+    return len < 0 ? -ENODEV : len;
+}
+static struct kobj_attribute dv_deviations = __ATTR_RO(deviations);
+
+static ssize_t seat_show(struct kobject *kobj, struct kobj_attribute *attr, char *buf)
+{
+    ssize_t len;
+
+    mutex_lock(&dv_verifier_lock);
+    len = rust_seat_read(buf, PAGE_SIZE);
+    mutex_unlock(&dv_verifier_lock);
+
+    return len < 0 ? -ENODEV : len;
+}
+
+static ssize_t seat_store(struct kobject *kobj, struct kobj_attribute *attr,
+                          const char *buf, size_t count)
+{
+    int ret;
+
+    mutex_lock(&dv_verifier_lock);
+    ret = rust_configure_seat(buf, count);
+    mutex_unlock(&dv_verifier_lock);
+
+    return ret ? -EINVAL : count;
+}
+static struct kobj_attribute dv_seat = __ATTR(seat, 0644, seat_show, seat_store);
+
+/* The code table is static, so it is read without the verifier lock */
+static ssize_t reason_codes_show(struct kobject *kobj, struct kobj_attribute *attr, char *buf)
+{
+    ssize_t len = rust_reason_codes_read(buf, PAGE_SIZE);
+
+    return len < 0 ? -EINVAL : len;
+}
+static struct kobj_attribute dv_reason_codes = __ATTR_RO(reason_codes);
+
+static struct attribute *dv_attrs[] = {
+    &dv_touchpad_status.attr,
+    &dv_expectations.attr,
+    &dv_deviations.attr,
+    &dv_seat.attr,
+    &dv_reason_codes.attr,
+    NULL,
+};
+
+static const struct attribute_group dv_group = {
+    .attrs = dv_attrs,
+};
+
+/**
+ * dv_sysfs_init - Creates the sysfs directory and attribute groups
+ *
+ * Return: 0 on success, negative error code on failure
+ */
+int dv_sysfs_init(void)
+{
+    int error;
+
+    dv_kobj = kobject_create_and_add("driver_verifier", kernel_kobj);
+    if (!dv_kobj)
+        return -ENOMEM;
+
+    error = sysfs_create_group(dv_kobj, &dv_stats_group);
+    if (error)
+        goto err_put;
+
+    error = sysfs_create_group(dv_kobj, &dv_group);
+    if (error) {
+        sysfs_remove_group(dv_kobj, &dv_stats_group);
+        goto err_put;
+    }
+
+    return 0;
+
+err_put:
+    kobject_put(dv_kobj);
+    dv_kobj = NULL;
+    return error;
+}
+
+/**
+ * dv_sysfs_exit - Removes the sysfs directory
+ */
+void dv_sysfs_exit(void)
+{
+    if (dv_kobj) {
+        sysfs_remove_group(dv_kobj, &dv_group);
+        sysfs_remove_group(dv_kobj, &dv_stats_group);
+        kobject_put(dv_kobj);
+        dv_kobj = NULL;
+    }
+}
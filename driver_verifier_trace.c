@@ -0,0 +1,33 @@
+/**
+ * driver_verifier_trace.c - Tracepoint shims called from Rust
+ *
+ * Tracepoints are static inline functions generated from macros, so they
+ * can't be called from Rust directly. These thin wrappers expose them as
+ * regular functions for the kernel operations table.
+ */
+#include <linux/kernel.h>
+
+#include "driver_verifier.h"
+
+#define CREATE_TRACE_POINTS
+#include "driver_verifier_trace.h"
+
+void kernel_trace_scan_start(void)
+{
+    trace_dv_scan_start(0);
+}
+
+void kernel_trace_scan_end(u32 devices, int result)
+{
+    trace_dv_scan_end(devices, result);
+}
+
+void kernel_trace_phase(const char *path, size_t path_len, u32 phase, int outcome)
+{
+    trace_dv_phase(path, path_len, phase, outcome);
+}
+
+void kernel_trace_classify(const char *path, size_t path_len, u32 device_type, u32 method)
+{
+    trace_dv_classify(path, path_len, device_type, method);
+}
@@ -0,0 +1,99 @@
+/**
+ * driver_verifier_trace.h - Tracepoints for verification phases
+ *
+ * Events are emitted through the kernel_trace_* shims in
+ * driver_verifier_trace.c and show up under events/driver_verifier/ in
+ * tracefs, so verifier activity can be correlated with input core events.
+ */
+#undef TRACE_SYSTEM
+#define TRACE_SYSTEM driver_verifier
+
+#if !defined(_DRIVER_VERIFIER_TRACE_H) || defined(TRACE_HEADER_MULTI_READ)
+#define _DRIVER_VERIFIER_TRACE_H
+
+#include <linux/tracepoint.h>
+
+TRACE_EVENT(dv_scan_start,
+    TP_PROTO(int unused),
+    TP_ARGS(unused),
+    TP_STRUCT__entry(
+        __field(int, unused)
+    ),
+    TP_fast_assign(
+        __entry->unused = unused;
+    ),
+    TP_printk("scan started")
+);
+
+TRACE_EVENT(dv_scan_end,
+    TP_PROTO(u32 devices, int result),
+    TP_ARGS(devices, result),
+    TP_STRUCT__entry(
+        __field(u32, devices)
+        __field(int, result)
+    ),
+    TP_fast_assign(
+        __entry->devices = devices;
+        __entry->result = result;
+    ),
+    TP_printk("devices=%u result=%d", __entry->devices, __entry->result)
+);
+
+TRACE_EVENT(dv_phase,
+    TP_PROTO(const char *path, size_t path_len, u32 phase, int outcome),
+    TP_ARGS(path, path_len, phase, outcome),
+    TP_STRUCT__entry(
+        __string_len(path, path, path_len)
+        __field(u32, phase)
+        __field(int, outcome)
+    ),
+    TP_fast_assign(
+        __assign_str(path);
+        __entry->phase = phase;
+        __entry->outcome = outcome;
+    ),
+    TP_printk("path=%s phase=%s outcome=%d", __get_str(path),
+              __print_symbolic(__entry->phase,
+                               { 0, "modules" },
+                               { 1, "responsive" },
+                               { 2, "events" },
+                               { 3, "palm" },
+                               { 4, "multitouch" },
+                               { 5, "fn_keys" }),
+              __entry->outcome)
+);
+
+TRACE_EVENT(dv_classify,
+    TP_PROTO(const char *path, size_t path_len, u32 device_type, u32 method),
+    TP_ARGS(path, path_len, device_type, method),
+    TP_STRUCT__entry(
+        __string_len(path, path, path_len)
+        __field(u32, device_type)
+        __field(u32, method)
+    ),
+    TP_fast_assign(
+        __assign_str(path);
+        __entry->device_type = device_type;
+        __entry->method = method;
+    ),
+    TP_printk("path=%s type=%s method=%s", __get_str(path),
+              __print_symbolic(__entry->device_type,
+                               { 0, "touchpad" },
+                               { 1, "keyboard" },
+                               { 2, "mouse" },
+                               { 3, "unknown" },
+                               { 4, "sensor" },
+                               { 5, "pointing stick" },
+                               { 6, "touchscreen" }),
+              __print_symbolic(__entry->method,
+                               { 0, "name" },
+                               { 1, "capabilities" }))
+);
+
+#endif /* _DRIVER_VERIFIER_TRACE_H */
+
+#undef TRACE_INCLUDE_PATH
+#define TRACE_INCLUDE_PATH .
+#undef TRACE_INCLUDE_FILE
+#define TRACE_INCLUDE_FILE driver_verifier_trace
+#include <trace/define_trace.h>
@@ -0,0 +1,92 @@
+//! Deferred boot-time verification.
+//!
+//! When the module is loaded early in boot, touchpad drivers may still be
+//! probing asynchronously, so a touchpad missing from the first scan is not a
+//! failure yet. In boot mode the first scan runs from a work item shortly after
+//! load, and scans are retried with exponential backoff until a touchpad shows
+//! up or the retry period ends; only then is the final verdict issued.
+use crate::event_monitor::now_ms;
+
+/// Delay before the first retry, doubled after every attempt.
+const INITIAL_BACKOFF_MS: u32 = 500;
+
+/// Longest delay between two attempts.
+const MAX_BACKOFF_MS: u32 = 8000;
+
+/// Retry state of the boot-time verification.
+pub struct BootRetry {
+    retry_ms: u32,
+    started_ms: u64,
+    backoff_ms: u32,
+    attempts: u32,
+}
+
+impl BootRetry {
+    /// Starts the retry period.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry_ms` - How long a missing touchpad is retried before the final verdict
+    pub fn new(retry_ms: u32) -> Self {
+        Self {
+            retry_ms,
+            started_ms: now_ms(),
+            backoff_ms: INITIAL_BACKOFF_MS,
+            attempts: 0,
+        }
+    }
+
+    /// Records an attempt and returns its number, starting at 1.
+    pub fn record_attempt(&mut self) -> u32 {
+        self.attempts += 1;
+        self.attempts
+    }
+
+    /// Returns the delay before the next attempt.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<u32>` - Delay in milliseconds, or None if the retry period is over
+    pub fn next_delay(&mut self) -> Option<u32> {
+        let elapsed = now_ms().saturating_sub(self.started_ms);
+        let remaining = (self.retry_ms as u64).checked_sub(elapsed).filter(|remaining| *remaining > 0)?;
+
+        let delay = (self.backoff_ms as u64).min(remaining) as u32;
+        self.backoff_ms = self.backoff_ms.saturating_mul(2).min(MAX_BACKOFF_MS);
+        Some(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Without a kernel the clock reads 0, so no time passes between calls
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        let mut retry = BootRetry::new(60_000);
+        let delays: [Option<u32>; 7] = core::array::from_fn(|_| retry.next_delay());
+        assert_eq!(delays, [500, 1000, 2000, 4000, 8000, 8000, 8000].map(Some));
+    }
+
+    #[test]
+    fn delay_never_exceeds_the_remaining_period() {
+        let mut retry = BootRetry::new(1500);
+        assert_eq!(retry.next_delay(), Some(500));
+        assert_eq!(retry.next_delay(), Some(1000));
+        assert_eq!(retry.next_delay(), Some(1500));
+    }
+
+    #[test]
+    fn no_delay_once_the_period_is_over() {
+        assert_eq!(BootRetry::new(0).next_delay(), None);
+    }
+
+    #[test]
+    fn attempts_are_numbered_from_one() {
+        let mut retry = BootRetry::new(1000);
+        assert_eq!(retry.record_attempt(), 1);
+        assert_eq!(retry.record_attempt(), 2);
+    }
+}
@@ -0,0 +1,79 @@
+//! Cache of the last verification result.
+//!
+//! A full verification reads sysfs, opens the device node and may wait for
+//! events, which is too expensive for status polling. The aggregate result of
+//! the last verification is kept for a configurable time to live; callers can
+//! force a fresh run. Hotplug of any input device, a rescan or a configuration
+//! change invalidates the cached result.
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::event_monitor::now_ms;
+use crate::input_verifier::VerifyError;
+
+/// Default time to live of a cached result, in milliseconds.
+pub const DEFAULT_CACHE_TTL_MS: u32 = 2000;
+
+/// Bumped on every input device hotplug, which invalidates cached results.
+static HOTPLUG_GENERATION: AtomicU32 = AtomicU32::new(0);
+
+/// Records an input device hotplug.
+///
+/// Does not need the verifier, so it can be called from the input handler.
+pub fn device_changed() {
+    HOTPLUG_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A cached verification result.
+struct Entry {
+    at_ms: u64,
+    generation: u32,
+    result: Result<bool, VerifyError>,
+}
+
+/// Verification result cache with a time to live.
+pub struct ResultCache {
+    ttl_ms: u32,
+    entry: Option<Entry>,
+}
+
+impl ResultCache {
+    /// Creates an empty cache with the default time to live.
+    pub fn new() -> Self {
+        Self { ttl_ms: DEFAULT_CACHE_TTL_MS, entry: None }
+    }
+
+    /// Sets the time to live; 0 disables caching.
+    ///
+    /// # Arguments
+    ///
+    /// * `ttl_ms` - How long a result stays fresh, in milliseconds
+    pub fn configure(&mut self, ttl_ms: u32) {
+        self.ttl_ms = ttl_ms;
+        self.entry = None;
+    }
+
+    /// Returns the cached result if it is still fresh.
+    pub fn get(&self) -> Option<Result<bool, VerifyError>> {
+        let entry = self.entry.as_ref()?;
+        let fresh = now_ms().saturating_sub(entry.at_ms) < self.ttl_ms as u64
+            && entry.generation == HOTPLUG_GENERATION.load(Ordering::Relaxed);
+        fresh.then_some(entry.result)
+    }
+
+    /// Stores the result of a verification that just finished.
+    pub fn store(&mut self, result: Result<bool, VerifyError>) {
+        if self.ttl_ms == 0 {
+            return;
+        }
+        self.entry = Some(Entry {
+            at_ms: now_ms(),
+            generation: HOTPLUG_GENERATION.load(Ordering::Relaxed),
+            result,
+        });
+    }
+
+    /// Drops the cached result.
+    pub fn invalidate(&mut self) {
+        self.entry = None;
+    }
+}
@@ -0,0 +1,205 @@
+//! Runtime configuration pushed from C.
+//!
+//! Module parameters are only read at load time. A `DvConfig` pushed through
+//! `rust_set_config` (e.g. by the `DV_IOC_SET_CONFIG` ioctl) changes timeouts,
+//! verbosity, the monitoring interval, the enabled phases and the scan
+//! backend of a loaded module. Only the fields flagged in `fields` are applied, and they are all
+//! validated before any is applied, so a rejected config changes nothing.
+use crate::input_core::Enumeration;
+use crate::trace::Phase;
+
+/// `DvConfig::fields` bits, must match `DV_CONFIG_*` in driver_verifier_ioctl.h.
+pub const CONFIG_EVENT_WINDOW: u32 = 1 << 0;
+pub const CONFIG_FREEZE: u32 = 1 << 1;
+pub const CONFIG_CACHE_TTL: u32 = 1 << 2;
+pub const CONFIG_MONITOR_INTERVAL: u32 = 1 << 3;
+pub const CONFIG_VERBOSITY: u32 = 1 << 4;
+pub const CONFIG_PHASES: u32 = 1 << 5;
+pub const CONFIG_ENUMERATION: u32 = 1 << 6;
+
+/// All `DvConfig::fields` bits.
+const CONFIG_ALL: u32 = CONFIG_EVENT_WINDOW | CONFIG_FREEZE | CONFIG_CACHE_TTL
+    | CONFIG_MONITOR_INTERVAL | CONFIG_VERBOSITY | CONFIG_PHASES | CONFIG_ENUMERATION;
+
+/// Longest accepted event window, in milliseconds.
+const MAX_EVENT_WINDOW_MS: u32 = 60_000;
+
+/// Range of the accepted watchdog monitoring interval, in milliseconds.
+const MIN_MONITOR_INTERVAL_MS: u32 = 100;
+const MAX_MONITOR_INTERVAL_MS: u32 = 60_000;
+
+/// Phases run by default: all of those the profile selects.
+pub const ALL_PHASES: u32 = (1 << Phase::Modules as u32) | (1 << Phase::Responsive as u32)
+    | (1 << Phase::Events as u32) | (1 << Phase::Palm as u32) | (1 << Phase::Multitouch as u32)
+    | (1 << Phase::FnKeys as u32);
+
+/// How much the verifier writes to the kernel log. The numeric values identify
+/// levels over FFI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Verbosity {
+    /// Reports are kept but not logged.
+    Quiet = 0,
+    /// Every report is logged.
+    Normal = 1,
+    /// Phases are also logged with their duration as they end.
+    Verbose = 2,
+}
+
+impl Verbosity {
+    /// Converts a raw verbosity level received over FFI.
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(Verbosity::Quiet),
+            1 => Some(Verbosity::Normal),
+            2 => Some(Verbosity::Verbose),
+            _ => None,
+        }
+    }
+}
+
+/// A configuration update. Mirrors `struct dv_config`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct DvConfig {
+    /// `CONFIG_*` bits of the fields to apply; the others are ignored.
+    pub fields: u32,
+    /// How long the event check waits for events in interactive mode.
+    pub event_window_ms: u32,
+    /// Touchpad silence after which the watchdog suspects a freeze.
+    pub freeze_ms: u32,
+    /// Time to live of a cached verification result, 0 to always verify.
+    pub cache_ttl_ms: u32,
+    /// Interval of the watchdog checks, applied by the C work item.
+    pub monitor_interval_ms: u32,
+    /// Raw `Verbosity` level.
+    pub verbosity: u32,
+    /// Bits of the phases to run, indexed by `Phase`. The modules and
+    /// responsiveness phases gate the others and always run.
+    pub phases: u32,
+    /// Raw `Enumeration` backend of the following scans.
+    pub enumeration: u32,
+}
+
+impl DvConfig {
+    /// Returns true if the update sets the given field.
+    pub fn has(&self, field: u32) -> bool {
+        self.fields & field != 0
+    }
+
+    /// Checks every field the update sets.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), &'static str>` - Ok if the update can be applied, Err naming the first
+    ///   invalid field otherwise
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.fields & !CONFIG_ALL != 0 {
+            return Err("fields");
+        }
+        if self.has(CONFIG_EVENT_WINDOW) && (self.event_window_ms == 0 || self.event_window_ms > MAX_EVENT_WINDOW_MS) {
+            return Err("event_window_ms");
+        }
+        if self.has(CONFIG_FREEZE) && self.freeze_ms == 0 {
+            return Err("freeze_ms");
+        }
+        if self.has(CONFIG_MONITOR_INTERVAL)
+            && !(MIN_MONITOR_INTERVAL_MS..=MAX_MONITOR_INTERVAL_MS).contains(&self.monitor_interval_ms) {
+            return Err("monitor_interval_ms");
+        }
+        if self.has(CONFIG_VERBOSITY) && Verbosity::from_raw(self.verbosity).is_none() {
+            return Err("verbosity");
+        }
+        if self.has(CONFIG_PHASES) && self.phases & !ALL_PHASES != 0 {
+            return Err("phases");
+        }
+        if self.has(CONFIG_ENUMERATION)
+            && !Enumeration::from_raw(self.enumeration).is_some_and(|enumeration| enumeration.is_supported()) {
+            return Err("enumeration");
+        }
+        Ok(())
+    }
+}
+
+/// Returns true if a phase mask enables the given phase.
+///
+/// # Arguments
+///
+/// * `phases` - Bits of the enabled phases, indexed by `Phase`
+/// * `phase` - Phase to check
+pub fn phase_enabled(phases: u32, phase: Phase) -> bool {
+    matches!(phase, Phase::Modules | Phase::Responsive) || phases & (1 << phase as u32) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(fields: u32) -> DvConfig {
+        DvConfig {
+            fields,
+            event_window_ms: 5000,
+            freeze_ms: 2000,
+            cache_ttl_ms: 0,
+            monitor_interval_ms: 1000,
+            verbosity: Verbosity::Normal as u32,
+            phases: ALL_PHASES,
+            enumeration: Enumeration::Sysfs as u32,
+        }
+    }
+
+    #[test]
+    fn accepts_valid_updates() {
+        assert_eq!(config(0).validate(), Ok(()));
+        assert_eq!(config(CONFIG_ALL).validate(), Ok(()));
+        assert_eq!(DvConfig { monitor_interval_ms: MIN_MONITOR_INTERVAL_MS, ..config(CONFIG_ALL) }.validate(), Ok(()));
+        assert_eq!(DvConfig { event_window_ms: MAX_EVENT_WINDOW_MS, ..config(CONFIG_ALL) }.validate(), Ok(()));
+        assert_eq!(DvConfig { phases: 0, ..config(CONFIG_PHASES) }.validate(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        assert_eq!(config(1 << 7).validate(), Err("fields"));
+        assert_eq!(config(CONFIG_ALL | 1 << 31).validate(), Err("fields"));
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        let invalid = [
+            (DvConfig { event_window_ms: 0, ..config(CONFIG_EVENT_WINDOW) }, "event_window_ms"),
+            (DvConfig { event_window_ms: MAX_EVENT_WINDOW_MS + 1, ..config(CONFIG_EVENT_WINDOW) }, "event_window_ms"),
+            (DvConfig { freeze_ms: 0, ..config(CONFIG_FREEZE) }, "freeze_ms"),
+            (DvConfig { monitor_interval_ms: MIN_MONITOR_INTERVAL_MS - 1, ..config(CONFIG_MONITOR_INTERVAL) },
+             "monitor_interval_ms"),
+            (DvConfig { monitor_interval_ms: MAX_MONITOR_INTERVAL_MS + 1, ..config(CONFIG_MONITOR_INTERVAL) },
+             "monitor_interval_ms"),
+            (DvConfig { verbosity: 3, ..config(CONFIG_VERBOSITY) }, "verbosity"),
+            (DvConfig { phases: ALL_PHASES | 1 << 31, ..config(CONFIG_PHASES) }, "phases"),
+            (DvConfig { enumeration: 2, ..config(CONFIG_ENUMERATION) }, "enumeration"),
+        ];
+        for (update, field) in invalid {
+            assert_eq!(update.validate(), Err(field));
+        }
+    }
+
+    #[test]
+    fn ignores_values_of_fields_not_set() {
+        let update = DvConfig { event_window_ms: 0, freeze_ms: 0, verbosity: 9, ..config(CONFIG_CACHE_TTL) };
+        assert_eq!(update.validate(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_backend_the_shim_lacks() {
+        // Without a kernel the shim provides no input core enumeration
+        let update = DvConfig { enumeration: Enumeration::InputCore as u32, ..config(CONFIG_ENUMERATION) };
+        assert_eq!(update.validate(), Err("enumeration"));
+    }
+
+    #[test]
+    fn gating_phases_always_run() {
+        assert!(phase_enabled(0, Phase::Modules));
+        assert!(phase_enabled(0, Phase::Responsive));
+        assert!(!phase_enabled(0, Phase::Palm));
+        assert!(phase_enabled(ALL_PHASES, Phase::Palm));
+    }
+}
@@ -0,0 +1,125 @@
+//! Device allow and ignore lists.
+//!
+//! Lab machines and VMs often expose virtual input devices (QEMU tablets,
+//! uinput test devices) that should not be scanned or verified. The lists are
+//! set through the `ignore_devices` and `only_devices` module parameters as
+//! comma separated patterns, where each pattern is either a `vendor:product`
+//! pair in hex (e.g. `0627:0001`), a case-insensitive substring of the device
+//! name (e.g. `QEMU`) or a case-insensitive glob matching the whole device
+//! name (e.g. `QEMU*Tablet`). Declared expectations use the same patterns.
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::matcher;
+
+/// A single device pattern.
+pub enum Pattern {
+    /// Matches devices whose name contains the string, ignoring ASCII case.
+    Name(String),
+    /// Matches devices whose whole name matches the glob, ignoring ASCII case.
+    Glob(String),
+    /// Matches devices with the given vendor and product id.
+    Id(u16, u16),
+}
+
+impl Pattern {
+    /// Parses a pattern, returning None for an empty one.
+    pub fn parse(pattern: &str) -> Option<Self> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return None;
+        }
+
+        let id = pattern.split_once(':').and_then(|(vendor, product)| {
+            let is_id = |id: &str| id.len() == 4 && id.chars().all(|c| c.is_ascii_hexdigit());
+            if !is_id(vendor) || !is_id(product) {
+                return None;
+            }
+            Some((u16::from_str_radix(vendor, 16).ok()?, u16::from_str_radix(product, 16).ok()?))
+        });
+
+        Some(match id {
+            Some((vendor, product)) => Pattern::Id(vendor, product),
+            None if matcher::is_glob(pattern) => Pattern::Glob(pattern.to_string()),
+            None => Pattern::Name(pattern.to_string()),
+        })
+    }
+
+    /// Returns true if the pattern matches the device.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Raw name of the device
+    /// * `vendor` - Vendor id of the device
+    /// * `product` - Product id of the device
+    pub fn matches(&self, name: &[u8], vendor: u16, product: u16) -> bool {
+        match self {
+            Pattern::Name(pattern) => matcher::contains_ignore_ascii_case(name, pattern.as_bytes()),
+            Pattern::Glob(pattern) => matcher::glob_match(pattern.as_bytes(), name),
+            Pattern::Id(pattern_vendor, pattern_product) => {
+                *pattern_vendor == vendor && *pattern_product == product
+            },
+        }
+    }
+
+    /// Renders the pattern as it was written.
+    pub fn render(&self) -> String {
+        match self {
+            Pattern::Name(pattern) | Pattern::Glob(pattern) => pattern.to_string(),
+            Pattern::Id(vendor, product) => alloc::format!("{:04x}:{:04x}", vendor, product),
+        }
+    }
+}
+
+/// Allow and ignore lists applied while scanning.
+#[derive(Default)]
+pub struct DeviceFilter {
+    ignore: Vec<Pattern>,
+    only: Vec<Pattern>,
+}
+
+impl DeviceFilter {
+    /// Creates a filter from the comma separated pattern lists.
+    ///
+    /// # Arguments
+    ///
+    /// * `ignore` - Patterns of devices to skip
+    /// * `only` - Patterns of devices to consider; empty to consider every device
+    pub fn new(ignore: &str, only: &str) -> Self {
+        let parse = |list: &str| list.split(',').filter_map(Pattern::parse).collect();
+        Self { ignore: parse(ignore), only: parse(only) }
+    }
+
+    /// Returns true if no pattern is set.
+    pub fn is_empty(&self) -> bool {
+        self.ignore.is_empty() && self.only.is_empty()
+    }
+
+    /// Checks whether a device passes the filter.
+    ///
+    /// A device is skipped if it matches an ignore pattern, or if allow
+    /// patterns are set and it matches none of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Raw name of the device
+    /// * `vendor` - Vendor id of the device
+    /// * `product` - Product id of the device
+    pub fn allows(&self, name: &[u8], vendor: u16, product: u16) -> bool {
+        if self.ignore.iter().any(|pattern| pattern.matches(name, vendor, product)) {
+            return false;
+        }
+        self.only.is_empty() || self.only.iter().any(|pattern| pattern.matches(name, vendor, product))
+    }
+
+    /// Renders the patterns for the kernel log.
+    pub fn describe(&self) -> String {
+        let render = |patterns: &[Pattern]| {
+            patterns.iter()
+                .map(Pattern::render)
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        alloc::format!("ignore [{}], only [{}]", render(&self.ignore), render(&self.only))
+    }
+}
@@ -0,0 +1,86 @@
+//! Directory listing through the kernel shim.
+//!
+//! No Rust object crosses the FFI boundary: the first call of
+//! `kernel_read_directory` only counts the entries, the second fills a buffer
+//! of fixed-size entries provided by the caller. If the directory grew between
+//! the two calls, the listing is retried with a larger buffer. Hidden entries
+//! are skipped by the shim.
+use alloc::vec::Vec;
+
+use crate::kbuf::ShimBuf;
+use crate::kprint;
+use crate::shim;
+
+/// Longest entry name the shim copies, must match `DV_DIR_NAME_MAX` in driver_verifier.h.
+pub const DIR_NAME_MAX: usize = 64;
+
+/// Number of times a growing directory is listed before settling for a partial listing.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// A directory entry as filled in by the shim. Mirrors `struct dv_dir_entry`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct DirEntry {
+    /// Length of the full name, which is larger than `DIR_NAME_MAX` if it was truncated.
+    len: u32,
+    name: [u8; DIR_NAME_MAX],
+}
+
+impl DirEntry {
+    const EMPTY: Self = Self { len: 0, name: [0; DIR_NAME_MAX] };
+
+    /// Returns the name of the entry, None if it was too long to be copied.
+    pub fn name(&self) -> Option<&[u8]> {
+        self.name.get(..self.len as usize)
+    }
+}
+
+/// Lists a directory.
+///
+/// # Arguments
+///
+/// * `path` - Directory path
+/// * `entries` - Buffer receiving the entries; its allocation is reused across calls
+///
+/// # Returns
+///
+/// * `Result<(), ()>` - Ok if the directory was read, Err otherwise
+pub fn read_directory(path: &str, entries: &mut Vec<DirEntry>) -> Result<(), ()> {
+    let Some(kernel_read_directory) = shim::ops().read_directory else {
+        kprint!("Can't list {} without the directory shim\n", path);
+        return Err(());
+    };
+
+    let path_bytes = path.as_bytes();
+    for _ in 0..MAX_ATTEMPTS {
+        let mut count: usize = 0;
+        let result = unsafe {
+            kernel_read_directory(path_bytes.as_ptr(), path_bytes.len(), core::ptr::null_mut(), 0, &mut count)
+        };
+        if result < 0 {
+            kprint!("Failed to read directory: {}\n", path);
+            return Err(());
+        }
+
+        let mut buffer = ShimBuf::reuse(core::mem::take(entries), count, DirEntry::EMPTY)?;
+        let capacity = buffer.capacity();
+        let result = unsafe {
+            kernel_read_directory(path_bytes.as_ptr(), path_bytes.len(), buffer.as_mut_ptr(), capacity,
+                                  buffer.reported_mut())
+        };
+        if result < 0 {
+            kprint!("Failed to read directory: {}\n", path);
+            return Err(());
+        }
+
+        let grew = buffer.overflowed();
+        *entries = buffer.into_vec();
+        if !grew {
+            return Ok(());
+        }
+        // The directory grew between the calls, count again
+    }
+
+    kprint!("Directory {} keeps changing, listing may be incomplete\n", path);
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//! evdev event layout of the running kernel.
+//!
+//! Userspace reads `struct input_event` from evdev nodes, and its size
+//! depends on the ABI: 24 bytes with 64-bit time fields on 64-bit kernels, 16
+//! bytes for 32-bit processes going through the compat layer. A 32-bit
+//! library built against the wrong header, or a 64-bit kernel without compat
+//! support, misreads every event, which looks exactly like a driver sending
+//! garbage. The shim reports the layout the kernel was built with and whether
+//! it serves 32-bit clients; the layout is checked against the one this crate
+//! was built for, so a mismatched build is caught too.
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use crate::kprint;
+use crate::reason::Reason;
+use crate::shim;
+
+/// Size of a compat event: 32-bit seconds and microseconds, type, code and value.
+const COMPAT_EVENT_SIZE: u32 = 16;
+
+/// Layout as filled in by the shim. Mirrors `struct dv_evdev_layout`.
+#[repr(C)]
+pub struct EvdevLayout {
+    event_size: u32,
+    time_size: u32,
+    type_offset: u32,
+    code_offset: u32,
+    value_offset: u32,
+    big_endian: u32,
+    version: u32,
+    compat_event_size: u32,
+    x32: u32,
+}
+
+/// evdev ABI of the running kernel.
+#[derive(Debug, Clone)]
+pub struct EvdevAbi {
+    /// Size of `struct input_event` for native clients.
+    pub event_size: u32,
+    /// Size of each of the two time fields.
+    pub time_size: u32,
+    pub big_endian: bool,
+    /// `EV_VERSION` of the evdev protocol.
+    pub version: u32,
+    /// Size of the events 32-bit clients read, None without compat support.
+    pub compat_event_size: Option<u32>,
+    /// Whether x32 clients are supported; they read native events.
+    pub x32: bool,
+    /// Differences from the layout this crate was built for.
+    pub mismatches: Vec<Reason>,
+}
+
+impl EvdevAbi {
+    /// Lists how 32-bit clients are served, the usual source of mixed userspace breakage.
+    pub fn notes(&self) -> Vec<Reason> {
+        let mut notes = Vec::new();
+        if self.event_size > COMPAT_EVENT_SIZE {
+            match self.compat_event_size {
+                Some(_) => notes.push(Reason::CompatHeaderRisk),
+                None => notes.push(Reason::NoCompat),
+            }
+        }
+        notes
+    }
+
+    /// Writes the layout to the kernel log.
+    pub fn log(&self) {
+        kprint!("  evdev ABI:       {}-byte events, {}-byte time, {} endian, version {:#x}\n",
+                self.event_size, self.time_size, if self.big_endian { "big" } else { "little" }, self.version);
+        match self.compat_event_size {
+            Some(size) => kprint!("    compat: {}-byte events{}\n", size, if self.x32 { ", x32" } else { "" }),
+            None => kprint!("    compat: none\n"),
+        }
+        for mismatch in self.mismatches.iter() {
+            kprint!("    {}\n", mismatch);
+        }
+        for note in self.notes() {
+            kprint!("    {}\n", note);
+        }
+    }
+}
+
+/// Reads the evdev layout of the running kernel and checks it.
+///
+/// # Returns
+///
+/// * `Option<EvdevAbi>` - Layout and its mismatches, None if the shim is missing
+pub fn read() -> Option<EvdevAbi> {
+    let kernel_get_evdev_layout = shim::ops().get_evdev_layout?;
+
+    let mut layout = EvdevLayout {
+        event_size: 0,
+        time_size: 0,
+        type_offset: 0,
+        code_offset: 0,
+        value_offset: 0,
+        big_endian: 0,
+        version: 0,
+        compat_event_size: 0,
+        x32: 0,
+    };
+    unsafe { kernel_get_evdev_layout(&mut layout) };
+
+    // The crate targets the kernel's ABI: time fields are longs, then u16 type and code, s32 value
+    let word = size_of::<usize>() as u32;
+    let mut mismatches = Vec::new();
+    if layout.event_size != 2 * word + 8 {
+        mismatches.push(Reason::EventSizeMismatch);
+    }
+    if layout.time_size != word {
+        mismatches.push(Reason::TimeSizeMismatch);
+    }
+    if (layout.type_offset, layout.code_offset, layout.value_offset) != (2 * word, 2 * word + 2, 2 * word + 4) {
+        mismatches.push(Reason::FieldOffsetMismatch);
+    }
+    if (layout.big_endian != 0) != cfg!(target_endian = "big") {
+        mismatches.push(Reason::ByteOrderMismatch);
+    }
+    if layout.compat_event_size != 0 && layout.compat_event_size != COMPAT_EVENT_SIZE {
+        mismatches.push(Reason::CompatSizeMismatch);
+    }
+
+    Some(EvdevAbi {
+        event_size: layout.event_size,
+        time_size: layout.time_size,
+        big_endian: layout.big_endian != 0,
+        version: layout.version,
+        compat_event_size: (layout.compat_event_size != 0).then_some(layout.compat_event_size),
+        x32: layout.x32 != 0,
+        mismatches,
+    })
+}
@@ -0,0 +1,528 @@
+//! Passive input event monitor fed by the C input handler.
+//!
+//! The C side registers an input handler that connects to every input device and
+//! forwards each event to `rust_input_event`. Devices are bound to monitor slots by
+//! their evdev node name, so the verifier can ask how many events a given device
+//! produced without opening the node itself. A device may be bound to several
+//! slots, and a slot may aggregate several devices. The handler only opens a
+//! device while at least one slot is bound to it, so unmonitored devices can
+//! still runtime suspend.
+//!
+//! Events are delivered from interrupt context, so all monitor state is kept in
+//! atomics and nothing here allocates or logs on the event path.
+use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicU64, Ordering};
+
+use crate::notify::notify_syn_dropped;
+use crate::shim;
+use crate::stats::{Counter, STATS};
+
+/// Number of devices that can be monitored at the same time.
+pub const MAX_SLOTS: usize = 8;
+
+/// Slot used for the touchpad under verification.
+pub const TOUCHPAD_SLOT: u32 = 0;
+
+/// Slot the watchdog uses to passively monitor the touchpad.
+pub const WATCHDOG_TOUCHPAD_SLOT: u32 = 1;
+
+/// Slot the watchdog uses to monitor activity on other devices (keyboards).
+pub const WATCHDOG_ACTIVITY_SLOT: u32 = 2;
+
+/// Slot the event recorder captures events from.
+pub const RECORDER_SLOT: u32 = 3;
+
+/// Slot aggregating the keyboards while special function keys are confirmed.
+pub const FN_KEYS_SLOT: u32 = 4;
+
+/// Slot the guided self test follows the touchpad on. Apart from `TOUCHPAD_SLOT`,
+/// so a verification running during the self test doesn't reset its counters.
+pub const SELFTEST_SLOT: u32 = 5;
+
+/// Words of the pressed key bitmap, covering the key codes below 0x280.
+const KEY_WORDS: usize = 10;
+
+/// Interval between checks while waiting for events, in milliseconds.
+const POLL_INTERVAL_MS: u32 = 10;
+
+/// Event type for synchronization events (EV_SYN).
+pub const EV_SYN: u32 = 0x00;
+/// Event type for key and button events (EV_KEY).
+pub const EV_KEY: u32 = 0x01;
+/// Event type for absolute axis events (EV_ABS).
+pub const EV_ABS: u32 = 0x03;
+
+const ABS_X: u32 = 0x00;
+const ABS_Y: u32 = 0x01;
+const ABS_MT_SLOT: u32 = 0x2f;
+const ABS_MT_TOUCH_MAJOR: u32 = 0x30;
+const ABS_MT_POSITION_X: u32 = 0x35;
+const ABS_MT_POSITION_Y: u32 = 0x36;
+const ABS_MT_TOOL_TYPE: u32 = 0x37;
+const ABS_MT_TRACKING_ID: u32 = 0x39;
+const MT_TOOL_PALM: i32 = 0x02;
+const SYN_REPORT: u32 = 0x00;
+const SYN_DROPPED: u32 = 0x03;
+const BTN_LEFT: u32 = 0x110;
+const BTN_TOUCH: u32 = 0x14a;
+const BTN_TOOL_DOUBLETAP: u32 = 0x14d;
+
+/// Longest touch, in milliseconds, that is still recognized as a tap.
+const TAP_MAX_MS: u64 = 180;
+
+/// Most frames with motion a touch may contain and still be recognized as a tap.
+const TAP_MAX_MOTION_FRAMES: u32 = 3;
+
+/// Snapshot of the gesture related counters of a slot.
+///
+/// Counters only ever grow while a slot is attached, so a gesture is detected by
+/// comparing a snapshot taken before prompting with one taken afterwards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PatternCounts {
+    /// Absolute position updates (single or multi-touch axes).
+    pub motion: u32,
+    /// Frames with motion while two or more contacts were on the touchpad.
+    pub two_finger_motion: u32,
+    /// Presses of the left button, including clickpad clicks.
+    pub clicks: u32,
+    /// Short single-contact touches without significant motion.
+    pub taps: u32,
+    /// Short touches with two or more contacts without significant motion.
+    pub two_finger_taps: u32,
+    /// Highest number of simultaneous contacts seen.
+    pub max_contacts: u32,
+    /// Largest ABS_MT_TOUCH_MAJOR value reported.
+    pub max_touch_major: i32,
+    /// Touch size reports at or above the large-contact threshold.
+    pub large_contacts: u32,
+    /// Contacts the firmware flagged as MT_TOOL_PALM.
+    pub palm_reports: u32,
+}
+
+/// Per-slot event counters.
+///
+/// Besides plain counters, each slot tracks the multi-touch protocol B state of
+/// its device: the active MT slot, and a bitmask of MT slots with a valid tracking
+/// ID. At every SYN_REPORT the number of contacts is evaluated to recognize
+/// two-finger motion and taps.
+pub struct SlotState {
+    attached: AtomicBool,
+    events: AtomicU32,
+    syn_reports: AtomicU32,
+    last_event_ms: AtomicU64,
+    motion: AtomicU32,
+    two_finger_motion: AtomicU32,
+    clicks: AtomicU32,
+    taps: AtomicU32,
+    two_finger_taps: AtomicU32,
+    max_contacts: AtomicU32,
+    max_touch_major: AtomicI32,
+    large_contacts: AtomicU32,
+    palm_reports: AtomicU32,
+    large_touch_threshold: AtomicI32,
+    two_fingers_down: AtomicBool,
+    touching: AtomicBool,
+    mt_slot: AtomicU32,
+    mt_contacts: AtomicU32,
+    frame_motion: AtomicBool,
+    touch_start_ms: AtomicU64,
+    touch_max_contacts: AtomicU32,
+    touch_motion_frames: AtomicU32,
+    /// Codes of the keys pressed since the slot was attached.
+    keys_pressed: [AtomicU64; KEY_WORDS],
+}
+
+impl SlotState {
+    const fn new() -> Self {
+        Self {
+            attached: AtomicBool::new(false),
+            events: AtomicU32::new(0),
+            syn_reports: AtomicU32::new(0),
+            last_event_ms: AtomicU64::new(0),
+            motion: AtomicU32::new(0),
+            two_finger_motion: AtomicU32::new(0),
+            clicks: AtomicU32::new(0),
+            taps: AtomicU32::new(0),
+            two_finger_taps: AtomicU32::new(0),
+            max_contacts: AtomicU32::new(0),
+            max_touch_major: AtomicI32::new(0),
+            large_contacts: AtomicU32::new(0),
+            palm_reports: AtomicU32::new(0),
+            large_touch_threshold: AtomicI32::new(i32::MAX),
+            two_fingers_down: AtomicBool::new(false),
+            touching: AtomicBool::new(false),
+            mt_slot: AtomicU32::new(0),
+            mt_contacts: AtomicU32::new(0),
+            frame_motion: AtomicBool::new(false),
+            touch_start_ms: AtomicU64::new(0),
+            touch_max_contacts: AtomicU32::new(0),
+            touch_motion_frames: AtomicU32::new(0),
+            keys_pressed: [const { AtomicU64::new(0) }; KEY_WORDS],
+        }
+    }
+
+    /// Clears all counters and tracking state.
+    fn reset(&self) {
+        self.events.store(0, Ordering::Relaxed);
+        self.syn_reports.store(0, Ordering::Relaxed);
+        self.last_event_ms.store(0, Ordering::Relaxed);
+        self.motion.store(0, Ordering::Relaxed);
+        self.two_finger_motion.store(0, Ordering::Relaxed);
+        self.clicks.store(0, Ordering::Relaxed);
+        self.taps.store(0, Ordering::Relaxed);
+        self.two_finger_taps.store(0, Ordering::Relaxed);
+        self.max_contacts.store(0, Ordering::Relaxed);
+        self.max_touch_major.store(0, Ordering::Relaxed);
+        self.large_contacts.store(0, Ordering::Relaxed);
+        self.palm_reports.store(0, Ordering::Relaxed);
+        self.large_touch_threshold.store(i32::MAX, Ordering::Relaxed);
+        self.two_fingers_down.store(false, Ordering::Relaxed);
+        self.touching.store(false, Ordering::Relaxed);
+        self.mt_slot.store(0, Ordering::Relaxed);
+        self.mt_contacts.store(0, Ordering::Relaxed);
+        self.frame_motion.store(false, Ordering::Relaxed);
+        self.touch_start_ms.store(0, Ordering::Relaxed);
+        self.touch_max_contacts.store(0, Ordering::Relaxed);
+        self.touch_motion_frames.store(0, Ordering::Relaxed);
+        for word in self.keys_pressed.iter() {
+            word.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the number of contacts currently on the device.
+    ///
+    /// Uses the MT slot state where available and falls back to BTN_TOUCH and
+    /// BTN_TOOL_DOUBLETAP for single-touch and semi-MT devices.
+    fn contact_count(&self) -> u32 {
+        let mut contacts = self.mt_contacts.load(Ordering::Relaxed).count_ones();
+        if self.touching.load(Ordering::Relaxed) {
+            contacts = contacts.max(1);
+        }
+        if self.two_fingers_down.load(Ordering::Relaxed) {
+            contacts = contacts.max(2);
+        }
+        contacts
+    }
+
+    /// Evaluates a completed frame for two-finger motion and tap sequences.
+    fn end_frame(&self, now: u64) {
+        let contacts = self.contact_count();
+        let moved = self.frame_motion.swap(false, Ordering::Relaxed);
+
+        self.max_contacts.fetch_max(contacts, Ordering::Relaxed);
+        if contacts >= 2 && moved {
+            self.two_finger_motion.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let touch_start = self.touch_start_ms.load(Ordering::Relaxed);
+        if contacts > 0 {
+            if touch_start == 0 {
+                self.touch_start_ms.store(now.max(1), Ordering::Relaxed);
+                self.touch_max_contacts.store(contacts, Ordering::Relaxed);
+                self.touch_motion_frames.store(0, Ordering::Relaxed);
+            } else {
+                self.touch_max_contacts.fetch_max(contacts, Ordering::Relaxed);
+                if moved {
+                    self.touch_motion_frames.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        } else if touch_start != 0 {
+            // All contacts lifted: a short touch without motion is a tap
+            let duration = now.saturating_sub(touch_start);
+            let motion_frames = self.touch_motion_frames.load(Ordering::Relaxed);
+            if duration <= TAP_MAX_MS && motion_frames <= TAP_MAX_MOTION_FRAMES {
+                if self.touch_max_contacts.load(Ordering::Relaxed) >= 2 {
+                    self.two_finger_taps.fetch_add(1, Ordering::Relaxed);
+                } else {
+                    self.taps.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            self.touch_start_ms.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Monitor state shared between the verifier and the input handler.
+pub struct EventMonitor {
+    slots: [SlotState; MAX_SLOTS],
+}
+
+/// Global event monitor instance.
+///
+/// Unlike the verifier this is not behind an `Option`, since the input handler
+/// may deliver events at any time while the module is loaded.
+pub static EVENT_MONITOR: EventMonitor = EventMonitor::new();
+
+impl EventMonitor {
+    const fn new() -> Self {
+        Self {
+            slots: [const { SlotState::new() }; MAX_SLOTS],
+        }
+    }
+
+    /// Binds a monitor slot to an evdev node so its events are counted.
+    ///
+    /// # Arguments
+    ///
+    /// * `slot` - Monitor slot to bind
+    /// * `path` - Path to the device node, e.g. `/dev/input/event5`
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ()>` - Ok if the input handler is connected to the device, Err otherwise
+    pub fn attach(&self, slot: u32, path: &str) -> Result<(), ()> {
+        let state = self.slots.get(slot as usize).ok_or(())?;
+        let node = path.rsplit('/').next().unwrap_or(path);
+        let kernel_monitor_attach = shim::ops().monitor_attach.ok_or(())?;
+
+        let node_bytes = node.as_bytes();
+        if unsafe { kernel_monitor_attach(node_bytes.as_ptr(), node_bytes.len(), slot) } < 0 {
+            return Err(());
+        }
+
+        state.reset();
+        state.attached.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Releases a monitor slot so the input handler stops forwarding its events.
+    ///
+    /// # Arguments
+    ///
+    /// * `slot` - Monitor slot to release
+    pub fn detach(&self, slot: u32) {
+        let Some(state) = self.slots.get(slot as usize) else {
+            return;
+        };
+
+        if let Some(kernel_monitor_detach) = shim::ops().monitor_detach {
+            unsafe { kernel_monitor_detach(slot) };
+        }
+
+        state.attached.store(false, Ordering::Release);
+    }
+
+    /// Records an event delivered by the input handler.
+    ///
+    /// Called from interrupt context; must not block or allocate.
+    ///
+    /// # Arguments
+    ///
+    /// * `slot` - Slot the originating device is bound to
+    /// * `ev_type` - Event type (EV_*)
+    /// * `code` - Event code
+    /// * `value` - Event value
+    pub fn record(&self, slot: u32, ev_type: u32, code: u32, value: i32) {
+        let Some(state) = self.slots.get(slot as usize) else {
+            return;
+        };
+        if !state.attached.load(Ordering::Acquire) {
+            return;
+        }
+
+        // Frame markers alone are no sign of life, only the device's own events
+        // count as activity for the watchdog
+        let now = now_ms();
+        if ev_type == EV_SYN {
+            state.syn_reports.fetch_add(1, Ordering::Relaxed);
+        } else {
+            state.events.fetch_add(1, Ordering::Relaxed);
+            state.last_event_ms.store(now, Ordering::Relaxed);
+            STATS.inc(Counter::EventsObserved);
+        }
+
+        match (ev_type, code) {
+            (EV_SYN, SYN_REPORT) => state.end_frame(now),
+            (EV_SYN, SYN_DROPPED) => notify_syn_dropped(slot),
+            (EV_ABS, ABS_X | ABS_Y | ABS_MT_POSITION_X | ABS_MT_POSITION_Y) => {
+                state.motion.fetch_add(1, Ordering::Relaxed);
+                state.frame_motion.store(true, Ordering::Relaxed);
+            },
+            (EV_ABS, ABS_MT_SLOT) => {
+                state.mt_slot.store(value as u32, Ordering::Relaxed);
+            },
+            (EV_ABS, ABS_MT_TRACKING_ID) => {
+                let mt_slot = state.mt_slot.load(Ordering::Relaxed);
+                if mt_slot < u32::BITS {
+                    if value < 0 {
+                        state.mt_contacts.fetch_and(!(1 << mt_slot), Ordering::Relaxed);
+                    } else {
+                        state.mt_contacts.fetch_or(1 << mt_slot, Ordering::Relaxed);
+                    }
+                }
+            },
+            (EV_ABS, ABS_MT_TOUCH_MAJOR) => {
+                state.max_touch_major.fetch_max(value, Ordering::Relaxed);
+                if value >= state.large_touch_threshold.load(Ordering::Relaxed) {
+                    state.large_contacts.fetch_add(1, Ordering::Relaxed);
+                }
+            },
+            (EV_ABS, ABS_MT_TOOL_TYPE) if value == MT_TOOL_PALM => {
+                state.palm_reports.fetch_add(1, Ordering::Relaxed);
+            },
+            (EV_KEY, BTN_TOUCH) => {
+                state.touching.store(value != 0, Ordering::Relaxed);
+            },
+            (EV_KEY, BTN_TOOL_DOUBLETAP) => {
+                state.two_fingers_down.store(value != 0, Ordering::Relaxed);
+            },
+            (EV_KEY, BTN_LEFT) if value == 1 => {
+                state.clicks.fetch_add(1, Ordering::Relaxed);
+            },
+            _ => {}
+        }
+
+        if ev_type == EV_KEY && value == 1 {
+            if let Some(word) = state.keys_pressed.get(code as usize / 64) {
+                word.fetch_or(1 << (code % 64), Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Returns the time of the last event on a slot in milliseconds, 0 if none was seen.
+    pub fn last_event_ms(&self, slot: u32) -> u64 {
+        self.slots
+            .get(slot as usize)
+            .map(|state| state.last_event_ms.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// Returns a snapshot of the gesture counters of a slot.
+    pub fn pattern_counts(&self, slot: u32) -> PatternCounts {
+        let Some(state) = self.slots.get(slot as usize) else {
+            return PatternCounts::default();
+        };
+
+        PatternCounts {
+            motion: state.motion.load(Ordering::Relaxed),
+            two_finger_motion: state.two_finger_motion.load(Ordering::Relaxed),
+            clicks: state.clicks.load(Ordering::Relaxed),
+            taps: state.taps.load(Ordering::Relaxed),
+            two_finger_taps: state.two_finger_taps.load(Ordering::Relaxed),
+            max_contacts: state.max_contacts.load(Ordering::Relaxed),
+            max_touch_major: state.max_touch_major.load(Ordering::Relaxed),
+            large_contacts: state.large_contacts.load(Ordering::Relaxed),
+            palm_reports: state.palm_reports.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns true if a key was pressed on a slot since it was attached.
+    ///
+    /// # Arguments
+    ///
+    /// * `slot` - Monitor slot
+    /// * `code` - Key code (KEY_*)
+    pub fn key_pressed(&self, slot: u32, code: u32) -> bool {
+        self.slots
+            .get(slot as usize)
+            .and_then(|state| state.keys_pressed.get(code as usize / 64))
+            .is_some_and(|word| word.load(Ordering::Relaxed) & (1 << (code % 64)) != 0)
+    }
+
+    /// Sets the ABS_MT_TOUCH_MAJOR value from which a contact counts as implausibly large.
+    ///
+    /// Must be called after `attach`, which resets the threshold to disabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `slot` - Monitor slot
+    /// * `threshold` - Touch major value, `i32::MAX` to disable
+    pub fn set_large_touch_threshold(&self, slot: u32, threshold: i32) {
+        if let Some(state) = self.slots.get(slot as usize) {
+            state.large_touch_threshold.store(threshold, Ordering::Relaxed);
+        }
+    }
+
+    /// Waits up to `window_ms` for the device bound to `slot` to produce events.
+    ///
+    /// The wait returns as soon as at least one event and a following EV_SYN
+    /// report have been observed, so a responsive device does not have to sit
+    /// out the whole window.
+    ///
+    /// # Arguments
+    ///
+    /// * `slot` - Monitor slot to watch
+    /// * `window_ms` - Maximum time to wait in milliseconds
+    ///
+    /// # Returns
+    ///
+    /// * `u32` - Number of events observed during the wait
+    pub fn wait_for_events(&self, slot: u32, window_ms: u32) -> u32 {
+        let Some(state) = self.slots.get(slot as usize) else {
+            return 0;
+        };
+
+        let start_events = state.events.load(Ordering::Relaxed);
+        let start_syn = state.syn_reports.load(Ordering::Relaxed);
+        let mut waited = 0;
+
+        while waited < window_ms {
+            let events = state.events.load(Ordering::Relaxed).wrapping_sub(start_events);
+            let syn = state.syn_reports.load(Ordering::Relaxed).wrapping_sub(start_syn);
+            if events > 0 && syn > 0 {
+                return events;
+            }
+
+            sleep_ms(POLL_INTERVAL_MS);
+            waited += POLL_INTERVAL_MS;
+        }
+
+        state.events.load(Ordering::Relaxed).wrapping_sub(start_events)
+    }
+
+    /// Waits up to `window_ms` until no contact touches the device bound to `slot`.
+    ///
+    /// A contact reports its size over many frames, so data judged per contact,
+    /// like the largest touch size, is only complete once the contact lifted.
+    ///
+    /// # Arguments
+    ///
+    /// * `slot` - Monitor slot to watch
+    /// * `window_ms` - Maximum time to wait in milliseconds
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if every contact lifted within the window
+    pub fn wait_for_lift(&self, slot: u32, window_ms: u32) -> bool {
+        let Some(state) = self.slots.get(slot as usize) else {
+            return false;
+        };
+
+        let mut waited = 0;
+        loop {
+            if state.mt_contacts.load(Ordering::Relaxed) == 0 && !state.touching.load(Ordering::Relaxed) {
+                return true;
+            }
+            if waited >= window_ms {
+                return false;
+            }
+
+            sleep_ms(POLL_INTERVAL_MS);
+            waited += POLL_INTERVAL_MS;
+        }
+    }
+}
+
+/// Returns the monotonic kernel time in milliseconds.
+pub fn now_ms() -> u64 {
+    match shim::ops().get_time_ms {
+        Some(kernel_get_time_ms) => unsafe { kernel_get_time_ms() },
+        None => 0,
+    }
+}
+
+/// Returns the monotonic kernel time in microseconds.
+///
+/// Safe to call from interrupt context.
+pub fn now_us() -> u64 {
+    match shim::ops().get_time_us {
+        Some(kernel_get_time_us) => unsafe { kernel_get_time_us() },
+        None => 0,
+    }
+}
+
+/// Sleeps the calling thread for the given number of milliseconds.
+pub fn sleep_ms(ms: u32) {
+    if let Some(kernel_msleep) = shim::ops().msleep {
+        unsafe { kernel_msleep(ms) };
+    }
+}
@@ -0,0 +1,372 @@
+//! Declared expectations and per-device annotations.
+//!
+//! Fleet imaging needs to know whether a machine has the input devices its
+//! model should have, not only whether the ones present work. Userspace
+//! declares the expected state through the `expectations` sysfs attribute,
+//! one declaration per line:
+//!
+//! ```text
+//! # exactly one touchpad on an internal bus
+//! internal Touchpad = 1
+//! Keyboard >= 1
+//! Touchpad name ELAN*
+//! note 04f3:3140 = replaced under RMA 1234
+//! ```
+//!
+//! A count declaration compares the number of scanned devices of a type
+//! (named as in the reports) with `=`, `>=` or `<=`; `internal` only counts
+//! devices that are neither on USB or Bluetooth nor emulated by a hypervisor.
+//! A name declaration requires every device of the type to match a pattern,
+//! and a note attaches free text to the reports of the matching devices.
+//! Patterns are those of the device filter. Every verification checks the
+//! declarations against the last scan and reports the deviations.
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::device_filter::Pattern;
+use crate::input_verifier::{DeviceInfo, DeviceType};
+use crate::reason::Reason;
+
+/// How a declared count is compared with the scanned one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Exactly,
+    AtLeast,
+    AtMost,
+}
+
+impl Comparison {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Comparison::Exactly => "=",
+            Comparison::AtLeast => ">=",
+            Comparison::AtMost => "<=",
+        }
+    }
+
+    fn holds(&self, found: usize, expected: usize) -> bool {
+        match self {
+            Comparison::Exactly => found == expected,
+            Comparison::AtLeast => found >= expected,
+            Comparison::AtMost => found <= expected,
+        }
+    }
+}
+
+/// A declared expectation.
+enum Rule {
+    /// Number of scanned devices of a type.
+    Count { device_type: DeviceType, internal: bool, comparison: Comparison, count: usize },
+    /// Every scanned device of a type matches the pattern.
+    Name { device_type: DeviceType, pattern: Pattern },
+}
+
+/// Free text attached to the reports of matching devices.
+struct Annotation {
+    pattern: Pattern,
+    note: String,
+}
+
+/// A difference between the declared and the scanned state.
+#[derive(PartialEq, Eq)]
+pub struct Deviation {
+    /// Device path the deviation is about, "-" for a count.
+    pub subject: String,
+    pub reason: Reason,
+    /// The violated declaration and what was found instead, tab separated: the
+    /// number of devices for a count, the device name for a name declaration.
+    pub detail: String,
+}
+
+/// Declared expectations, annotations and the deviations of the last check.
+#[derive(Default)]
+pub struct Expectations {
+    rules: Vec<Rule>,
+    annotations: Vec<Annotation>,
+    deviations: Vec<Deviation>,
+}
+
+impl Rule {
+    /// Renders the rule in the syntax it is declared with.
+    fn render(&self) -> String {
+        match self {
+            Rule::Count { device_type, internal, comparison, count } => alloc::format!(
+                "{}{} {} {}", if *internal { "internal " } else { "" }, device_type.as_str(),
+                comparison.as_str(), count
+            ),
+            Rule::Name { device_type, pattern } => {
+                alloc::format!("{} name {}", device_type.as_str(), pattern.render())
+            },
+        }
+    }
+}
+
+/// Parses a count declaration such as `internal Touchpad >= 1`.
+fn parse_count(line: &str) -> Option<Rule> {
+    let (subject, comparison, count) = if let Some((subject, count)) = line.split_once(">=") {
+        (subject, Comparison::AtLeast, count)
+    } else if let Some((subject, count)) = line.split_once("<=") {
+        (subject, Comparison::AtMost, count)
+    } else {
+        let (subject, count) = line.split_once('=')?;
+        (subject, Comparison::Exactly, count)
+    };
+
+    let subject = subject.trim();
+    let (internal, name) = match subject.strip_prefix("internal ") {
+        Some(name) => (true, name.trim()),
+        None => (false, subject),
+    };
+    Some(Rule::Count {
+        device_type: DeviceType::from_name(name)?,
+        internal,
+        comparison,
+        count: count.trim().parse().ok()?,
+    })
+}
+
+/// Parses one declaration line into the rule or annotation lists.
+fn parse_line(line: &str, rules: &mut Vec<Rule>, annotations: &mut Vec<Annotation>) -> Option<()> {
+    if let Some(rest) = line.strip_prefix("note ") {
+        let (pattern, note) = rest.split_once('=')?;
+        let note = note.trim();
+        if note.is_empty() {
+            return None;
+        }
+        annotations.push(Annotation { pattern: Pattern::parse(pattern)?, note: note.to_string() });
+        return Some(());
+    }
+
+    if let Some((name, pattern)) = line.split_once(" name ") {
+        rules.push(Rule::Name {
+            device_type: DeviceType::from_name(name.trim())?,
+            pattern: Pattern::parse(pattern)?,
+        });
+        return Some(());
+    }
+
+    rules.push(parse_count(line)?);
+    Some(())
+}
+
+impl Expectations {
+    /// Replaces the declarations.
+    ///
+    /// The text is parsed completely before anything is replaced, so invalid
+    /// declarations leave the previous ones in place. Blank lines and lines
+    /// starting with `#` are skipped; an empty text clears the declarations.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Declarations, one per line
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), usize>` - Ok if every declaration is valid, Err with the 1-based number
+    ///   of the first invalid line otherwise
+    pub fn declare(&mut self, text: &str) -> Result<(), usize> {
+        let mut rules = Vec::new();
+        let mut annotations = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            parse_line(line, &mut rules, &mut annotations).ok_or(number + 1)?;
+        }
+
+        self.rules = rules;
+        self.annotations = annotations;
+        self.deviations.clear();
+        Ok(())
+    }
+
+    /// Returns true if nothing is declared.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.annotations.is_empty()
+    }
+
+    /// Renders the declarations in the syntax they are declared with.
+    pub fn describe(&self) -> String {
+        let mut out = String::new();
+        for rule in self.rules.iter() {
+            let _ = writeln!(out, "{}", rule.render());
+        }
+        for annotation in self.annotations.iter() {
+            let _ = writeln!(out, "note {} = {}", annotation.pattern.render(), annotation.note);
+        }
+        out
+    }
+
+    /// Checks the declarations against the scanned devices.
+    ///
+    /// # Arguments
+    ///
+    /// * `devices` - Devices found by the last scan
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<&Deviation>` - Deviations that the previous check didn't report
+    pub fn check(&mut self, devices: &[DeviceInfo]) -> Vec<&Deviation> {
+        let mut deviations = Vec::new();
+        for rule in self.rules.iter() {
+            match rule {
+                Rule::Count { device_type, internal, comparison, count } => {
+                    let found = devices.iter()
+                        .filter(|device| device.device_type == *device_type && (!internal || device.is_internal()))
+                        .count();
+                    if !comparison.holds(found, *count) {
+                        deviations.push(Deviation {
+                            subject: "-".to_string(),
+                            reason: Reason::CountMismatch,
+                            detail: alloc::format!("{}\t{}", rule.render(), found),
+                        });
+                    }
+                },
+                Rule::Name { device_type, pattern } => {
+                    for device in devices.iter().filter(|device| device.device_type == *device_type) {
+                        if !pattern.matches(&device.raw_name, device.vendor, device.product) {
+                            deviations.push(Deviation {
+                                subject: device.path.clone(),
+                                reason: Reason::NameMismatch,
+                                detail: alloc::format!("{}\t{}", rule.render(), device.name),
+                            });
+                        }
+                    }
+                },
+            }
+        }
+
+        let previous = core::mem::replace(&mut self.deviations, deviations);
+        self.deviations.iter()
+            .filter(|deviation| !previous.contains(deviation))
+            .collect()
+    }
+
+    /// Returns the deviations found by the last check.
+    pub fn deviations(&self) -> &[Deviation] {
+        &self.deviations
+    }
+
+    /// Returns the notes attached to a device, joined by "; ", None if none matches.
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - Device the report is about
+    pub fn note_for(&self, device: &DeviceInfo) -> Option<String> {
+        let notes: Vec<&str> = self.annotations.iter()
+            .filter(|annotation| annotation.pattern.matches(&device.raw_name, device.vendor, device.product))
+            .map(|annotation| annotation.note.as_str())
+            .collect();
+        (!notes.is_empty()).then(|| notes.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input_verifier::FirmwareInfo;
+    use crate::verify_all::DeviceSlot;
+
+    /// Bus types of an internal and an external device.
+    const BUS_I2C: u16 = 0x18;
+    const BUS_USB: u16 = 0x03;
+
+    fn device(raw_name: &[u8], device_type: DeviceType, bustype: u16) -> DeviceInfo {
+        DeviceInfo {
+            name: crate::text::escape_lossy(raw_name).into_owned(),
+            raw_name: raw_name.to_vec(),
+            path: "/dev/input/event0".to_string(),
+            device_type,
+            vendor: 0x04f3,
+            product: 0x3140,
+            bustype,
+            stale: false,
+            by_path: None,
+            by_id: None,
+            udev: None,
+            props: None,
+            virtual_device: false,
+            seat: None,
+            firmware: FirmwareInfo::default(),
+            slot: DeviceSlot::new(),
+        }
+    }
+
+    #[test]
+    fn declare_skips_comments_and_blank_lines() {
+        let mut expectations = Expectations::default();
+        let text = "# exactly one touchpad on an internal bus\n\
+                    internal Touchpad = 1\n\
+                    \n\
+                    \x20 Keyboard >= 1  \n\
+                    Mouse <= 2\n\
+                    Touchpad name ELAN*\n\
+                    note 04f3:3140 = replaced under RMA 1234\n";
+        assert_eq!(expectations.declare(text), Ok(()));
+        assert_eq!(
+            expectations.describe(),
+            "internal Touchpad = 1\nKeyboard >= 1\nMouse <= 2\nTouchpad name ELAN*\n\
+             note 04f3:3140 = replaced under RMA 1234\n"
+        );
+    }
+
+    #[test]
+    fn declare_reports_the_first_invalid_line() {
+        let mut expectations = Expectations::default();
+        assert_eq!(expectations.declare("# comment\nTouchpad = 1\nTablet = 1\nMouse = x\n"), Err(3));
+        assert_eq!(expectations.declare("Touchpad = one"), Err(1));
+        assert_eq!(expectations.declare("Touchpad"), Err(1));
+        assert_eq!(expectations.declare("Touchpad name  "), Err(1));
+        assert_eq!(expectations.declare("note = text"), Err(1));
+        assert_eq!(expectations.declare("note ELAN* =  "), Err(1));
+        assert_eq!(expectations.declare("note ELAN*"), Err(1));
+        assert!(expectations.is_empty());
+    }
+
+    #[test]
+    fn declare_keeps_the_previous_declarations_on_error() {
+        let mut expectations = Expectations::default();
+        assert_eq!(expectations.declare("Touchpad = 1"), Ok(()));
+        assert_eq!(expectations.declare("Keyboard = 1\nbogus"), Err(2));
+        assert_eq!(expectations.describe(), "Touchpad = 1\n");
+
+        assert_eq!(expectations.declare("\n# nothing\n"), Ok(()));
+        assert!(expectations.is_empty());
+    }
+
+    #[test]
+    fn check_counts_internal_devices_and_reports_new_deviations_once() {
+        let mut expectations = Expectations::default();
+        assert_eq!(expectations.declare("internal Touchpad = 1"), Ok(()));
+        let devices = [
+            device(b"ELAN0501:00 04F3:3140 Touchpad", DeviceType::Touchpad, BUS_I2C),
+            device(b"USB Touchpad", DeviceType::Touchpad, BUS_USB),
+        ];
+        assert!(expectations.check(&devices).is_empty());
+
+        let deviations = expectations.check(&[]);
+        assert_eq!(deviations.len(), 1);
+        assert_eq!(deviations[0].reason, Reason::CountMismatch);
+        assert_eq!(deviations[0].detail, "internal Touchpad = 1\t0");
+        assert!(expectations.check(&[]).is_empty());
+        assert_eq!(expectations.deviations().len(), 1);
+    }
+
+    #[test]
+    fn check_matches_names_and_notes_against_raw_bytes() {
+        let mut expectations = Expectations::default();
+        assert_eq!(expectations.declare("Touchpad name ELAN? Touchpad\nnote xff = escaped\nnote ELAN = raw"), Ok(()));
+        // The escaped name reads "ELAN\xff Touchpad", four bytes where the raw name has one
+        let devices = [device(b"ELAN\xff Touchpad", DeviceType::Touchpad, BUS_I2C)];
+        assert!(expectations.check(&devices).is_empty());
+        assert_eq!(expectations.note_for(&devices[0]).as_deref(), Some("raw"));
+
+        let devices = [device(b"SYNA Touchpad", DeviceType::Touchpad, BUS_I2C)];
+        let deviations = expectations.check(&devices);
+        assert_eq!(deviations.len(), 1);
+        assert_eq!(deviations[0].reason, Reason::NameMismatch);
+        assert_eq!(deviations[0].subject, "/dev/input/event0");
+    }
+}
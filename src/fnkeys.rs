@@ -0,0 +1,135 @@
+//! Special function key readout.
+//!
+//! "Everything works except the Fn keys" is a frequent laptop complaint.
+//! Brightness, volume, airplane mode and keyboard backlight keys reach
+//! userspace through several input devices: the keyboard itself, the ACPI
+//! video bus and vendor WMI or platform hotkey drivers. A key only works if one
+//! of them maps it, which depends on the platform driver being loaded and on
+//! its keymap matching the firmware. The key bitmaps of all keyboard-like
+//! devices are merged and the expected keys no device maps are listed in the
+//! report. Keyboard backlight keys are only expected if the machine has a
+//! keyboard backlight LED. The interactive profile also asks for a key press
+//! and records which special keys generated events.
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::event_monitor::{now_ms, sleep_ms, EVENT_MONITOR, FN_KEYS_SLOT};
+use crate::kprint;
+
+const KEY_MUTE: u32 = 113;
+const KEY_VOLUMEDOWN: u32 = 114;
+const KEY_VOLUMEUP: u32 = 115;
+const KEY_BRIGHTNESSDOWN: u32 = 224;
+const KEY_BRIGHTNESSUP: u32 = 225;
+const KEY_KBDILLUMTOGGLE: u32 = 228;
+const KEY_KBDILLUMDOWN: u32 = 229;
+const KEY_KBDILLUMUP: u32 = 230;
+const KEY_WLAN: u32 = 238;
+const KEY_RFKILL: u32 = 247;
+
+/// Name suffix of keyboard backlight LEDs in /sys/class/leds, e.g. "asus::kbd_backlight".
+pub const KBD_BACKLIGHT_LED: &str = "::kbd_backlight";
+
+/// Interval between checks while waiting for a key press, in milliseconds.
+const POLL_INTERVAL_MS: u32 = 10;
+
+/// A special key, mapped if any device advertises one of its codes.
+pub struct SpecialKey {
+    pub name: &'static str,
+    pub codes: &'static [u32],
+    /// Only expected on machines with a keyboard backlight.
+    pub kbd_backlight: bool,
+}
+
+impl SpecialKey {
+    const fn new(name: &'static str, codes: &'static [u32], kbd_backlight: bool) -> Self {
+        Self { name, codes, kbd_backlight }
+    }
+}
+
+/// Special keys expected on a laptop keyboard.
+pub const SPECIAL_KEYS: &[SpecialKey] = &[
+    SpecialKey::new("mute", &[KEY_MUTE], false),
+    SpecialKey::new("volume down", &[KEY_VOLUMEDOWN], false),
+    SpecialKey::new("volume up", &[KEY_VOLUMEUP], false),
+    SpecialKey::new("brightness down", &[KEY_BRIGHTNESSDOWN], false),
+    SpecialKey::new("brightness up", &[KEY_BRIGHTNESSUP], false),
+    // Platform drivers send either, rfkill handles both the same
+    SpecialKey::new("airplane mode", &[KEY_RFKILL, KEY_WLAN], false),
+    SpecialKey::new("keyboard backlight", &[KEY_KBDILLUMTOGGLE, KEY_KBDILLUMDOWN, KEY_KBDILLUMUP], true),
+];
+
+/// Mapping of the special keys of a laptop keyboard.
+#[derive(Debug, Clone, Default)]
+pub struct FnKeyCheck {
+    /// Devices whose key bitmaps were merged.
+    pub sources: Vec<String>,
+    /// Whether a keyboard backlight LED exists.
+    pub kbd_backlight: bool,
+    /// Expected keys no device maps.
+    pub unmapped: Vec<&'static str>,
+    /// Special keys pressed during the interactive confirmation, None if it didn't run.
+    pub pressed: Option<Vec<&'static str>>,
+}
+
+impl FnKeyCheck {
+    /// Lists the expected keys no device maps.
+    ///
+    /// # Arguments
+    ///
+    /// * `sources` - Names of the devices whose key bitmaps are merged
+    /// * `kbd_backlight` - Whether the machine has a keyboard backlight LED
+    /// * `mapped` - Returns true if any of the devices advertises a key code
+    pub fn new(sources: Vec<String>, kbd_backlight: bool, mapped: impl Fn(u32) -> bool) -> Self {
+        let unmapped = SPECIAL_KEYS.iter()
+            .filter(|key| kbd_backlight || !key.kbd_backlight)
+            .filter(|key| !key.codes.iter().any(|&code| mapped(code)))
+            .map(|key| key.name)
+            .collect();
+        Self { sources, kbd_backlight, unmapped, pressed: None }
+    }
+
+    /// Returns true if every expected key is mapped.
+    pub fn complete(&self) -> bool {
+        self.unmapped.is_empty()
+    }
+
+    /// Asks the user to press special keys and records which ones generated events.
+    ///
+    /// The keyboards must be attached to `FN_KEYS_SLOT`. Returns once every
+    /// mapped key was pressed or the window elapsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_ms` - How long to wait for key presses
+    pub fn confirm(&mut self, window_ms: u32) {
+        let expected: Vec<&SpecialKey> = SPECIAL_KEYS.iter()
+            .filter(|key| (self.kbd_backlight || !key.kbd_backlight) && !self.unmapped.contains(&key.name))
+            .collect();
+        let was_pressed = |key: &SpecialKey| key.codes.iter().any(|&code| EVENT_MONITOR.key_pressed(FN_KEYS_SLOT, code));
+
+        kprint!("Press the brightness, volume and airplane mode keys now ({} ms)...\n", window_ms);
+        let deadline = now_ms() + window_ms as u64;
+        while now_ms() < deadline && !expected.iter().all(|key| was_pressed(key)) {
+            sleep_ms(POLL_INTERVAL_MS);
+        }
+
+        self.pressed = Some(expected.into_iter().filter(|key| was_pressed(key)).map(|key| key.name).collect());
+    }
+
+    /// Writes the readout to the kernel log.
+    pub fn log(&self) {
+        kprint!("  function keys:   {}\n", if self.complete() { "all mapped" } else { "incomplete" });
+        kprint!("    sources: {}\n", if self.sources.is_empty() { "none".into() } else { self.sources.join(", ") });
+        if self.kbd_backlight {
+            kprint!("    keyboard backlight present\n");
+        }
+        if !self.unmapped.is_empty() {
+            kprint!("    unmapped: {} (platform hotkey driver missing or keymap mismatch)\n",
+                    self.unmapped.join(", "));
+        }
+        if let Some(pressed) = self.pressed.as_ref() {
+            kprint!("    pressed: {}\n", if pressed.is_empty() { "none".into() } else { pressed.join(", ") });
+        }
+    }
+}
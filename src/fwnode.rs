@@ -0,0 +1,179 @@
+//! Firmware (device tree or ACPI) description of a touchpad.
+//!
+//! On boards describing the touchpad in DT or ACPI, the driver finds its
+//! interrupt and reset line through the firmware node. A wrong GPIO controller
+//! reference, a missing pinctrl or a driver that never requests the interrupt
+//! leaves a device that probes but never reports anything, and no event-level
+//! check can tell why. The shim reads the node of the closest parent of the
+//! input device that has one (e.g. the i2c client of an i2c-hid touchpad) and
+//! checks that every described resource resolves and is claimed by a driver.
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::kprint;
+use crate::reason::Reason;
+use crate::shim;
+use crate::text;
+
+/// Longest compatible string or ACPI id the shim copies, must match `DV_FW_ID_MAX` in driver_verifier.h.
+pub const FW_ID_MAX: usize = 32;
+
+/// Kind of firmware node describing a device. The numeric values match `DV_FW_*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FwKind {
+    DeviceTree,
+    Acpi,
+}
+
+impl FwKind {
+    fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            1 => Some(FwKind::DeviceTree),
+            2 => Some(FwKind::Acpi),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FwKind::DeviceTree => "dt",
+            FwKind::Acpi => "acpi",
+        }
+    }
+}
+
+/// State of a resource the firmware node may describe. The numeric values match `DV_RES_*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceState {
+    /// Not described.
+    Absent,
+    /// Described, but doesn't resolve, e.g. its controller is missing.
+    Missing,
+    /// Resolves, but no driver claimed it.
+    Unclaimed,
+    /// Resolves and a driver claimed it.
+    Claimed,
+}
+
+impl ResourceState {
+    fn from_raw(raw: u32) -> Self {
+        match raw {
+            1 => ResourceState::Missing,
+            2 => ResourceState::Unclaimed,
+            3 => ResourceState::Claimed,
+            _ => ResourceState::Absent,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResourceState::Absent => "not described",
+            ResourceState::Missing => "missing",
+            ResourceState::Unclaimed => "unclaimed",
+            ResourceState::Claimed => "claimed",
+        }
+    }
+}
+
+/// Description as filled in by the shim. Mirrors `struct dv_fw_resources`.
+#[repr(C)]
+pub struct FwResources {
+    kind: u32,
+    id_len: u32,
+    id: [u8; FW_ID_MAX],
+    irq: u32,
+    irq_state: u32,
+    irq_gpio: u32,
+    reset_gpio: u32,
+}
+
+/// Firmware description of a device and the state of its resources.
+#[derive(Debug, Clone)]
+pub struct FwDescription {
+    pub kind: FwKind,
+    /// First compatible string or ACPI hardware id, if any.
+    pub id: Option<String>,
+    /// Linux IRQ number of the described interrupt, 0 if none is mapped.
+    pub irq: u32,
+    pub irq_state: ResourceState,
+    pub irq_gpio: ResourceState,
+    pub reset_gpio: ResourceState,
+}
+
+impl FwDescription {
+    /// Lists the described resources that are missing or that no driver claimed.
+    pub fn mismatches(&self) -> Vec<Reason> {
+        let mut mismatches = Vec::new();
+        match self.irq_state {
+            ResourceState::Missing => mismatches.push(Reason::IrqNotMapped),
+            ResourceState::Unclaimed => mismatches.push(Reason::IrqUnclaimed),
+            _ => {},
+        }
+        if self.irq_gpio == ResourceState::Missing {
+            mismatches.push(Reason::IrqGpioMissing);
+        }
+        match self.reset_gpio {
+            ResourceState::Missing => mismatches.push(Reason::ResetGpioMissing),
+            ResourceState::Unclaimed => mismatches.push(Reason::ResetGpioUnclaimed),
+            _ => {},
+        }
+        mismatches
+    }
+
+    /// Writes the description to the kernel log.
+    pub fn log(&self) {
+        kprint!("  firmware node:   {} {}\n", self.kind.as_str(), self.id.as_deref().unwrap_or("-"));
+        if self.irq > 0 {
+            kprint!("    interrupt {}: {}\n", self.irq, self.irq_state.as_str());
+        } else {
+            kprint!("    interrupt: {}\n", self.irq_state.as_str());
+        }
+        kprint!("    interrupt GPIO: {}, reset GPIO: {}\n", self.irq_gpio.as_str(), self.reset_gpio.as_str());
+        for mismatch in self.mismatches() {
+            kprint!("    {}\n", mismatch);
+        }
+    }
+}
+
+/// Reads the firmware description of a device.
+///
+/// # Arguments
+///
+/// * `path` - Path to the device node
+///
+/// # Returns
+///
+/// * `Result<Option<FwDescription>, ()>` - Description, None if no firmware node describes
+///   the device, Err if the device is unknown or the shim is missing
+pub fn read(path: &str) -> Result<Option<FwDescription>, ()> {
+    let kernel_get_fw_resources = shim::ops().get_fw_resources.ok_or(())?;
+
+    let path_bytes = path.as_bytes();
+    let mut res = FwResources {
+        kind: 0,
+        id_len: 0,
+        id: [0; FW_ID_MAX],
+        irq: 0,
+        irq_state: 0,
+        irq_gpio: 0,
+        reset_gpio: 0,
+    };
+    if unsafe { kernel_get_fw_resources(path_bytes.as_ptr(), path_bytes.len(), &mut res) } < 0 {
+        return Err(());
+    }
+
+    let Some(kind) = FwKind::from_raw(res.kind) else {
+        return Ok(None);
+    };
+    let id = res.id.get(..res.id_len as usize)
+        .filter(|id| !id.is_empty())
+        .map(|id| text::escape_lossy(id).into_owned());
+    Ok(Some(FwDescription {
+        kind,
+        id,
+        irq: res.irq,
+        irq_state: ResourceState::from_raw(res.irq_state),
+        irq_gpio: ResourceState::from_raw(res.irq_gpio),
+        reset_gpio: ResourceState::from_raw(res.reset_gpio),
+    }))
+}
@@ -0,0 +1,81 @@
+//! Exclusive device grabs.
+//!
+//! A client holding a grab on an input device (EVIOCGRAB through evdev, or an
+//! in-kernel handler) receives all of its events, so every other consumer sees
+//! a dead device. Key remappers and some games grab touchpads, which looks
+//! exactly like a driver bug to the user. The verifier reports who holds a grab
+//! and, when the `exclusive_grab` parameter is set, grabs the touchpad itself
+//! during the event check, confirming events reach the verifier without any
+//! other consumer in between.
+use alloc::string::String;
+
+use crate::kprint;
+use crate::shim;
+use crate::text;
+
+/// Returns the name of the handler holding a grab on a device, if any.
+///
+/// Grabs by userspace clients through evdev show up as "evdev".
+///
+/// # Arguments
+///
+/// * `path` - Path to the device node
+///
+/// # Returns
+///
+/// * `Result<Option<String>, ()>` - Grabbing handler, None if the device is not grabbed, Err
+///   if the device is unknown
+pub fn holder(path: &str) -> Result<Option<String>, ()> {
+    let kernel_get_grab = shim::ops().get_grab.ok_or(())?;
+
+    let path_bytes = path.as_bytes();
+    let mut buffer = [0u8; 64];
+    let mut holder_len: usize = 0;
+    let result = unsafe {
+        kernel_get_grab(path_bytes.as_ptr(), path_bytes.len(), buffer.as_mut_ptr(), buffer.len(), &mut holder_len)
+    };
+
+    match result {
+        0 => Ok(None),
+        result if result > 0 => {
+            let name = buffer.get(..holder_len).unwrap_or(&[]);
+            Ok(Some(text::escape_lossy(name).into_owned()))
+        },
+        _ => Err(()),
+    }
+}
+
+/// A grab held by the verifier, released when dropped.
+pub struct ExclusiveGrab<'a> {
+    path: &'a str,
+}
+
+impl<'a> ExclusiveGrab<'a> {
+    /// Grabs a device, so its events go to the verifier only.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the device node
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, ()>` - The grab, or Err if another client holds one or the device is unknown
+    pub fn acquire(path: &'a str) -> Result<Self, ()> {
+        let kernel_grab_node = shim::ops().grab_node.ok_or(())?;
+        let path_bytes = path.as_bytes();
+        if unsafe { kernel_grab_node(path_bytes.as_ptr(), path_bytes.len(), 1) } < 0 {
+            return Err(());
+        }
+        kprint!("Grabbed {} for exclusive event delivery\n", path);
+        Ok(Self { path })
+    }
+}
+
+impl Drop for ExclusiveGrab<'_> {
+    fn drop(&mut self) {
+        let path_bytes = self.path.as_bytes();
+        if let Some(kernel_grab_node) = shim::ops().grab_node {
+            unsafe { kernel_grab_node(path_bytes.as_ptr(), path_bytes.len(), 0) };
+        }
+    }
+}
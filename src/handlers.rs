@@ -0,0 +1,107 @@
+//! Input handlers attached to a device.
+//!
+//! A driver can work perfectly while nothing reads its events: the evdev node
+//! may not be opened by any client (no libinput, no X driver), or the device
+//! may only be bound to legacy handlers such as mousedev. The verifier lists the
+//! handlers the input core connected to a device and whether each of them has
+//! the device open, which for evdev means at least one userspace client holds
+//! the node open. Which clients those are is private to evdev and can't be
+//! read from outside of it. The verifier's own handle is left out.
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::shim;
+use crate::text;
+
+/// Longest handler and handle name the shim copies, must match `DV_HANDLER_NAME_MAX` in driver_verifier.h.
+const HANDLER_NAME_MAX: usize = 32;
+
+/// Most handlers listed per device; devices rarely have more than four.
+const MAX_HANDLERS: usize = 8;
+
+/// A handler entry as filled in by the shim. Mirrors `struct dv_handler_entry`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct HandlerEntry {
+    open: u32,
+    handler_len: u32,
+    handle_len: u32,
+    handler: [u8; HANDLER_NAME_MAX],
+    handle: [u8; HANDLER_NAME_MAX],
+}
+
+impl HandlerEntry {
+    const EMPTY: Self = Self {
+        open: 0,
+        handler_len: 0,
+        handle_len: 0,
+        handler: [0; HANDLER_NAME_MAX],
+        handle: [0; HANDLER_NAME_MAX],
+    };
+}
+
+/// An input handler connected to a device.
+#[derive(Debug, Clone)]
+pub struct DeviceHandler {
+    /// Name of the handler, e.g. "evdev", "mousedev" or "leds".
+    pub handler: String,
+    /// Name of the handle, usually the node it created, e.g. "event5" or "mouse0".
+    pub handle: String,
+    /// Whether the handler has the device open, i.e. something reads its events.
+    pub open: bool,
+}
+
+/// Lists the handlers connected to a device.
+///
+/// # Arguments
+///
+/// * `path` - Path to the device node
+///
+/// # Returns
+///
+/// * `Result<Vec<DeviceHandler>, ()>` - Connected handlers, or Err if the device is unknown
+pub fn list(path: &str) -> Result<Vec<DeviceHandler>, ()> {
+    let kernel_get_handlers = shim::ops().get_handlers.ok_or(())?;
+
+    let path_bytes = path.as_bytes();
+    let mut entries = [HandlerEntry::EMPTY; MAX_HANDLERS];
+    let mut count: usize = 0;
+    let result = unsafe {
+        kernel_get_handlers(path_bytes.as_ptr(), path_bytes.len(), entries.as_mut_ptr(), entries.len(), &mut count)
+    };
+    if result < 0 {
+        return Err(());
+    }
+
+    let handlers = entries.iter()
+        .take(count)
+        .map(|entry| DeviceHandler {
+            handler: name(&entry.handler, entry.handler_len),
+            handle: name(&entry.handle, entry.handle_len),
+            open: entry.open != 0,
+        })
+        .collect();
+    Ok(handlers)
+}
+
+/// Converts a name filled in by the shim into a string.
+fn name(bytes: &[u8], len: u32) -> String {
+    text::escape_lossy(bytes.get(..len as usize).unwrap_or(bytes)).into_owned()
+}
+
+/// Returns true if any of the handlers has the device open.
+pub fn any_open(handlers: &[DeviceHandler]) -> bool {
+    handlers.iter().any(|handler| handler.open)
+}
+
+/// Formats handlers for the log, e.g. "evdev event5 (open), mousedev mouse0".
+pub fn describe(handlers: &[DeviceHandler]) -> String {
+    if handlers.is_empty() {
+        return "none".into();
+    }
+    handlers.iter()
+        .map(|handler| alloc::format!("{} {}{}", handler.handler, handler.handle,
+                                      if handler.open { " (open)" } else { "" }))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,118 @@
+//! Structured log of notable incidents.
+//!
+//! The kernel log mixes the verifier's incidents with every scan and report
+//! line, and rotates them out quickly. Incidents that matter when looking back
+//! at a session (a touchpad vanished, a freeze was suspected, a recovery was
+//! attempted, a verification regressed) are kept here with a monotonic
+//! timestamp instead. The log is bounded: once full, the oldest incident is
+//! dropped and counted. It is read through debugfs and cleared through the
+//! `DV_IOC_CLEAR_INCIDENTS` ioctl.
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use core::fmt::Write;
+
+use crate::event_monitor::now_ms;
+use crate::reason::Reason;
+
+/// Number of incidents the log holds.
+pub const INCIDENT_CAPACITY: usize = 64;
+
+/// Kinds of incidents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IncidentKind {
+    /// The touchpad disappeared or its node was reused since the last scan.
+    DeviceGone,
+    /// The watchdog suspects the touchpad froze.
+    FreezeSuspected,
+    /// A recovery action was taken on a failing touchpad.
+    RecoveryAttempted,
+    /// A touchpad that was working failed its verification.
+    VerificationRegressed,
+    /// The scanned devices deviate from the declared expectations.
+    ExpectationViolated,
+}
+
+impl IncidentKind {
+    /// Returns the name used in the debugfs output.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IncidentKind::DeviceGone => "device-gone",
+            IncidentKind::FreezeSuspected => "freeze-suspected",
+            IncidentKind::RecoveryAttempted => "recovery-attempted",
+            IncidentKind::VerificationRegressed => "verification-regressed",
+            IncidentKind::ExpectationViolated => "expectation-violated",
+        }
+    }
+}
+
+/// A recorded incident.
+pub struct Incident {
+    /// Monotonic time of the incident in milliseconds.
+    pub time_ms: u64,
+    pub kind: IncidentKind,
+    /// Device path the incident is about.
+    pub subject: String,
+    /// What was found, None if there is no finding, e.g. for a successful recovery.
+    pub reason: Option<Reason>,
+    /// Tab separated values specific to the kind, may be empty; never English text.
+    pub detail: String,
+}
+
+/// Bounded log of incidents, oldest first.
+pub struct IncidentLog {
+    entries: VecDeque<Incident>,
+    /// Incidents dropped since the last clear because the log was full.
+    dropped: u64,
+}
+
+impl IncidentLog {
+    /// Creates an empty log.
+    pub fn new() -> Self {
+        Self { entries: VecDeque::new(), dropped: 0 }
+    }
+
+    /// Records an incident, dropping the oldest one if the log is full.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - Kind of incident
+    /// * `subject` - Device path the incident is about
+    /// * `reason` - What was found, if anything
+    /// * `detail` - Tab separated values specific to the kind, may be empty
+    pub fn record(&mut self, kind: IncidentKind, subject: &str, reason: Option<Reason>, detail: String) {
+        if self.entries.len() == INCIDENT_CAPACITY {
+            self.entries.pop_front();
+            self.dropped += 1;
+        }
+        self.entries.push_back(Incident { time_ms: now_ms(), kind, subject: subject.into(), reason, detail });
+    }
+
+    /// Removes all incidents.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.dropped = 0;
+    }
+
+    /// Renders one line per incident: time in ms, kind, subject, reason code ("-" if none)
+    /// and details, tab separated.
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - String to append the log to
+    pub fn render(&self, out: &mut String) {
+        if self.dropped > 0 {
+            let _ = writeln!(out, "# {} older incidents dropped", self.dropped);
+        }
+        for incident in self.entries.iter() {
+            let _ = write!(out, "{}\t{}\t{}\t", incident.time_ms, incident.kind.as_str(), incident.subject);
+            let _ = match incident.reason {
+                Some(reason) => write!(out, "{}", reason.code()),
+                None => write!(out, "-"),
+            };
+            if !incident.detail.is_empty() {
+                let _ = write!(out, "\t{}", incident.detail);
+            }
+            let _ = writeln!(out);
+        }
+    }
+}
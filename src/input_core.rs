@@ -0,0 +1,193 @@
+//! Enumeration of input devices through the input core.
+//!
+//! The scan normally lists /sys/class/input, but minimal initramfs images
+//! and some namespaced environments don't mount sysfs, or mount one that
+//! doesn't show the input class. The input core itself always knows its
+//! devices: the event monitor is connected to every one of them, so the shim
+//! can walk those connections and report the evdev node, name, id and
+//! capability bitmaps of each device. The udev seat, firmware attributes and
+//! physical path are still read from sysfs when it is there.
+//!
+//! The walk is also selectable as the scan backend. It copies the bitmaps
+//! straight from the `input_dev` instead of opening and parsing a handful of
+//! sysfs files per device, so it is faster, and each device is read in one
+//! step under the input core's locks, so a device unplugged during the scan
+//! can't leave a half-read entry behind.
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::shim;
+use crate::text;
+use crate::udev::{Bitmap, InputBits};
+
+/// Source of the scanned devices. The numeric values identify backends over FFI
+/// and must match `DV_ENUM_*` in driver_verifier_ioctl.h.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Enumeration {
+    /// Lists /sys/class/input and reads the attributes of each node.
+    Sysfs = 0,
+    /// Walks the devices registered with the input core.
+    InputCore = 1,
+}
+
+impl Enumeration {
+    /// Converts a raw backend id received over FFI.
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(Enumeration::Sysfs),
+            1 => Some(Enumeration::InputCore),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the backend.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Enumeration::Sysfs => "sysfs",
+            Enumeration::InputCore => "input-core",
+        }
+    }
+
+    /// Returns true if the shim supports the backend.
+    pub fn is_supported(&self) -> bool {
+        match self {
+            Enumeration::Sysfs => true,
+            Enumeration::InputCore => shim::ops().next_input_device.is_some(),
+        }
+    }
+}
+
+/// Longest node name the shim copies, must match `DV_INPUT_NODE_MAX` in driver_verifier.h.
+const NODE_NAME_MAX: usize = 16;
+
+/// Longest device name the shim copies, must match `DV_INPUT_NAME_MAX` in driver_verifier.h.
+const DEVICE_NAME_MAX: usize = 128;
+
+/// Number of event types, keys, relative and absolute axes and properties, from the kernel's
+/// `EV_CNT`, `KEY_CNT`, `REL_CNT`, `ABS_CNT` and `INPUT_PROP_CNT`.
+const EV_CNT: usize = 0x20;
+const KEY_CNT: usize = 0x300;
+const REL_CNT: usize = 0x10;
+const ABS_CNT: usize = 0x40;
+const INPUT_PROP_CNT: usize = 0x20;
+
+/// Returns the number of `long` words of a bitmap, like the kernel's `BITS_TO_LONGS`.
+const fn words(bits: usize) -> usize {
+    bits.div_ceil(usize::BITS as usize)
+}
+
+/// A device as filled in by the shim. Mirrors `struct dv_input_device`.
+#[repr(C)]
+pub struct InputDeviceEntry {
+    node_len: u32,
+    name_len: u32,
+    node: [u8; NODE_NAME_MAX],
+    name: [u8; DEVICE_NAME_MAX],
+    bustype: u16,
+    vendor: u16,
+    product: u16,
+    version: u16,
+    evbit: [usize; words(EV_CNT)],
+    keybit: [usize; words(KEY_CNT)],
+    relbit: [usize; words(REL_CNT)],
+    absbit: [usize; words(ABS_CNT)],
+    propbit: [usize; words(INPUT_PROP_CNT)],
+}
+
+impl InputDeviceEntry {
+    const EMPTY: Self = Self {
+        node_len: 0,
+        name_len: 0,
+        node: [0; NODE_NAME_MAX],
+        name: [0; DEVICE_NAME_MAX],
+        bustype: 0,
+        vendor: 0,
+        product: 0,
+        version: 0,
+        evbit: [0; words(EV_CNT)],
+        keybit: [0; words(KEY_CNT)],
+        relbit: [0; words(REL_CNT)],
+        absbit: [0; words(ABS_CNT)],
+        propbit: [0; words(INPUT_PROP_CNT)],
+    };
+
+    /// Returns the capability bitmaps of the device.
+    fn bits(&self) -> InputBits {
+        InputBits {
+            ev: Bitmap::from_words(&self.evbit),
+            key: Bitmap::from_words(&self.keybit),
+            rel: Bitmap::from_words(&self.relbit),
+            abs: Bitmap::from_words(&self.absbit),
+            prop: Bitmap::from_words(&self.propbit),
+        }
+    }
+}
+
+/// An input device registered with the input core.
+#[derive(Debug, Clone)]
+pub struct CoreDevice {
+    /// Name of the evdev node, e.g. "event5".
+    pub node: String,
+    /// Raw device name, for matching.
+    pub name: Vec<u8>,
+    pub bustype: u16,
+    pub vendor: u16,
+    pub product: u16,
+    pub version: u16,
+    /// Capability bitmaps, copied from the `input_dev`.
+    pub bits: InputBits,
+}
+
+impl CoreDevice {
+    /// Returns the path to the device node.
+    pub fn path(&self) -> String {
+        alloc::format!("/dev/input/{}", self.node)
+    }
+}
+
+/// Lists the input devices that have an evdev node.
+///
+/// Devices connected or removed during the walk may be missed, a device is
+/// never listed twice.
+///
+/// # Returns
+///
+/// * `Result<Vec<CoreDevice>, ()>` - Registered devices, Err if the shim is missing
+pub fn list() -> Result<Vec<CoreDevice>, ()> {
+    let kernel_next_input_device = shim::ops().next_input_device.ok_or(())?;
+
+    let mut devices: Vec<CoreDevice> = Vec::new();
+    let mut cursor: u32 = 0;
+    loop {
+        let mut entry = InputDeviceEntry::EMPTY;
+        let previous = cursor;
+        if unsafe { kernel_next_input_device(&mut cursor, &mut entry) } <= 0 {
+            break;
+        }
+        // The cursor must move on, or a broken shim would keep the scan here forever
+        if cursor <= previous {
+            break;
+        }
+
+        let node = text::escape_lossy(bounded(&entry.node, entry.node_len)).into_owned();
+        if devices.iter().any(|device| device.node == node) {
+            continue;
+        }
+        devices.push(CoreDevice {
+            node,
+            name: bounded(&entry.name, entry.name_len).to_vec(),
+            bustype: entry.bustype,
+            vendor: entry.vendor,
+            product: entry.product,
+            version: entry.version,
+            bits: entry.bits(),
+        });
+    }
+    Ok(devices)
+}
+
+/// Returns the part of a field the shim filled in, never more than the field.
+fn bounded(bytes: &[u8], len: u32) -> &[u8] {
+    bytes.get(..len as usize).unwrap_or(bytes)
+}
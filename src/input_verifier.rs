@@ -4,20 +4,273 @@ use core::fmt;
 use alloc::vec::Vec;
 use alloc::string::{String, ToString};
 
+use crate::boot::BootRetry;
+use crate::cache::ResultCache;
+use crate::config::{self, DvConfig, Verbosity};
+use crate::device_filter::DeviceFilter;
+use crate::dir::{self, DirEntry};
+use crate::evdev_abi::{self, EvdevAbi};
+use crate::event_monitor::{now_ms, now_us, sleep_ms, EVENT_MONITOR, FN_KEYS_SLOT, TOUCHPAD_SLOT};
+use crate::expect::Expectations;
+use crate::fnkeys::{self, FnKeyCheck};
+use crate::fwnode;
+use crate::grab::{self, ExclusiveGrab};
+use crate::handlers;
+use crate::input_core::{self, Enumeration};
+use crate::incidents::{IncidentKind, IncidentLog};
+use crate::kbuf::ShimBuf;
+use crate::kmsg;
+use crate::kprint;
+use crate::matcher;
+use crate::metrics;
+use crate::notify::{notify, Notification};
+use crate::pool::{StringPool, DEFAULT_POOL_BYTES, DEFAULT_POOL_ENTRIES};
+use crate::profile::Profile;
+use crate::quirks;
+use crate::recorder::{RecorderCommand, RECORDER};
+use crate::reason::{Reason, Token};
+use crate::recovery;
+use crate::report::{
+    EventCheckResult, MultitouchCheck, MultitouchMode, PalmCheck, PhaseTimings, RecoveryAction, RecoveryAttempt,
+    ResponsiveCheck, StepOutcome, VerificationReport,
+};
+use crate::safety::{GuardedAction, SafetyPolicy};
+use crate::seat;
+use crate::selftest::{Selftest, SelftestCommand};
+use crate::shim;
+use crate::snapshot::{Difference, Snapshot};
+use crate::stable_name;
+use crate::stats::{Counter, STATS};
+use crate::text;
+use crate::trace::{self, ClassifyMethod, Phase};
+use crate::trackpoint::{self, TrackpointInfo};
+use crate::udev::{Bitmap, InputBits, InputProps, UdevClass};
+use crate::verify_all::{DeviceSlot, DeviceVerdict};
+use crate::virt;
+use crate::watchdog::Watchdog;
+
+/// Default length of the interactive event wait window in milliseconds.
+pub const DEFAULT_EVENT_WINDOW_MS: u32 = 5000;
+
+/// sysfs class directory of the input device nodes.
+const SYS_CLASS_INPUT: &str = "/sys/class/input";
+
+/// Modules a PS/2 touchpad needs, as named under /sys/module.
+const PS2_TOUCHPAD_MODULES: &[&str] = &["psmouse"];
+
+/// Firmware attributes exposed by touchpad drivers on the physical device, e.g.
+/// `firmware_version` by elan_i2c and elantech, `firmware_id` by psmouse.
+const FIRMWARE_VERSION_ATTRS: &[&str] = &["firmware_version", "fw_version"];
+const FIRMWARE_ID_ATTRS: &[&str] = &["firmware_id", "fw_id"];
+
+/// Modules an I2C HID touchpad needs, as named under /sys/module.
+const I2C_HID_TOUCHPAD_MODULES: &[&str] = &["i2c_hid", "hid_multitouch"];
+
+const ABS_X: u32 = 0x00;
+const ABS_PRESSURE: u32 = 0x18;
+const ABS_MT_TOUCH_MAJOR: u32 = 0x30;
+const ABS_MT_TOUCH_MINOR: u32 = 0x31;
+const ABS_MT_POSITION_X: u32 = 0x35;
+const ABS_MT_TOOL_TYPE: u32 = 0x37;
+const ABS_MT_PRESSURE: u32 = 0x3a;
+
+/// Bus types of removable devices (BUS_USB, BUS_BLUETOOTH).
+const BUS_USB: u16 = 0x03;
+const BUS_BLUETOOTH: u16 = 0x05;
+
 /// Type of input device
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DeviceType {
     Touchpad,
     Keyboard,
     Mouse,
+    /// TrackPoint or other pointing stick; a mouse to userspace, but tuned through psmouse.
+    PointingStick,
+    /// Touch device mapped to a screen (INPUT_PROP_DIRECT); never a touchpad candidate.
+    Touchscreen,
+    /// Accelerometer or other sensor exposed as an input device; never a touchpad candidate.
+    Sensor,
     Unknown,
 }
 
+impl DeviceType {
+    /// Returns the name of the device type.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceType::Touchpad => "Touchpad",
+            DeviceType::Keyboard => "Keyboard",
+            DeviceType::Mouse => "Mouse",
+            DeviceType::PointingStick => "PointingStick",
+            DeviceType::Touchscreen => "Touchscreen",
+            DeviceType::Sensor => "Sensor",
+            DeviceType::Unknown => "Unknown",
+        }
+    }
+
+    /// Parses a device type name as returned by `as_str`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Touchpad" => Some(DeviceType::Touchpad),
+            "Keyboard" => Some(DeviceType::Keyboard),
+            "Mouse" => Some(DeviceType::Mouse),
+            "PointingStick" => Some(DeviceType::PointingStick),
+            "Touchscreen" => Some(DeviceType::Touchscreen),
+            "Sensor" => Some(DeviceType::Sensor),
+            "Unknown" => Some(DeviceType::Unknown),
+            _ => None,
+        }
+    }
+}
+
 /// Input device information
 pub struct DeviceInfo {
     pub name: String,
+    /// Name as the driver reported it, before escaping. Filters, rules and notes match it.
+    pub raw_name: Vec<u8>,
     pub path: String,
     pub device_type: DeviceType,
+    /// Vendor id of the device, 0 if unknown.
+    pub vendor: u16,
+    /// Product id of the device, 0 if unknown.
+    pub product: u16,
+    /// Bus type of the device (BUS_*), 0 if unknown.
+    pub bustype: u16,
+    /// Set when the node no longer exists or now belongs to a different device.
+    pub stale: bool,
+    /// udev style `/dev/input/by-path` name, if the device has a stable address.
+    pub by_path: Option<String>,
+    /// udev style `/dev/input/by-id` name, if the bus type is known.
+    pub by_id: Option<String>,
+    /// Properties udev assigns to the device, None if the capabilities can't be read.
+    pub udev: Option<UdevClass>,
+    /// Input properties the driver sets, None if they can't be read.
+    pub props: Option<InputProps>,
+    /// Set when the device is emulated by the hypervisor the kernel runs under.
+    pub virtual_device: bool,
+    /// Seat udev assigned the device to, None if udev has no record of it.
+    pub seat: Option<String>,
+    /// Firmware and product version information.
+    pub firmware: FirmwareInfo,
+    /// Result of the last concurrent verification of all devices.
+    pub slot: DeviceSlot,
+}
+
+/// Firmware and product version information of a device.
+#[derive(Debug, Clone, Default)]
+pub struct FirmwareInfo {
+    /// Version from the input id (the HID version for i2c-hid devices), 0 if unknown.
+    pub id_version: u16,
+    /// Firmware version exposed by the driver, if any.
+    pub version: Option<String>,
+    /// Firmware id exposed by the driver (e.g. the PNP id of a PS/2 touchpad), if any.
+    pub id: Option<String>,
+}
+
+impl FirmwareInfo {
+    /// Returns true if nothing is known about the firmware.
+    pub fn is_empty(&self) -> bool {
+        self.id_version == 0 && self.version.is_none() && self.id.is_none()
+    }
+}
+
+impl DeviceInfo {
+    /// Returns the name that identifies the device across reboots.
+    ///
+    /// The `by-path` name is preferred since it stays unique when several
+    /// identical devices are connected; the `by-id` name is used otherwise.
+    pub fn stable_name(&self) -> Option<&str> {
+        self.by_path.as_deref().or(self.by_id.as_deref())
+    }
+
+    /// Returns true if the device sits on an internal bus: neither on USB or
+    /// Bluetooth nor emulated by a hypervisor.
+    pub fn is_internal(&self) -> bool {
+        !self.virtual_device && self.bustype != BUS_USB && self.bustype != BUS_BLUETOOTH
+    }
+}
+
+/// A touchpad found by the last scan and the outcome of its verification.
+pub struct Touchpad {
+    pub name: String,
+    pub path: String,
+    /// Report of the last verification run, None if not verified yet.
+    pub report: Option<VerificationReport>,
+    /// Why the last verification produced no result, so `report` is from an earlier
+    /// run; None if it produced one.
+    pub error: Option<VerifyError>,
+}
+
+impl Touchpad {
+    /// Returns the working flag of the last verification, None if not verified yet.
+    pub fn working(&self) -> Option<bool> {
+        self.report.as_ref().map(|report| report.working)
+    }
+
+    /// Returns the result of the last verification, None if not verified yet.
+    pub fn result(&self) -> Option<Result<bool, VerifyError>> {
+        match self.error {
+            Some(error) => Some(Err(error)),
+            None => self.working().map(Ok),
+        }
+    }
+}
+
+/// Reasons a verification did not produce a result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The verification could not run.
+    Failed,
+    /// The touchpad node no longer exists or now belongs to a different device.
+    DeviceGone,
+    /// No touchpad was found, and none is expected in a virtual machine.
+    NotExpected,
+    /// /sys/class/input is unavailable, as in a minimal initramfs or a namespace.
+    EnvironmentUnsupported,
+}
+
+impl VerifyError {
+    /// Returns the stable reason code of the error.
+    pub fn reason(&self) -> Reason {
+        match self {
+            VerifyError::Failed => Reason::VerificationFailed,
+            VerifyError::DeviceGone => Reason::DeviceGone,
+            VerifyError::NotExpected => Reason::NotExpected,
+            VerifyError::EnvironmentUnsupported => Reason::EnvironmentUnsupported,
+        }
+    }
+
+    /// Returns the status reported over FFI, must match `DV_VERIFY_*` in driver_verifier.h.
+    pub fn status(&self) -> i32 {
+        match self {
+            VerifyError::Failed => -1,
+            VerifyError::DeviceGone => -2,
+            VerifyError::NotExpected => -3,
+            VerifyError::EnvironmentUnsupported => -4,
+        }
+    }
+}
+
+/// Capability bitmaps of an input device
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeviceCapabilities {
+    pub abs: u64,
+    pub rel: u32,
+    pub key: u32,
+}
+
+impl DeviceCapabilities {
+    /// Returns true if the absolute axis `code` is supported.
+    pub fn has_abs(&self, code: u32) -> bool {
+        code < u64::BITS && (self.abs & (1 << code)) != 0
+    }
+}
+
+/// Range of an absolute axis
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AbsInfo {
+    pub minimum: i32,
+    pub maximum: i32,
+    pub resolution: i32,
 }
 
 /// Represents a verifier for Linux input devices with focus on touchpad verification.
@@ -25,10 +278,54 @@ pub struct DeviceInfo {
 /// This struct maintains state about discovered input devices and their functionality,
 /// particularly focused on touchpad devices for debugging purposes.
 pub struct InputDeviceVerifier {
-    touchpad_found: bool,
-    touchpad_working: bool,
-    touchpad_path: Option<String>,
-    touchpad_name: Option<String>,
+    /// Touchpads found by the last scan. Single-device features (self test,
+    /// watchdog, recorder) use the first one.
+    touchpads: Vec<Touchpad>,
+    devices: Vec<DeviceInfo>,
+    event_window_ms: u32,
+    profile: Profile,
+    /// Bits of the phases enabled on top of the profile, indexed by `Phase`.
+    phases: u32,
+    verbosity: Verbosity,
+    /// Actions that change device state the verifier may take, checked by `permits`.
+    safety: SafetyPolicy,
+    recovery_enabled: bool,
+    /// Pulse the reset GPIO of a touchpad that stays unresponsive.
+    reset_gpio_enabled: bool,
+    load_missing_modules: bool,
+    /// Grab the touchpad while waiting for events, so no other consumer gets them.
+    exclusive_grab: bool,
+    /// Devices to skip or to limit the scan to.
+    filter: DeviceFilter,
+    /// Seat the scan is limited to, None for every seat.
+    seat: Option<String>,
+    /// Hypervisor the kernel runs under, None on bare metal.
+    hypervisor: Option<String>,
+    /// evdev event layout of the running kernel, read once since it can't change.
+    evdev_abi: Option<EvdevAbi>,
+    /// Backend the scan enumerates the devices with.
+    enumeration: Enumeration,
+    /// Whether /sys/class/input was available at the last scan; without it the
+    /// devices are enumerated through the input core and can't be verified.
+    sysfs_input: bool,
+    /// Retry state of the boot-time verification, None unless boot mode is enabled.
+    boot: Option<BootRetry>,
+    /// The scan was deferred to the first status query.
+    scan_pending: bool,
+    /// Result of the last verification, reused by polling callers while fresh.
+    cache: ResultCache,
+    /// Preallocated storage for the directory listing of a scan.
+    pool: StringPool,
+    /// Entry buffer the scan lists /sys/class/input into, reused across scans.
+    dir_entries: Vec<DirEntry>,
+    /// Differences found by the last snapshot comparison, None if none was run.
+    comparison: Option<Vec<Difference>>,
+    /// Notable incidents, kept apart from the kernel log.
+    incidents: IncidentLog,
+    /// State declared by userspace, checked on every verification.
+    expectations: Expectations,
+    selftest: Selftest,
+    watchdog: Watchdog,
 }
 
 impl InputDeviceVerifier {
@@ -39,400 +336,2459 @@ impl InputDeviceVerifier {
     ///
     /// # Returns
     ///
-    /// * `Result<Self, ()>` - A new verifier instance wrapped in Ok, or Err if initialization fails
-    pub fn new() -> Result<Self, ()> {
-        kprint!("Initializing InputDeviceVerifier\n");
-        
-        Ok(Self {
-            touchpad_found: false,
-            touchpad_working: false,
-            touchpad_path: None,
-            touchpad_name: None,
+    /// * `Result<Self, ()>` - A new verifier instance wrapped in Ok, or Err if initialization fails
+    pub fn new() -> Result<Self, ()> {
+        kprint!("Initializing InputDeviceVerifier\n");
+        
+        Ok(Self {
+            touchpads: Vec::new(),
+            devices: Vec::new(),
+            event_window_ms: DEFAULT_EVENT_WINDOW_MS,
+            profile: Profile::Standard,
+            phases: config::ALL_PHASES,
+            verbosity: Verbosity::Normal,
+            safety: SafetyPolicy::Repair,
+            recovery_enabled: false,
+            reset_gpio_enabled: false,
+            load_missing_modules: false,
+            exclusive_grab: false,
+            filter: DeviceFilter::default(),
+            seat: None,
+            hypervisor: virt::detect_hypervisor(),
+            evdev_abi: evdev_abi::read(),
+            enumeration: Enumeration::Sysfs,
+            sysfs_input: true,
+            boot: None,
+            scan_pending: false,
+            cache: ResultCache::new(),
+            pool: StringPool::new(DEFAULT_POOL_BYTES, DEFAULT_POOL_ENTRIES)?,
+            dir_entries: Vec::new(),
+            comparison: None,
+            incidents: IncidentLog::new(),
+            expectations: Expectations::default(),
+            selftest: Selftest::new(),
+            watchdog: Watchdog::new(),
+        })
+    }
+
+    /// Configures how the input event check is performed.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_ms` - How long to wait for events in interactive mode
+    /// * `interactive` - If true, select the interactive profile, which prompts the
+    ///   user and waits for real events; otherwise select the standard profile
+    pub fn configure_event_check(&mut self, window_ms: u32, interactive: bool) {
+        self.event_window_ms = window_ms;
+        self.profile = if interactive { Profile::Interactive } else { Profile::Standard };
+        self.cache.invalidate();
+    }
+
+    /// Selects the verification profile used by the next verifications.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - Profile selecting the phases that run and the event window
+    pub fn configure_profile(&mut self, profile: Profile) {
+        kprint!("Verification profile: {}\n", profile.as_str());
+        self.profile = profile;
+        self.cache.invalidate();
+    }
+
+    /// Selects the backend the scan enumerates the devices with.
+    ///
+    /// # Arguments
+    ///
+    /// * `enumeration` - Backend used from the next scan on
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ()>` - Ok if the shim supports the backend, Err otherwise
+    pub fn configure_enumeration(&mut self, enumeration: Enumeration) -> Result<(), ()> {
+        if !enumeration.is_supported() {
+            return Err(());
+        }
+        kprint!("Device enumeration: {}\n", enumeration.as_str());
+        self.enumeration = enumeration;
+        self.cache.invalidate();
+        Ok(())
+    }
+
+    /// Sets the safety policy limiting the actions that change device state.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Least restrictive policy the next verifications may act under
+    pub fn configure_safety(&mut self, policy: SafetyPolicy) {
+        kprint!("Safety policy: {}\n", policy.as_str());
+        self.safety = policy;
+        self.cache.invalidate();
+    }
+
+    /// Checks an action that changes device state against the safety policy.
+    ///
+    /// Every such action is gated here, so a refused one is logged the same way
+    /// wherever it was requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - Action about to be taken
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the policy allows the action
+    fn permits(&self, action: GuardedAction) -> bool {
+        let permitted = self.safety.permits(action);
+        if !permitted {
+            kprint!("Safety policy {} refuses the {}\n", self.safety.as_str(), action.as_str());
+        }
+        permitted
+    }
+
+    /// Enables or disables automatic recovery of unresponsive touchpads.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - If true, a touchpad whose driver is bound but unresponsive
+    ///   gets its driver rebound and is verified again
+    pub fn configure_recovery(&mut self, enabled: bool) {
+        self.recovery_enabled = enabled;
+    }
+
+    /// Enables or disables the reset GPIO pulse for unresponsive touchpads.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - If true, a touchpad whose driver is bound but unresponsive and whose
+    ///   firmware node describes a reset GPIO is reset through it and verified again
+    pub fn configure_reset_gpio(&mut self, enabled: bool) {
+        self.reset_gpio_enabled = enabled;
+    }
+
+    /// Enables or disables loading of missing touchpad modules.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - If true, required modules found missing are requested and
+    ///   the touchpad is verified again once it reappears
+    pub fn configure_module_loading(&mut self, enabled: bool) {
+        self.load_missing_modules = enabled;
+    }
+
+    /// Enables or disables the exclusive grab during the event check.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - If true, the touchpad is grabbed while the event check waits for
+    ///   events, confirming they reach the verifier without other consumers
+    pub fn configure_exclusive_grab(&mut self, enabled: bool) {
+        self.exclusive_grab = enabled;
+    }
+
+    /// Configures which devices the scan considers.
+    ///
+    /// Takes effect on the next scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `ignore` - Comma separated name or `vendor:product` patterns of devices to skip
+    /// * `only` - Comma separated patterns of the only devices to consider; empty for all
+    pub fn configure_device_filter(&mut self, ignore: &str, only: &str) {
+        self.filter = DeviceFilter::new(ignore, only);
+        self.cache.invalidate();
+        if !self.filter.is_empty() {
+            kprint!("Device filter: {}\n", self.filter.describe());
+        }
+    }
+
+    /// Limits the scan to the devices of one seat.
+    ///
+    /// Takes effect on the next scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `seat` - Seat name such as "seat1", empty for every seat
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ()>` - Err if the seat name is not valid, keeping the previous scope
+    pub fn configure_seat(&mut self, seat: &str) -> Result<(), ()> {
+        let seat = seat.trim();
+        if seat.is_empty() {
+            self.seat = None;
+        } else if seat::is_valid(seat) {
+            kprint!("Limiting the scan to {}\n", seat);
+            self.seat = Some(seat.into());
+        } else {
+            kprint!("Invalid seat name {}\n", seat);
+            return Err(());
+        }
+        self.cache.invalidate();
+        Ok(())
+    }
+
+    /// Renders the seat the scan is limited to.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Seat name, or "all" if the scan is not limited
+    pub fn seat_status(&self) -> String {
+        alloc::format!("{}\n", self.seat.as_deref().unwrap_or("all"))
+    }
+
+    /// Sets how long a verification result is reused by polling callers.
+    ///
+    /// # Arguments
+    ///
+    /// * `ttl_ms` - Time to live of a cached result in milliseconds, 0 to always verify
+    pub fn configure_cache(&mut self, ttl_ms: u32) {
+        self.cache.configure(ttl_ms);
+    }
+
+    /// Resizes the string pool used by scans.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - Capacity for string data in bytes
+    /// * `entries` - Capacity in number of strings
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ()>` - Ok if the pool was allocated, Err if the allocation fails, in
+    ///   which case the previous pool is kept
+    pub fn configure_pool(&mut self, bytes: usize, entries: usize) -> Result<(), ()> {
+        self.pool = StringPool::new(bytes, entries)?;
+        Ok(())
+    }
+
+    /// Enables the deferred boot-time verification.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry_ms` - How long a missing touchpad is retried before the final verdict
+    pub fn configure_boot_verify(&mut self, retry_ms: u32) {
+        self.boot = Some(BootRetry::new(retry_ms));
+    }
+
+    /// Defers the scan and verification to the first status query.
+    ///
+    /// Lets the module load without touching any device; the first query pays
+    /// for the scan and its result is cached like any other.
+    pub fn configure_lazy_scan(&mut self) {
+        self.scan_pending = true;
+    }
+
+    /// Runs one attempt of the boot-time verification.
+    ///
+    /// Scans for devices and verifies the touchpads. If no touchpad is present
+    /// yet, or it went away during the attempt, another attempt is requested
+    /// until the retry period is over, then the final verdict is logged.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<u32>` - Delay in milliseconds before the next attempt, or None once the
+    ///   final verdict was issued
+    pub fn boot_verify_attempt(&mut self) -> Option<u32> {
+        let attempt = self.boot.as_mut()?.record_attempt();
+        kprint!("Boot verification attempt {}\n", attempt);
+
+        let result = match self.scan_devices() {
+            Ok(_) => self.verify_touchpad(),
+            Err(_) => Err(self.scan_error()),
+        };
+
+        let missing = result == Err(VerifyError::DeviceGone)
+            || (result == Ok(false) && self.touchpads.is_empty());
+        if missing {
+            if let Some(delay) = self.boot.as_mut().and_then(|boot| boot.next_delay()) {
+                kprint!("Touchpad not present yet, retrying in {} ms\n", delay);
+                return Some(delay);
+            }
+        }
+
+        match result {
+            Ok(working) => kprint!("Boot verification verdict after {} attempts: {}\n", attempt,
+                                   if working { "working" } else { "not working or not found" }),
+            Err(error) => kprint!("Boot verification verdict after {} attempts: {}\n", attempt, error.reason()),
+        }
+        self.boot = None;
+        None
+    }
+
+    /// Configures the touchpad freeze watchdog.
+    ///
+    /// The watchdog starts monitoring after the next scan that finds a touchpad.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether the watchdog runs
+    /// * `freeze_ms` - Touchpad silence period after which a freeze is suspected
+    /// * `reverify` - Whether a suspected freeze triggers a re-verification
+    pub fn configure_watchdog(&mut self, enabled: bool, freeze_ms: u32, reverify: bool) {
+        self.watchdog.configure(enabled, freeze_ms, reverify);
+    }
+
+    /// Applies a configuration update pushed at runtime.
+    ///
+    /// All the fields the update sets are validated first, so it is applied
+    /// entirely or not at all. The changes take effect with the next verification.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration update
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ()>` - Ok if the update was applied, Err if a field is invalid
+    pub fn apply_config(&mut self, config: &DvConfig) -> Result<(), ()> {
+        if let Err(field) = config.validate() {
+            kprint!("Rejected configuration update: invalid {}\n", field);
+            return Err(());
+        }
+
+        if config.has(config::CONFIG_EVENT_WINDOW) {
+            self.event_window_ms = config.event_window_ms;
+        }
+        if config.has(config::CONFIG_FREEZE) {
+            self.watchdog.set_freeze_ms(config.freeze_ms);
+        }
+        if config.has(config::CONFIG_CACHE_TTL) {
+            self.cache.configure(config.cache_ttl_ms);
+        }
+        if config.has(config::CONFIG_VERBOSITY) {
+            self.verbosity = Verbosity::from_raw(config.verbosity).unwrap_or(Verbosity::Normal);
+        }
+        if config.has(config::CONFIG_PHASES) {
+            self.phases = config.phases;
+        }
+        if config.has(config::CONFIG_ENUMERATION) {
+            self.enumeration = Enumeration::from_raw(config.enumeration).unwrap_or(Enumeration::Sysfs);
+        }
+        self.cache.invalidate();
+        kprint!("Applied configuration update (fields {:#x})\n", config.fields);
+        Ok(())
+    }
+
+    /// Returns true if the given phase runs, as selected by the profile and the enabled phases.
+    fn runs(&self, phase: Phase) -> bool {
+        self.profile.runs(phase) && config::phase_enabled(self.phases, phase)
+    }
+
+    /// Records the duration of a phase that just ended.
+    ///
+    /// # Arguments
+    ///
+    /// * `timings` - Timings of the running verification, usually those of its report
+    /// * `phase` - Phase that ended
+    /// * `started` - Monotonic time the phase started at
+    fn end_phase(&self, timings: &mut PhaseTimings, phase: Phase, started: u64) {
+        timings.record(phase, started);
+        if self.verbosity >= Verbosity::Verbose {
+            kprint!("Phase {} ended after {} us\n", phase.as_str(), timings.get(phase).unwrap_or(0));
+        }
+    }
+
+    /// Returns the number of touchpads found by the last scan.
+    pub fn touchpad_count(&self) -> usize {
+        self.touchpads.len()
+    }
+
+    /// Returns the touchpad at `index` in scan order.
+    pub fn touchpad(&self, index: usize) -> Option<&Touchpad> {
+        self.touchpads.get(index)
+    }
+
+    /// Renders one line per touchpad with its path, status and name.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Touchpad list, or a status line if no touchpad was found
+    pub fn touchpads_status(&self) -> String {
+        let mut status = String::new();
+        if self.touchpads.is_empty() {
+            status.push_str("no touchpad\n");
+        }
+        for touchpad in self.touchpads.iter() {
+            let state = match touchpad.working() {
+                _ if touchpad.error == Some(VerifyError::Failed) => "error",
+                _ if touchpad.error == Some(VerifyError::DeviceGone) => "gone",
+                Some(true) if touchpad.report.as_ref().is_some_and(|report| report.multitouch.degraded()) => "degraded",
+                Some(true) => "working",
+                Some(false) if touchpad.report.as_ref()
+                    .is_some_and(|report| report.event_check == EventCheckResult::Grabbed) => "grabbed",
+                Some(false) => "not-working",
+                None => "unverified",
+            };
+            status.push_str(&alloc::format!("{} {} {}\n", touchpad.path, state, touchpad.name));
+        }
+        status
+    }
+
+    /// Runs one watchdog check, re-verifying the touchpads if configured.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, ()>` - Ok with true if a freeze was suspected, Err if re-verification failed
+    pub fn watchdog_tick(&mut self) -> Result<bool, ()> {
+        let Some(path) = self.touchpads.first().map(|touchpad| touchpad.path.as_str()) else {
+            return Ok(false);
+        };
+
+        if !self.watchdog.tick(path) {
+            return Ok(false);
+        }
+        // A grabbed touchpad is silent to the watchdog's handler but not frozen
+        if grab::holder(path).ok().flatten().is_some() {
+            return Ok(false);
+        }
+
+        self.incidents.record(IncidentKind::FreezeSuspected, path, Some(Reason::FreezeSuspected), String::new());
+        if self.watchdog.reverify() {
+            kprint!("Watchdog: re-verifying touchpad\n");
+            self.verify_touchpad().map_err(|_| ())?;
+        }
+        Ok(true)
+    }
+
+    /// Applies a command to the guided self test.
+    ///
+    /// Starting the self test requires a touchpad to have been found by a
+    /// previous scan; each step waits up to the configured event window.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - Command to apply
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ()>` - Ok if the command was applied, Err if the self test could not start
+    pub fn selftest_command(&mut self, command: SelftestCommand) -> Result<(), ()> {
+        match command {
+            SelftestCommand::Start => {
+                let Some(path) = self.touchpads.first().map(|touchpad| touchpad.path.as_str()) else {
+                    kprint!("Touchpad not found, cannot run self test\n");
+                    return Err(());
+                };
+                let threshold = self.large_touch_threshold(path);
+                let buttonpad = self.devices.iter()
+                    .find(|dev| dev.path == path)
+                    .and_then(|dev| dev.props)
+                    .is_some_and(|props| props.buttonpad);
+                self.selftest.start(path, self.event_window_ms, threshold, buttonpad)
+            },
+            SelftestCommand::Skip => {
+                self.selftest.skip();
+                Ok(())
+            },
+            SelftestCommand::Abort => {
+                self.selftest.abort();
+                Ok(())
+            },
+        }
+    }
+
+    /// Advances the self test and renders its status.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Current prompt and per-gesture results
+    pub fn selftest_status(&mut self) -> String {
+        self.selftest.poll();
+
+        let mut status = String::new();
+        self.selftest.render(&mut status);
+        status
+    }
+    
+    /// Applies a command to the event recorder.
+    ///
+    /// Recording and replay operate on the first touchpad found by the last scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - Command to apply
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize, ()>` - Number of events recorded or replayed, Err if there is no
+    ///   touchpad or the recorder is busy
+    pub fn recorder_command(&mut self, command: RecorderCommand) -> Result<usize, ()> {
+        if command == RecorderCommand::Stop {
+            return Ok(RECORDER.stop());
+        }
+
+        let Some(path) = self.touchpads.first().map(|touchpad| touchpad.path.as_str()) else {
+            kprint!("Touchpad not found, cannot record or replay events\n");
+            return Err(());
+        };
+
+        if command == RecorderCommand::Replay {
+            if !self.permits(GuardedAction::Replay) {
+                return Err(());
+            }
+            RECORDER.replay(path)
+        } else {
+            RECORDER.start(path).map(|_| 0)
+        }
+    }
+
+    /// Captures the device registry and the latest verification reports.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Serialized snapshot
+    pub fn snapshot(&self) -> String {
+        self.capture_snapshot().serialize()
+    }
+
+    /// Compares a previously taken snapshot with the current state.
+    ///
+    /// The current state is the result of the last scan and verification, so
+    /// after an update the module should have scanned and verified before the
+    /// old snapshot is compared. Each difference is logged and kept for
+    /// `comparison_status`.
+    ///
+    /// # Arguments
+    ///
+    /// * `blob` - Serialized snapshot taken before the update
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize, ()>` - Number of differences found, Err if the snapshot can't be parsed
+    pub fn compare_snapshot(&mut self, blob: &str) -> Result<usize, ()> {
+        let Ok(previous) = Snapshot::parse(blob) else {
+            kprint!("Invalid snapshot\n");
+            return Err(());
+        };
+
+        let differences = previous.compare(&self.capture_snapshot());
+        let mut text = String::new();
+        for difference in differences.iter() {
+            difference.render(&mut text);
+        }
+        for (difference, line) in differences.iter().zip(text.lines()) {
+            let detail = line.split_once('\t').map_or("", |(_, detail)| detail);
+            kprint!("Snapshot comparison: {}: {}\n", difference.reason(), detail);
+        }
+        kprint!("Snapshot comparison found {} difference(s)\n", differences.len());
+
+        let count = differences.len();
+        self.comparison = Some(differences);
+        Ok(count)
+    }
+
+    /// Renders the result of the last snapshot comparison.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - One line per difference, or a status line if nothing differed
+    pub fn comparison_status(&self) -> String {
+        let mut status = String::new();
+        match self.comparison.as_ref() {
+            None => status.push_str("no comparison\n"),
+            Some(differences) if differences.is_empty() => status.push_str("no differences\n"),
+            Some(differences) => {
+                for difference in differences.iter() {
+                    difference.render(&mut status);
+                }
+            },
+        }
+        status
+    }
+
+    /// Renders the incident log.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - One line per incident, oldest first, or a status line if there are none
+    pub fn incidents_status(&self) -> String {
+        let mut status = String::new();
+        self.incidents.render(&mut status);
+        if status.is_empty() {
+            status.push_str("no incidents\n");
+        }
+        status
+    }
+
+    /// Clears the incident log.
+    pub fn clear_incidents(&mut self) {
+        self.incidents.clear();
+    }
+
+    /// Replaces the declared expectations and annotations.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Declarations, one per line, in the syntax described in `expect`
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), usize>` - Ok if the declarations were applied, Err with the number of
+    ///   the first invalid line, in which case the previous ones are kept
+    pub fn declare_expectations(&mut self, text: &str) -> Result<(), usize> {
+        self.expectations.declare(text).inspect_err(|line| {
+            kprint!("Invalid expectation on line {}, keeping the previous ones\n", line);
+        })?;
+        self.cache.invalidate();
+        Ok(())
+    }
+
+    /// Renders the declared expectations and annotations.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - One declaration per line, empty if nothing is declared
+    pub fn expectations_status(&self) -> String {
+        self.expectations.describe()
+    }
+
+    /// Renders the deviations found by the last verification.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - One line per deviation (subject, reason code and details, tab separated),
+    ///   or a status line if there are none
+    pub fn deviations_status(&self) -> String {
+        if self.expectations.is_empty() {
+            return "no expectations\n".into();
+        }
+        let mut status = String::new();
+        for deviation in self.expectations.deviations() {
+            status.push_str(&alloc::format!("{}\t{}\t{}\n", deviation.subject, deviation.reason.code(),
+                                            deviation.detail));
+        }
+        if status.is_empty() {
+            status.push_str("conforming\n");
+        }
+        status
+    }
+
+    /// Renders the statistics and touchpad health for Prometheus.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Metrics in the Prometheus text exposition format
+    pub fn metrics(&self) -> String {
+        metrics::render(&self.touchpads, self.expectations.deviations().len())
+    }
+
+    /// Checks the declared expectations against the last scan.
+    ///
+    /// Deviations not reported by the previous check are logged and recorded
+    /// as incidents.
+    fn check_expectations(&mut self) {
+        for deviation in self.expectations.check(&self.devices) {
+            kprint!("Deviation from the declared expectations: {} ({})\n", deviation.reason, deviation.detail);
+            self.incidents.record(IncidentKind::ExpectationViolated, &deviation.subject, Some(deviation.reason),
+                                  deviation.detail.clone());
+        }
+    }
+
+    /// Builds a snapshot of the devices found by the last scan and the latest reports.
+    fn capture_snapshot(&self) -> Snapshot {
+        let drivers = self.devices.iter()
+            .map(|dev| recovery::bound_driver(&recovery::sysfs_path(&dev.path)).unwrap_or_default())
+            .collect();
+        let reports = self.touchpads.iter().filter_map(|touchpad| touchpad.report.as_ref());
+        Snapshot::capture(&self.devices, drivers, reports)
+    }
+    
+    /// Scans the system for input devices with focus on touchpad devices.
+    ///
+    /// Performs a system scan to detect input devices connected to the system.
+    /// Updates the internal state to track discovered devices.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ()>` - Ok if the scan completes successfully, Err otherwise
+    pub fn scan_devices(&mut self) -> Result<(), ()> {
+        kprint!("Scanning for input devices...\n");
+        STATS.inc(Counter::ScansPerformed);
+        self.cache.invalidate();
+        self.scan_pending = false;
+        trace::scan_start();
+        
+        let input_devices = match self.read_input_devices() {
+            Ok(devices) => devices,
+            Err(_) => {
+                trace::scan_end(0, -1);
+                return Err(());
+            }
+        };
+        trace::scan_end(input_devices.len() as u32, 0);
+        for device in input_devices.iter() {
+            STATS.inc(Counter::for_device_type(device.device_type));
+        }
+        
+        match self.identify_touchpads(&input_devices) {
+            Ok(found) => {
+                // Keep the last report of touchpads seen before, so status changes
+                // are still detected when a rescan renumbers the nodes
+                let mut previous = core::mem::take(&mut self.touchpads);
+                for (path, name) in found {
+                    kprint!("Touchpad device found: {}\n", name);
+                    kprint!("Touchpad path: {}\n", path);
+                    
+                    // Each earlier touchpad is carried over once, so same-named touchpads
+                    // keep their own reports
+                    let report = previous.iter()
+                        .position(|touchpad| touchpad.path == path && touchpad.name == name)
+                        .or_else(|| previous.iter().position(|touchpad| touchpad.name == name))
+                        .and_then(|index| previous.remove(index).report);
+                    self.touchpads.push(Touchpad { name, path, report, error: None });
+                }
+                
+                if self.touchpads.is_empty() {
+                    kprint!("No touchpad device identified\n");
+                }
+                
+                self.devices = input_devices;
+                self.restart_watchdog();
+                
+                kprint!("Input device scan complete\n");
+                Ok(())
+            },
+            Err(_) => {
+                kprint!("Failed to identify touchpad\n");
+                Err(())
+            }
+        }
+    }
+
+    /// Returns the error a verification reports when its scan failed.
+    fn scan_error(&self) -> VerifyError {
+        if self.sysfs_input {
+            VerifyError::Failed
+        } else {
+            VerifyError::EnvironmentUnsupported
+        }
+    }
+
+    /// Restarts the watchdog on the first touchpad and the keyboards.
+    fn restart_watchdog(&mut self) {
+        self.watchdog.stop();
+
+        let Some(touchpad) = self.touchpads.first() else {
+            return;
+        };
+
+        let keyboards = self.devices.iter()
+            .filter(|dev| dev.device_type == DeviceType::Keyboard && !dev.stale)
+            .map(|dev| dev.path.as_str());
+        if self.watchdog.start(&touchpad.path, keyboards).is_err() {
+            kprint!("Failed to start touchpad watchdog\n");
+        }
+    }
+
+    /// Reads input devices from sysfs and proc.
+    ///
+    /// Reads through the input core instead if that backend is selected, or
+    /// if /sys/class/input can't be listed.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<DeviceInfo>, ()>` - Vector of input device info or error
+    fn read_input_devices(&mut self) -> Result<Vec<DeviceInfo>, ()> {
+        let mut devices = Vec::new();
+        
+        if self.enumeration == Enumeration::InputCore {
+            // Only looked up, the devices themselves come from the input core
+            self.set_sysfs_input(recovery::resolve_path(SYS_CLASS_INPUT).is_ok());
+            return self.read_input_core_devices();
+        }
+        
+        // Call the kernel FFI function to get input devices
+        if self.read_sysfs_directory_pooled(SYS_CLASS_INPUT).is_err() {
+            self.set_sysfs_input(false);
+            kprint!("Enumerating input devices through the input core\n");
+            return self.read_input_core_devices();
+        }
+        self.set_sysfs_input(true);
+        if self.pool.dropped() > 0 {
+            kprint!("Scan pool full, {} entries of /sys/class/input skipped\n", self.pool.dropped());
+        }
+        
+        for entry in self.pool.iter() {
+            if !entry.starts_with("event") {
+                continue;
+            }
+            
+            let device_path = alloc::format!("/dev/input/{}", entry);
+            let sys_path = alloc::format!("{}/{}", SYS_CLASS_INPUT, entry);
+            
+            match self.read_device_name_bytes(&sys_path) {
+                Ok(raw_name) => {
+                    // Devices are classified by their raw name and reported by the escaped one
+                    let name = text::escape_lossy(&raw_name).into_owned();
+                    let (vendor, product) = self.read_device_id(&sys_path);
+                    if !self.filter.allows(&raw_name, vendor, product) {
+                        kprint!("Ignoring input device: {} at {}\n", name, device_path);
+                        continue;
+                    }
+                    let device_seat = self.read_seat(&sys_path);
+                    if !self.in_seat_scope(device_seat.as_deref(), &name, &device_path) {
+                        continue;
+                    }
+                    
+                    kprint!("Found input device: {} at {}\n", name, device_path);
+                    
+                    let bits = self.read_input_bits(&sys_path).ok();
+                    let props = bits.as_ref().map(InputBits::props);
+                    let virtual_device = self.hypervisor.is_some() && virt::is_virtual_device(&raw_name);
+                    let device_type = self.classify(&raw_name, &device_path, bits.as_ref(), virtual_device);
+                    let bustype = self.read_file_contents(&alloc::format!("{}/device/id/bustype", sys_path))
+                        .ok()
+                        .and_then(|bustype| u16::from_str_radix(&bustype, 16).ok())
+                        .unwrap_or(0);
+                    let by_id = stable_name::by_id(bustype, &name, device_type);
+                    let by_path = stable_name::by_path(&sys_path, device_type);
+                    let udev = bits.as_ref().map(UdevClass::classify);
+                    if udev.is_some_and(|udev| !udev.agrees_with(device_type)) {
+                        kprint!("Classified {} as {}, udev disagrees\n", device_path, device_type.as_str());
+                    }
+                    
+                    devices.push(DeviceInfo {
+                        name,
+                        raw_name,
+                        path: device_path,
+                        device_type,
+                        vendor,
+                        product,
+                        bustype,
+                        stale: false,
+                        by_path,
+                        by_id,
+                        udev,
+                        props,
+                        virtual_device,
+                        seat: device_seat,
+                        firmware: self.read_firmware_info(&sys_path),
+                        slot: DeviceSlot::new(),
+                    });
+                },
+                Err(_) => continue, 
+            }
+        }
+        
+        Ok(devices)
+    }
+
+    /// Reads input devices through the input core.
+    ///
+    /// Used as the scan backend when selected, and when sysfs is unavailable.
+    /// The name, id and capabilities come from the input core in one step per
+    /// device; the seat, physical path and firmware attributes are only known
+    /// from sysfs and left empty without it.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<DeviceInfo>, ()>` - Vector of input device info, Err if the input
+    ///   core can't be enumerated
+    fn read_input_core_devices(&mut self) -> Result<Vec<DeviceInfo>, ()> {
+        let Ok(core_devices) = input_core::list() else {
+            kprint!("Input core enumeration is unavailable\n");
+            return Err(());
+        };
+
+        let mut devices = Vec::new();
+        for core_device in core_devices {
+            let device_path = core_device.path();
+            let sys_path = recovery::sysfs_path(&device_path);
+            let raw_name = core_device.name;
+            let name = text::escape_lossy(&raw_name).into_owned();
+            if !self.filter.allows(&raw_name, core_device.vendor, core_device.product) {
+                kprint!("Ignoring input device: {} at {}\n", name, device_path);
+                continue;
+            }
+            let device_seat = if self.sysfs_input { self.read_seat(&sys_path) } else { None };
+            if !self.in_seat_scope(device_seat.as_deref(), &name, &device_path) {
+                continue;
+            }
+
+            kprint!("Found input device: {} at {}\n", name, device_path);
+
+            let bits = core_device.bits;
+            let virtual_device = self.hypervisor.is_some() && virt::is_virtual_device(&raw_name);
+            let device_type = self.classify(&raw_name, &device_path, Some(&bits), virtual_device);
+            let udev = UdevClass::classify(&bits);
+            if !udev.agrees_with(device_type) {
+                kprint!("Classified {} as {}, udev disagrees\n", device_path, device_type.as_str());
+            }
+            let (by_path, firmware) = if self.sysfs_input {
+                (stable_name::by_path(&sys_path, device_type), self.read_firmware_info(&sys_path))
+            } else {
+                (None, FirmwareInfo::default())
+            };
+
+            devices.push(DeviceInfo {
+                by_id: stable_name::by_id(core_device.bustype, &name, device_type),
+                name,
+                raw_name,
+                path: device_path,
+                device_type,
+                vendor: core_device.vendor,
+                product: core_device.product,
+                bustype: core_device.bustype,
+                stale: false,
+                by_path,
+                udev: Some(udev),
+                props: Some(bits.props()),
+                virtual_device,
+                seat: device_seat,
+                firmware: FirmwareInfo { id_version: core_device.version, ..firmware },
+                slot: DeviceSlot::new(),
+            });
+        }
+
+        Ok(devices)
+    }
+
+    /// Checks if a device belongs to the seat the scan is limited to, logging it if not.
+    ///
+    /// # Arguments
+    ///
+    /// * `device_seat` - Seat of the device, None if unknown
+    /// * `name` - Name of the device
+    /// * `device_path` - Path to the device node
+    fn in_seat_scope(&self, device_seat: Option<&str>, name: &str, device_path: &str) -> bool {
+        let Some(scope) = self.seat.as_deref() else {
+            return true;
+        };
+        if seat::belongs_to(device_seat, scope) {
+            return true;
+        }
+        kprint!("Ignoring input device on {}: {} at {}\n",
+                device_seat.unwrap_or(seat::DEFAULT_SEAT), name, device_path);
+        false
+    }
+
+    /// Records whether /sys/class/input is available, logging when that changes.
+    fn set_sysfs_input(&mut self, available: bool) {
+        if available != self.sysfs_input {
+            kprint!("/sys/class/input is {}\n", if available { "available again" } else { "unavailable" });
+        }
+        self.sysfs_input = available;
+    }
+
+    /// Classifies an input device.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw_name` - Raw name of the device
+    /// * `device_path` - Path to the device node
+    /// * `bits` - Capability bitmaps of the device, None if unknown
+    /// * `virtual_device` - Whether the device is emulated by a hypervisor
+    ///
+    /// # Returns
+    ///
+    /// * `DeviceType` - Type of the device, Unknown if nothing identifies it
+    fn classify(&self, raw_name: &[u8], device_path: &str, bits: Option<&InputBits>,
+                virtual_device: bool) -> DeviceType {
+        let props = bits.map(InputBits::props);
+
+        // Sensors advertise absolute axes too, so they are ruled out before the
+        // name and capability checks can mistake them for a touchpad
+        let (device_type, method) = if bits.is_some_and(|bits| bits.is_sensor()) {
+            (DeviceType::Sensor, ClassifyMethod::Capabilities)
+        } else if virtual_device {
+            // Emulated tablets have absolute axes, but are pointers like a mouse
+            if matcher::matches_any(matcher::KEYBOARD_NAME_RULES, raw_name) {
+                (DeviceType::Keyboard, ClassifyMethod::Name)
+            } else {
+                (DeviceType::Mouse, ClassifyMethod::Name)
+            }
+        } else if bits.is_some_and(|bits| bits.is_pointing_stick()) {
+            (DeviceType::PointingStick, ClassifyMethod::Capabilities)
+        } else if props.is_some_and(|props| props.direct) {
+            // Touchscreens share vendors and multitouch axes with touchpads, so
+            // the property decides before the name or axes can
+            (DeviceType::Touchscreen, ClassifyMethod::Capabilities)
+        } else if matcher::matches_any(matcher::POINTING_STICK_NAME_RULES, raw_name) {
+            // Before the touchpad names, which match the vendor of some sticks
+            (DeviceType::PointingStick, ClassifyMethod::Name)
+        } else if self.is_touchpad_by_name(raw_name) {
+            (DeviceType::Touchpad, ClassifyMethod::Name)
+        } else if matcher::matches_any(matcher::KEYBOARD_NAME_RULES, raw_name) {
+            (DeviceType::Keyboard, ClassifyMethod::Name)
+        } else if matcher::matches_any(matcher::MOUSE_NAME_RULES, raw_name) {
+            (DeviceType::Mouse, ClassifyMethod::Name)
+        } else {
+            let device_type = self.determine_device_type(device_path).unwrap_or(DeviceType::Unknown);
+            (device_type, ClassifyMethod::Capabilities)
+        };
+        trace::classify(device_path, device_type, method);
+        device_type
+    }
+
+    /// Reads the name of an input device from sysfs.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to device directory in sysfs
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, ()>` - Device name, with bytes that aren't valid UTF-8 escaped, or error
+    fn read_device_name(&self, path: &str) -> Result<String, ()> {
+        self.read_device_name_bytes(path).map(|name| text::escape_lossy(&name).into_owned())
+    }
+
+    /// Reads the raw name of an input device from sysfs, for matching.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to device directory in sysfs
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<u8>, ()>` - Device name or error
+    fn read_device_name_bytes(&self, path: &str) -> Result<Vec<u8>, ()> {
+        let name_path = alloc::format!("{}/device/name", path);
+        self.read_file_bytes(&name_path)
+    }
+
+    /// Reads the vendor and product id of an input device from sysfs.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to device directory in sysfs
+    ///
+    /// # Returns
+    ///
+    /// * `(u16, u16)` - Vendor and product id, 0 for ids that can't be read
+    fn read_device_id(&self, path: &str) -> (u16, u16) {
+        let read_id = |name: &str| {
+            self.read_file_contents(&alloc::format!("{}/device/id/{}", path, name))
+                .ok()
+                .and_then(|id| u16::from_str_radix(&id, 16).ok())
+                .unwrap_or(0)
+        };
+        (read_id("vendor"), read_id("product"))
+    }
+
+    /// Reads the firmware information of an input device from sysfs.
+    ///
+    /// The input id carries a version for every device; drivers of some
+    /// touchpads also expose firmware attributes on the physical device.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to device directory in sysfs
+    ///
+    /// # Returns
+    ///
+    /// * `FirmwareInfo` - Firmware information, with unknown fields left empty
+    fn read_firmware_info(&self, path: &str) -> FirmwareInfo {
+        let read_first = |attrs: &[&str]| {
+            attrs.iter()
+                .find_map(|attr| self.read_file_contents(&alloc::format!("{}/device/device/{}", path, attr)).ok())
+                .filter(|value| !value.is_empty())
+        };
+
+        FirmwareInfo {
+            id_version: self.read_file_contents(&alloc::format!("{}/device/id/version", path))
+                .ok()
+                .and_then(|version| u16::from_str_radix(&version, 16).ok())
+                .unwrap_or(0),
+            version: read_first(FIRMWARE_VERSION_ATTRS),
+            id: read_first(FIRMWARE_ID_ATTRS),
+        }
+    }
+
+    /// Reads the attachment and tuning of a pointing stick from sysfs.
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - Pointing stick found by the scan
+    ///
+    /// # Returns
+    ///
+    /// * `TrackpointInfo` - Attachment and tuning, with unknown fields left empty
+    fn read_trackpoint_info(&self, device: &DeviceInfo) -> TrackpointInfo {
+        let sys_path = recovery::sysfs_path(&device.path);
+        let serio_path = alloc::format!("{}/device/device", sys_path);
+        let read_attr = |attr: &str| self.read_file_contents(&alloc::format!("{}/{}", serio_path, attr)).ok();
+        let read_number = |attr: &str| read_attr(attr).and_then(|value| value.parse::<u32>().ok());
+
+        // The parent of a PS/2 input device is its serio port, e.g. .../serio1/serio2
+        let serio_port = recovery::resolve_path(&serio_path)
+            .ok()
+            .map(|port| String::from(recovery::last_component(&port)))
+            .filter(|port| port.starts_with("serio"));
+
+        TrackpointInfo {
+            name: device.name.clone(),
+            path: device.path.clone(),
+            driver: serio_port.as_ref().and_then(|_| recovery::bound_driver(&sys_path).ok()),
+            serio_port,
+            protocol: read_attr("protocol"),
+            sensitivity: read_number(trackpoint::SENSITIVITY_ATTR),
+            speed: read_number(trackpoint::SPEED_ATTR),
+            inertia: read_number(trackpoint::INERTIA_ATTR),
+            press_to_select: read_number(trackpoint::PRESS_TO_SELECT_ATTR).map(|value| value != 0),
+        }
+    }
+
+    /// Checks which special function keys the keyboards of a laptop map.
+    ///
+    /// The key bitmaps of every keyboard and unclassified device (hotkey
+    /// drivers and the ACPI video bus have no other class) are merged. With
+    /// the interactive profile the user is also asked to press the keys.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<FnKeyCheck>` - Mapping of the special keys, None if the machine has no
+    ///   internal keyboard
+    fn check_fn_keys(&self) -> Option<FnKeyCheck> {
+        let candidates = || self.devices.iter()
+            .filter(|dev| !dev.stale && matches!(dev.device_type, DeviceType::Keyboard | DeviceType::Unknown));
+        if !candidates().any(|dev| dev.device_type == DeviceType::Keyboard && dev.is_internal()) {
+            return None;
+        }
+
+        let sources: Vec<(&DeviceInfo, InputBits)> = candidates()
+            .filter_map(|dev| Some((dev, self.read_input_bits(&recovery::sysfs_path(&dev.path)).ok()?)))
+            .collect();
+
+        let mut leds = Vec::new();
+        let kbd_backlight = dir::read_directory("/sys/class/leds", &mut leds).is_ok()
+            && leds.iter().filter_map(DirEntry::name)
+                .any(|name| matcher::contains(name, fnkeys::KBD_BACKLIGHT_LED.as_bytes()));
+
+        let mut check = FnKeyCheck::new(
+            sources.iter().map(|(dev, _)| dev.name.clone()).collect(),
+            kbd_backlight,
+            |code| sources.iter().any(|(_, bits)| bits.key.test(code as usize)),
+        );
+
+        if self.profile == Profile::Interactive {
+            let attached = sources.iter().filter(|(dev, _)| EVENT_MONITOR.attach(FN_KEYS_SLOT, &dev.path).is_ok()).count();
+            if attached > 0 {
+                check.confirm(self.profile.event_window_ms(self.event_window_ms));
+            }
+            EVENT_MONITOR.detach(FN_KEYS_SLOT);
+        }
+        Some(check)
+    }
+
+    /// Reads the capability bitmaps of an input device from sysfs.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to device directory in sysfs
+    ///
+    /// # Returns
+    ///
+    /// * `Result<InputBits, ()>` - Capability bitmaps or error
+    fn read_input_bits(&self, path: &str) -> Result<InputBits, ()> {
+        let read_bitmap = |name: &str| {
+            self.read_file_contents(&alloc::format!("{}/device/{}", path, name))
+                .and_then(|text| Bitmap::parse(&text))
+        };
+
+        Ok(InputBits {
+            ev: read_bitmap("capabilities/ev")?,
+            key: read_bitmap("capabilities/key")?,
+            rel: read_bitmap("capabilities/rel")?,
+            abs: read_bitmap("capabilities/abs")?,
+            prop: read_bitmap("properties")?,
+        })
+    }
+
+    /// Checks that a registry entry still refers to the device it was scanned as.
+    ///
+    /// The node must still exist, and the input device behind it must have the
+    /// same name, vendor and product as when it was scanned. Nodes are reused
+    /// after unplug or rebind, so existence alone does not prove identity.
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - Registry entry to revalidate
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the entry is still valid
+    fn revalidate(&self, device: &DeviceInfo) -> bool {
+        if self.enumeration == Enumeration::InputCore || !self.sysfs_input {
+            return input_core::list().is_ok_and(|devices| devices.iter().any(|core| {
+                core.path() == device.path && text::escape_lossy(&core.name) == device.name
+                    && (core.vendor, core.product) == (device.vendor, device.product)
+            }));
+        }
+        if recovery::resolve_path(&device.path).is_err() {
+            return false;
+        }
+
+        let sys_path = recovery::sysfs_path(&device.path);
+        match self.read_device_name(&sys_path) {
+            Ok(name) if name == device.name => {
+                self.read_device_id(&sys_path) == (device.vendor, device.product)
+            },
+            _ => false,
+        }
+    }
+
+    /// Revalidates a touchpad before it is verified.
+    ///
+    /// If the touchpad is gone, its registry entry is marked stale and the
+    /// touchpad is forgotten until the next scan. If the watchdog was monitoring
+    /// it, it moves to the next touchpad so the silence of a removed device is
+    /// never reported as a freeze.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the touchpad device node
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the touchpad is still present
+    fn revalidate_touchpad(&mut self, path: &str) -> bool {
+        let valid = match self.devices.iter().find(|dev| dev.path == path) {
+            Some(device) => !device.stale && self.revalidate(device),
+            None => false,
+        };
+        if valid {
+            return true;
+        }
+
+        kprint!("Touchpad {} is gone, rescan to find it again\n", path);
+        for device in self.devices.iter_mut().filter(|dev| dev.path == path) {
+            device.stale = true;
+        }
+        let was_primary = self.touchpads.first().map(|touchpad| touchpad.path.as_str()) == Some(path);
+        self.touchpads.retain(|touchpad| touchpad.path != path);
+        if was_primary {
+            self.restart_watchdog();
+        }
+        notify(Notification::DeviceGone, path, 0);
+        self.incidents.record(IncidentKind::DeviceGone, path, Some(Reason::DeviceGone), String::new());
+        false
+    }
+
+    /// Determines device type based on capabilities in /proc/bus/input/devices.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to device node
+    ///
+    /// # Returns
+    ///
+    /// * `Result<DeviceType, ()>` - Device type or error
+    fn determine_device_type(&self, path: &str) -> Result<DeviceType, ()> {
+        let caps = self.read_capabilities(path)?;
+        
+        // Check for ABS_MT_POSITION_X (0x35) and ABS_MT_POSITION_Y (0x36) due them absolute
+        // positioning
+        if caps.has_abs(0x35) && caps.has_abs(0x36) {
+            return Ok(DeviceType::Touchpad);
+        }
+        
+        // Check for REL_X (0x00) and REL_Y (0x01) due the possibility to have a relative positioning
+        if (caps.rel & (1 << 0x00)) != 0 && (caps.rel & (1 << 0x01)) != 0 {
+            return Ok(DeviceType::Mouse);
+        }
+        
+        // Keyboard check might involve KEY_A through KEY_Z
+        let has_letter_keys = (0x04..=0x1D).any(|key_code| (caps.key & (1 << key_code)) != 0);
+        if has_letter_keys {
+            return Ok(DeviceType::Keyboard);
+        }
+        
+        Ok(DeviceType::Unknown)
+    }
+
+    /// Reads the capability bitmaps of an input device.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to device node
+    ///
+    /// # Returns
+    ///
+    /// * `Result<DeviceCapabilities, ()>` - Capability bitmaps or error
+    fn read_capabilities(&self, path: &str) -> Result<DeviceCapabilities, ()> {
+        let Some(kernel_get_device_capabilities) = shim::ops().get_device_capabilities else {
+            return Err(());
+        };
+
+        unsafe {
+            let path_bytes = path.as_bytes();
+            let mut caps = DeviceCapabilities::default();
+            
+            let result = kernel_get_device_capabilities(
+                path_bytes.as_ptr(),
+                path_bytes.len(),
+                &mut caps.abs,     // For EV_ABS support (all ABS_* codes)
+                &mut caps.rel,     // For EV_REL support
+                &mut caps.key      // For EV_KEY support
+            );
+            
+            if result < 0 {
+                return Err(());
+            }
+            
+            Ok(caps)
+        }
+    }
+
+    /// Reads the range of an absolute axis of an input device.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to device node
+    /// * `code` - Absolute axis code (ABS_*)
+    ///
+    /// # Returns
+    ///
+    /// * `Result<AbsInfo, ()>` - Axis range or error if the axis is not supported
+    fn read_abs_info(&self, path: &str, code: u32) -> Result<AbsInfo, ()> {
+        let Some(kernel_get_abs_info) = shim::ops().get_abs_info else {
+            return Err(());
+        };
+
+        unsafe {
+            let path_bytes = path.as_bytes();
+            let mut info = AbsInfo::default();
+            
+            let result = kernel_get_abs_info(
+                path_bytes.as_ptr(),
+                path_bytes.len(),
+                code,
+                &mut info.minimum,
+                &mut info.maximum,
+                &mut info.resolution
+            );
+            
+            if result < 0 {
+                return Err(());
+            }
+            
+            Ok(info)
+        }
+    }
+
+    /// Reads sysfs directory entries.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Directory path
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<String>, ()>` - Directory entries or error
+    fn read_sysfs_directory(&self, path: &str) -> Result<Vec<String>, ()> {
+        let mut entries = Vec::new();
+        dir::read_directory(path, &mut entries)?;
+        Ok(entries.iter()
+            .filter_map(DirEntry::name)
+            .map(|name| text::escape_lossy(name).into_owned())
+            .collect())
+    }
+
+    /// Lists a directory into the scan string pool, replacing its contents.
+    ///
+    /// Entries that don't fit into the pool are dropped and counted.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Directory path
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ()>` - Ok if the directory was read, Err otherwise
+    fn read_sysfs_directory_pooled(&mut self, path: &str) -> Result<(), ()> {
+        dir::read_directory(path, &mut self.dir_entries)?;
+        self.pool.clear();
+        for name in self.dir_entries.iter().filter_map(DirEntry::name) {
+            let _ = self.pool.push(&text::escape_lossy(name));
+        }
+        Ok(())
+    }
+    
+    /// Reads the seat udev assigned an input device to.
+    ///
+    /// # Arguments
+    ///
+    /// * `sys_path` - sysfs directory of the input device node, e.g. `/sys/class/input/event5`
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>` - Seat name, None if udev has no record of the device
+    fn read_seat(&self, sys_path: &str) -> Option<String> {
+        let dev = self.read_file_contents(&alloc::format!("{}/dev", sys_path)).ok()?;
+        let db = self.read_file_bytes_sized(&seat::udev_db_path(&dev), seat::UDEV_DB_MAX).ok()?;
+        Some(seat::from_udev_db(&text::escape_lossy(&db)))
+    }
+
+    /// Reads file contents from sysfs or proc.
+    ///
+    /// Bytes that aren't valid UTF-8 are escaped rather than failing the read.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - File path
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, ()>` - File contents or error
+    fn read_file_contents(&self, path: &str) -> Result<String, ()> {
+        self.read_file_bytes(path).map(|bytes| text::escape_lossy(&bytes).into_owned())
+    }
+
+    /// Reads the raw contents of a file from sysfs or proc.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - File path
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<u8>, ()>` - File contents without trailing whitespace, or error
+    fn read_file_bytes(&self, path: &str) -> Result<Vec<u8>, ()> {
+        self.read_file_bytes_sized(path, 256)
+    }
+
+    /// Reads the raw contents of a file that may exceed a sysfs attribute.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - File path
+    /// * `size` - Most bytes to read, the rest of the file is ignored
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<u8>, ()>` - File contents without trailing whitespace, or error
+    fn read_file_bytes_sized(&self, path: &str, size: usize) -> Result<Vec<u8>, ()> {
+        let Some(kernel_read_file) = shim::ops().read_file else {
+            return Err(());
+        };
+
+        let path_bytes = path.as_bytes();
+        let mut buffer = ShimBuf::new(size, 0u8);
+        let capacity = buffer.capacity();
+        let result = unsafe {
+            kernel_read_file(path_bytes.as_ptr(), path_bytes.len(), buffer.as_mut_ptr(), capacity,
+                             buffer.reported_mut())
+        };
+        
+        // A file longer than the buffer is truncated to it
+        let mut buffer = buffer.into_vec();
+        if result < 0 || buffer.is_empty() {
+            return Err(());
+        }
+        
+        // Remove any trailing whitespace
+        while buffer.last() == Some(&b'\n') || buffer.last() == Some(&b'\r') || buffer.last() == Some(&b' ') {
+            buffer.pop();
+        }
+        
+        Ok(buffer)
+    }
+
+    /// Identifies all touchpad devices in a list of input devices.
+    ///
+    /// # Arguments
+    ///
+    /// * `devices` - List of device information structures
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(String, String)>, ()>` - Path and name of every touchpad, in scan order
+    fn identify_touchpads(&self, devices: &[DeviceInfo]) -> Result<Vec<(String, String)>, ()> {
+        let mut touchpads = Vec::new();
+        
+        for device in devices {
+            // Devices already identified as touchpads, or with indicators in their name.
+            // Sensors, pointing sticks, touchscreens and emulated devices are never
+            // candidates, even if their name matches a touchpad vendor
+            if matches!(device.device_type, DeviceType::Sensor | DeviceType::PointingStick | DeviceType::Touchscreen)
+                || device.virtual_device {
+                continue;
+            } else if device.device_type == DeviceType::Touchpad {
+                kprint!("Found explicit touchpad device: {}\n", device.name);
+            } else if self.is_touchpad_by_name(&device.raw_name) {
+                kprint!("Identified touchpad by name: {}\n", device.name);
+            } else {
+                continue;
+            }
+            touchpads.push((device.path.clone(), device.name.clone()));
+        }
+        
+        Ok(touchpads)
+    }
+    
+    /// Checks if a device is a touchpad based on its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Raw device name to check
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the device name indicates a touchpad
+    fn is_touchpad_by_name(&self, name: &[u8]) -> bool {
+        matcher::matches_any(matcher::TOUCHPAD_NAME_RULES, name)
+    }
+
+    /// Verifies the touchpads, reusing the last result while it is fresh.
+    ///
+    /// A cached result is reused until its time to live expires, an input device
+    /// is connected or disconnected, or the devices are rescanned. The per-touchpad
+    /// reports are those of the verification that produced the result. If the scan
+    /// was deferred, the first call scans before verifying.
+    ///
+    /// Status polling must not change device state, so a verification that isn't
+    /// forced runs under at most the diagnose policy; modules are only loaded, and
+    /// drivers rebound or reset, by a forced verification.
+    ///
+    /// # Arguments
+    ///
+    /// * `force` - If true, ignore the cached result and verify again, with the
+    ///   configured safety policy
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, VerifyError>` - Same as `verify_touchpad`
+    pub fn verify_touchpad_cached(&mut self, force: bool) -> Result<bool, VerifyError> {
+        if self.scan_pending {
+            kprint!("First status query, running the deferred scan\n");
+            if self.scan_devices().is_err() {
+                return Err(self.scan_error());
+            }
+        }
+
+        if !force {
+            if let Some(result) = self.cache.get() {
+                return result;
+            }
+        }
+
+        let safety = self.safety;
+        if !force {
+            self.safety = safety.min(SafetyPolicy::Diagnose);
+        }
+        let result = self.verify_touchpad();
+        self.safety = safety;
+        self.cache.store(result);
+        result
+    }
+
+    /// Verifies if the touchpads are functioning correctly.
+    ///
+    /// This function checks if a touchpad was found first, then verifies each
+    /// touchpad independently by interacting with its device driver. The result
+    /// of each touchpad is kept in its own report. The declared expectations are
+    /// checked against the scan first; deviations don't change the result.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, VerifyError>` - Ok with true if all touchpads are working, Ok with false
+    ///   if one is not working, could not be verified or none was found, Err with `DeviceGone`
+    ///   if every touchpad disappeared since the scan, Err with `NotExpected` if none was found
+    ///   in a virtual machine, Err with `EnvironmentUnsupported` if the touchpads were found
+    ///   without sysfs, and Err with `Failed` if the verification could not run for any touchpad
+    pub fn verify_touchpad(&mut self) -> Result<bool, VerifyError> {
+        self.check_expectations();
+
+        if self.touchpads.is_empty() {
+            if let Some(hypervisor) = self.hypervisor.as_ref() {
+                kprint!("Virtual environment ({}), physical touchpad not expected\n", hypervisor);
+                return Err(VerifyError::NotExpected);
+            }
+            kprint!("Touchpad not found, cannot verify\n");
+            return Ok(false);
+        }
+
+        // The module and driver checks read sysfs, so a touchpad found through the
+        // input core can be listed but not verified
+        if !self.sysfs_input {
+            kprint!("/sys/class/input is unavailable, touchpad drivers can't be verified here\n");
+            return Err(VerifyError::EnvironmentUnsupported);
+        }
+        
+        // Recovery rescans, so the list is re-read for every touchpad
+        let targets: Vec<(String, String)> = self.touchpads.iter()
+            .map(|touchpad| (touchpad.path.clone(), touchpad.name.clone()))
+            .collect();
+        
+        // The special keys belong to the machine rather than to a touchpad, so they
+        // are checked once per run and the result goes into every report
+        let fn_keys = if self.runs(Phase::FnKeys) {
+            let started = now_us();
+            let check = self.check_fn_keys();
+            self.end_phase(&mut PhaseTimings::default(), Phase::FnKeys, started);
+            check
+        } else {
+            None
+        };
+
+        let mut done: Vec<String> = Vec::new();
+        let mut verified = 0;
+        let mut all_working = true;
+        let mut failed = false;
+        for (path, name) in targets {
+            let path = match self.find_touchpad(&path, &name, &done) {
+                Some(touchpad) => touchpad.path.clone(),
+                None if !done.contains(&path) => path,
+                None => continue,
+            };
+            
+            // A touchpad whose verification can't run counts as not working, the
+            // others are still verified and reported
+            let result = self.verify_one(&path, &name, fn_keys.as_ref());
+            if let Some(touchpad) = self.touchpads.iter_mut().find(|touchpad| touchpad.path == path) {
+                touchpad.error = result.err();
+            }
+            match result {
+                Ok(working) => {
+                    verified += 1;
+                    all_working &= working;
+                },
+                Err(VerifyError::Failed) => {
+                    kprint!("Verification of {} could not run\n", name);
+                    failed = true;
+                },
+                Err(_) => {},
+            }
+            done.push(path);
+        }
+        
+        if verified > 0 {
+            Ok(all_working && !failed)
+        } else if failed {
+            Err(VerifyError::Failed)
+        } else {
+            Err(VerifyError::DeviceGone)
+        }
+    }
+
+    /// Starts the verification of every scanned device.
+    ///
+    /// Clears the results of the previous run. The devices are then verified
+    /// concurrently by `verify_device`, and the results collected by
+    /// `verify_all_end`.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - Number of devices to verify
+    pub fn verify_all_begin(&mut self) -> usize {
+        for device in self.devices.iter() {
+            if let Some(mut verdict) = device.slot.lock() {
+                *verdict = None;
+            }
+        }
+        kprint!("Verifying {} input devices\n", self.devices.len());
+        self.devices.len()
+    }
+
+    /// Verifies one device of the registry.
+    ///
+    /// Takes `&self` since it runs concurrently for several devices; the
+    /// verdict is stored in the result slot of the device.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Index of the device in the registry
+    ///
+    /// # Returns
+    ///
+    /// * `Result<DeviceVerdict, ()>` - Verdict of the device, Err if the index is out of
+    ///   range or the device is already being verified
+    pub fn verify_device(&self, index: usize) -> Result<DeviceVerdict, ()> {
+        let device = self.devices.get(index).ok_or(())?;
+        let mut slot = device.slot.lock().ok_or(())?;
+
+        let verdict = if device.stale || !self.revalidate(device) {
+            DeviceVerdict::Gone
+        } else {
+            let grabbed = grab::holder(&device.path).ok().flatten().is_some();
+            match self.check_device_responsive(&device.path, grabbed) {
+                Ok(_) if grabbed => DeviceVerdict::Grabbed,
+                Ok(check) if check.passed() => DeviceVerdict::Working,
+                Ok(_) => DeviceVerdict::NotResponsive,
+                Err(_) => DeviceVerdict::Failed,
+            }
+        };
+
+        *slot = Some(verdict);
+        Ok(verdict)
+    }
+
+    /// Collects and logs the results of the verification of every device.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - Number of devices that are not working, including the ones
+    ///   gone or not verified; grabbed devices are not counted
+    pub fn verify_all_end(&mut self) -> usize {
+        let mut not_working = 0;
+        for device in self.devices.iter() {
+            let verdict = device.slot.lock().and_then(|verdict| *verdict);
+            if !matches!(verdict, Some(DeviceVerdict::Working | DeviceVerdict::Grabbed)) {
+                not_working += 1;
+            }
+            kprint!("{} {} ({}): {}\n", device.device_type.as_str(), device.name, device.path,
+                   verdict.map_or("not verified", |verdict| verdict.as_str()));
+            if let Ok(device_handlers) = handlers::list(&device.path) {
+                kprint!("  handlers: {}\n", handlers::describe(&device_handlers));
+            }
+            if device.device_type == DeviceType::PointingStick {
+                self.read_trackpoint_info(device).log();
+            }
+        }
+        kprint!("Verified {} input devices, {} not working\n", self.devices.len(), not_working);
+        not_working
+    }
+
+    /// Finds a touchpad by path, or by name if its node changed after a rescan.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path the touchpad had
+    /// * `name` - Name of the touchpad
+    /// * `done` - Paths already verified in this run, never returned, so a touchpad
+    ///   sharing its name with another one isn't verified twice
+    fn find_touchpad(&self, path: &str, name: &str, done: &[String]) -> Option<&Touchpad> {
+        let candidates = || self.touchpads.iter().filter(|touchpad| !done.contains(&touchpad.path));
+        candidates()
+            .find(|touchpad| touchpad.path == path && touchpad.name == name)
+            .or_else(|| candidates().find(|touchpad| touchpad.name == name))
+    }
+
+    /// Verifies a single touchpad, attempting recovery if enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `touchpad_path` - Path to the touchpad device node
+    /// * `touchpad_name` - Name of the touchpad
+    /// * `fn_keys` - Result of the special key check of this run, None if it didn't run
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, VerifyError>` - Ok with the working flag, Err with `DeviceGone` if
+    ///   the touchpad disappeared, Err with `Failed` if a phase could not run
+    fn verify_one(
+        &mut self,
+        touchpad_path: &str,
+        touchpad_name: &str,
+        fn_keys: Option<&FnKeyCheck>,
+    ) -> Result<bool, VerifyError> {
+        if !self.revalidate_touchpad(touchpad_path) {
+            return Err(VerifyError::DeviceGone);
+        }
+        
+        kprint!("Verifying touchpad functionality for: {}\n", touchpad_name);
+        
+        let mut report = VerificationReport::new(touchpad_name.to_string(), touchpad_path.to_string());
+        notify(Notification::VerificationStarted, touchpad_path, 0);
+        STATS.inc(Counter::VerificationsRun);
+        
+        report.working = self.run_phases(touchpad_path, &mut report)
+            .map_err(|_| VerifyError::Failed)?;
+        
+        if !report.working && self.load_missing_modules && !report.missing_modules.is_empty()
+            && self.permits(GuardedAction::LoadModule) {
+            report = self.recover_by_loading_modules(report).map_err(|_| VerifyError::Failed)?;
+        }
+        
+        if !report.working && self.recovery_enabled && recovery::is_driver_unresponsive(&report)
+            && self.permits(GuardedAction::Rebind) {
+            report = self.recover_by_rebind(report).map_err(|_| VerifyError::Failed)?;
+        }
+
+        // Escalates after the rebind, a reset that helps where the rebind didn't points at hung firmware
+        if !report.working && self.reset_gpio_enabled && recovery::is_driver_unresponsive(&report)
+            && recovery::has_reset_gpio(&report) && self.permits(GuardedAction::ResetGpio) {
+            report = self.recover_by_reset_gpio(report).map_err(|_| VerifyError::Failed)?;
+        }
+
+        // Machine wide, and doesn't affect the touchpad result
+        report.fn_keys = fn_keys.cloned();
+
+        kprint!("Touchpad verification complete for {}: {}\n", touchpad_name,
+               if report.working { "working" } else { "not working" });
+        
+        Ok(self.finish_report(report))
+    }
+
+    /// Runs the verification phases on a touchpad and records them in the report.
+    ///
+    /// Stops at the first failing phase, except for the informational palm check.
+    ///
+    /// # Arguments
+    ///
+    /// * `touchpad_path` - Path to the touchpad device node
+    /// * `report` - Report to record the phase outcomes in
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, ()>` - Ok with true if all phases passed, Err if a phase could not run
+    fn run_phases(&mut self, touchpad_path: &str, report: &mut VerificationReport) -> Result<bool, ()> {
+        // Check if required kernel modules are loaded
+        let started = now_us();
+        let modules = self.check_touchpad_modules(report);
+        self.end_phase(&mut report.timings, Phase::Modules, started);
+        trace::phase(touchpad_path, Phase::Modules, trace::outcome(&modules));
+        match modules {
+            Ok(true) => {
+                kprint!("Touchpad modules are loaded correctly\n");
+                report.modules_loaded = true;
+            },
+            Ok(false) => {
+                kprint!("Required touchpad modules not loaded\n");
+                STATS.inc(Counter::FailuresModulesMissing);
+                return Ok(false);
+            },
+            Err(_) => {
+                kprint!("Failed to check touchpad modules\n");
+                STATS.inc(Counter::FailuresError);
+                return Err(());
+            }
+        }
+        
+        // A grab by another client starves every other consumer and fails the
+        // roundtrip below, so it is recorded first to explain the failure
+        report.grab.held_by = grab::holder(touchpad_path).ok().flatten();
+        if let Some(holder) = report.grab.held_by.as_ref() {
+            kprint!("Touchpad is grabbed by {}, other consumers receive no events\n", holder);
+        }
+        report.handlers = handlers::list(touchpad_path).unwrap_or_default();
+
+        // Board bring-up errors in the firmware description explain a touchpad
+        // that probed but never reports; they don't fail the verification
+        report.fw_node = fwnode::read(touchpad_path).ok().flatten();
+        if let Some(fw_node) = report.fw_node.as_ref() {
+            for mismatch in fw_node.mismatches() {
+                kprint!("Firmware description of the touchpad: {}\n", mismatch);
+            }
+        }
+
+        // The stick shares the touchpad's PS/2 port on many laptops, so it is read
+        // with the touchpad; it doesn't affect the touchpad result
+        report.trackpoint = self.devices.iter()
+            .find(|dev| dev.device_type == DeviceType::PointingStick && !dev.stale)
+            .map(|dev| self.read_trackpoint_info(dev));
+        
+        // Verify device node is responsive
+        let started = now_us();
+        let responsive = self.check_device_responsive(touchpad_path, report.grab.held_by.is_some());
+        self.end_phase(&mut report.timings, Phase::Responsive, started);
+        trace::phase(touchpad_path, Phase::Responsive, trace::outcome(&responsive.map(|check| check.passed())));
+        match responsive {
+            Ok(check) if check.passed() => {
+                kprint!("Touchpad device node is responsive\n");
+                report.responsive = check;
+                report.node_responsive = true;
+            },
+            Ok(check) => {
+                kprint!("Touchpad device node is not responsive: {}\n",
+                       check.failed_reason().unwrap_or(Reason::VerificationFailed));
+                report.responsive = check;
+                STATS.inc(Counter::FailuresNodeUnresponsive);
+                return Ok(false);
+            },
+            Err(_) => {
+                kprint!("Failed to check touchpad device node\n");
+                STATS.inc(Counter::FailuresError);
+                return Err(());
+            }
+        }
+        
+        // The remaining phases are skipped by the quick profile
+        if !self.runs(Phase::Events) {
+            return Ok(true);
+        }
+        
+        // Verify input event generation
+        let started = now_us();
+        let events = self.check_input_events(touchpad_path, report);
+        self.end_phase(&mut report.timings, Phase::Events, started);
+        trace::phase(touchpad_path, Phase::Events, trace::outcome(&events.map(|result| result.is_pass())));
+        let working = match events {
+            Ok(result) if result.is_pass() => {
+                kprint!("Touchpad can generate input events ({})\n", result.as_str());
+                true
+            },
+            Ok(EventCheckResult::Grabbed) => {
+                kprint!("Touchpad events can't be checked while another client grabs it\n");
+                false
+            },
+            Ok(result) => {
+                kprint!("Touchpad cannot generate input events ({})\n", result.as_str());
+                STATS.inc(if result == EventCheckResult::NotCapable {
+                    Counter::FailuresNotCapable
+                } else {
+                    Counter::FailuresNoEvents
+                });
+                false
+            },
+            Err(_) => {
+                kprint!("Failed to check touchpad event generation\n");
+                STATS.inc(Counter::FailuresError);
+                return Err(());
+            }
+        };
+
+        // A degraded mode is flagged in the report, the touchpad still works
+        if self.runs(Phase::Multitouch) {
+            let started = now_us();
+            let multitouch = self.check_multitouch(touchpad_path, report).map(|_| !report.multitouch.degraded());
+            self.end_phase(&mut report.timings, Phase::Multitouch, started);
+            trace::phase(touchpad_path, Phase::Multitouch, trace::outcome(&multitouch));
+            if multitouch.is_err() {
+                kprint!("Failed to check touchpad multitouch mode\n");
+            }
+        }
+
+        if !self.runs(Phase::Palm) {
+            return Ok(working);
+        }
+
+        // Palm data is informational and does not affect the working flag
+        let started = now_us();
+        let palm = self.check_palm_data(touchpad_path, report).map(|_| report.palm.usable);
+        self.end_phase(&mut report.timings, Phase::Palm, started);
+        trace::phase(touchpad_path, Phase::Palm, trace::outcome(&palm));
+        if palm.is_err() {
+            kprint!("Failed to check touchpad palm data\n");
+        }
+
+        Ok(working)
+    }
+
+    /// Checks that a device node responds, step by step.
+    ///
+    /// Runs through the input handle the event monitor holds on every device:
+    /// opens the device, queries its capabilities and injects an EV_SYN frame,
+    /// which must be delivered back through the input core. The steps stop at
+    /// the first failure. The open step is not run when the device was already
+    /// open, since the driver never sees that open, and the roundtrip is not run
+    /// when the safety policy forbids injecting events or another client grabbed
+    /// the device, which would receive the frame alone; see `ResponsiveCheck`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the device node
+    /// * `grabbed` - Whether another client holds a grab on the device
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ResponsiveCheck, ()>` - Outcome of each step, Err if the check could not run
+    fn check_device_responsive(&self, path: &str, grabbed: bool) -> Result<ResponsiveCheck, ()> {
+        let Some(kernel_check_node) = shim::ops().check_node else {
+            return Err(());
+        };
+
+        // Bits of the steps that passed, as returned by kernel_check_node
+        const NODE_OPENED: i32 = 1 << 0;
+        const NODE_CAPABILITIES: i32 = 1 << 1;
+        const NODE_SYN_ROUNDTRIP: i32 = 1 << 2;
+        const NODE_OPEN_SHARED: i32 = 1 << 3;
+        const ENODEV: i32 = 19;
+
+        // Checked quietly, a read-only policy skips the roundtrip on every verification
+        let inject = !grabbed && self.safety.permits(GuardedAction::SynProbe);
+        let path_bytes = path.as_bytes();
+        let passed = match unsafe { kernel_check_node(path_bytes.as_ptr(), path_bytes.len(), inject) } {
+            result if result >= 0 => result,
+            result if result == -ENODEV => 0,
+            _ => return Err(()),
+        };
+
+        let mut failed = false;
+        let mut step = |bit: i32| {
+            if failed {
+                StepOutcome::NotRun
+            } else if passed & bit != 0 {
+                StepOutcome::Passed
+            } else {
+                failed = true;
+                StepOutcome::Failed
+            }
+        };
+
+        let opened = step(NODE_OPENED);
+        Ok(ResponsiveCheck {
+            opened: if opened == StepOutcome::Passed && passed & NODE_OPEN_SHARED != 0 {
+                StepOutcome::NotRun
+            } else {
+                opened
+            },
+            capabilities: step(NODE_CAPABILITIES),
+            syn_roundtrip: if inject { step(NODE_SYN_ROUNDTRIP) } else { StepOutcome::NotRun },
         })
     }
-    
-    /// Scans the system for input devices with focus on touchpad devices.
+
+    /// Checks if the kernel modules the touchpad needs are loaded.
     ///
-    /// Performs a system scan to detect input devices connected to the system.
-    /// Updates the internal state to track discovered devices.
+    /// The required modules depend on how the touchpad is attached: PS/2
+    /// touchpads need psmouse, everything else is assumed to be an I2C HID
+    /// touchpad needing the i2c-hid core and hid-multitouch. A module counts
+    /// as loaded if it appears under /sys/module.
+    ///
+    /// # Arguments
+    ///
+    /// * `report` - Report to record missing modules in
     ///
     /// # Returns
     ///
-    /// * `Result<(), ()>` - Ok if the scan completes successfully, Err otherwise
-    pub fn scan_devices(&mut self) -> Result<(), ()> {
-        kprint!("Scanning for input devices...\n");
+    /// * `Result<bool, ()>` - Ok with true if all required modules are loaded, Err if
+    ///   /sys/module can't be read
+    fn check_touchpad_modules(&self, report: &mut VerificationReport) -> Result<bool, ()> {
+        let loaded = self.read_sysfs_directory("/sys/module")?;
+        let required = self.required_touchpad_modules(&report.device_name);
         
-        let input_devices = self.read_input_devices()?;
+        report.missing_modules = required.iter()
+            .filter(|module| !loaded.iter().any(|entry| entry == *module))
+            .map(|module| module.to_string())
+            .collect();
         
-        match self.identify_touchpad(&input_devices) {
-            Ok((found, path, name)) => {
-                self.touchpad_found = found;
-                self.touchpad_path = path;
-                self.touchpad_name = name;
-                
-                if self.touchpad_found {
-                    kprint!("Touchpad device found: {}\n", self.touchpad_name.as_ref().unwrap());
-                    kprint!("Touchpad path: {}\n", self.touchpad_path.as_ref().unwrap());
-                } else {
-                    kprint!("No touchpad device identified\n");
-                }
-                
-                kprint!("Input device scan complete\n");
-                Ok(())
-            },
-            Err(_) => {
-                kprint!("Failed to identify touchpad\n");
-                Err(())
-            }
+        for module in report.missing_modules.iter() {
+            kprint!("Required touchpad module not loaded: {}\n", module);
+        }
+        
+        Ok(report.missing_modules.is_empty())
+    }
+    
+    /// Returns the modules a touchpad needs, as named under /sys/module.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the touchpad
+    fn required_touchpad_modules(&self, name: &str) -> &'static [&'static str] {
+        if name.contains("PS/2") || name.contains("ETPS") {
+            PS2_TOUCHPAD_MODULES
+        } else {
+            I2C_HID_TOUCHPAD_MODULES
         }
     }
 
-    /// Reads input devices from sysfs and proc.
+    /// Attempts to recover a touchpad with missing modules by loading them.
+    ///
+    /// Each missing module is requested, then the devices are rescanned until
+    /// the touchpad reappears with all modules loaded or the wait times out,
+    /// and the phases run again.
+    ///
+    /// # Arguments
+    ///
+    /// * `failed` - Report of the failed verification run
     ///
     /// # Returns
     ///
-    /// * `Result<Vec<DeviceInfo>, ()>` - Vector of input device info or error
-    fn read_input_devices(&self) -> Result<Vec<DeviceInfo>, ()> {
-        let mut devices = Vec::new();
-        
-        // Call the kernel FFI function to get input devices
-        let device_entries = self.read_sysfs_directory("/sys/class/input")?;
+    /// * `Result<VerificationReport, ()>` - Report of the verification after loading the
+    ///   modules (or the failed report if they could not be loaded), Err if a rescan failed
+    fn recover_by_loading_modules(&mut self, mut failed: VerificationReport) -> Result<VerificationReport, ()> {
+        let modules = failed.missing_modules.join(",");
+        kprint!("Loading missing touchpad modules: {}\n", modules);
         
-        for entry in device_entries {
-            if !entry.starts_with("event") {
-                continue;
+        let mut loaded_any = false;
+        for module in failed.missing_modules.iter() {
+            if recovery::request_module(module).is_ok() {
+                loaded_any = true;
+            } else {
+                kprint!("Failed to load module {}\n", module);
             }
+        }
+        
+        if !loaded_any {
+            failed.recovery = Some(RecoveryAttempt::new(RecoveryAction::LoadModule, modules, false));
+            return Ok(failed);
+        }
+        
+        // Wait for the touchpad to be re-probed by the newly loaded driver
+        let mut waited = 0;
+        loop {
+            sleep_ms(recovery::MODULE_POLL_MS);
+            waited += recovery::MODULE_POLL_MS;
+            self.scan_devices()?;
             
-            let device_path = alloc::format!("/dev/input/{}", entry);
-            let sys_path = alloc::format!("/sys/class/input/{}", entry);
-            
-            match self.read_device_name(&sys_path) {
-                Ok(name) => {
-                    kprint!("Found input device: {} at {}\n", name, device_path);
-                    
-                    let device_type = if self.is_touchpad_by_name(&name) {
-                        DeviceType::Touchpad
-                    } else if name.contains("keyboard") || name.contains("Keyboard") {
-                        DeviceType::Keyboard
-                    } else if name.contains("mouse") || name.contains("Mouse") {
-                        DeviceType::Mouse
-                    } else {
-                        self.determine_device_type(&device_path).unwrap_or(DeviceType::Unknown)
-                    };
-                    
-                    devices.push(DeviceInfo {
-                        name,
-                        path: device_path,
-                        device_type,
-                    });
-                },
-                Err(_) => continue, 
+            let mut probe = VerificationReport::new(failed.device_name.clone(), String::new());
+            let found = self.find_touchpad(&failed.device_path, &failed.device_name, &[]).is_some();
+            let ready = found && self.check_touchpad_modules(&mut probe)?;
+            if ready || waited >= recovery::MODULE_LOAD_WAIT_MS {
+                break;
             }
         }
         
-        Ok(devices)
+        let Some(touchpad_path) = self.find_touchpad(&failed.device_path, &failed.device_name, &[])
+            .map(|touchpad| touchpad.path.clone()) else {
+            kprint!("Touchpad did not appear after loading {}\n", modules);
+            failed.recovery = Some(RecoveryAttempt::new(RecoveryAction::LoadModule, modules, false));
+            return Ok(failed);
+        };
+        
+        let mut report = VerificationReport::new(failed.device_name.clone(), touchpad_path.clone());
+        report.working = self.run_phases(&touchpad_path, &mut report)?;
+        
+        if report.working {
+            kprint!("Touchpad recovered by loading {}\n", modules);
+        }
+        report.recovery = Some(RecoveryAttempt::new(RecoveryAction::LoadModule, modules, report.working));
+        Ok(report)
     }
 
-    /// Reads the name of an input device from sysfs.
+    /// Attempts to recover an unresponsive touchpad by rebinding its driver.
+    ///
+    /// After the rebind the input device is recreated, possibly under a new
+    /// node, so the devices are rescanned and the phases run again on the
+    /// touchpad found by the new scan.
     ///
     /// # Arguments
     ///
-    /// * `path` - Path to device directory in sysfs
+    /// * `failed` - Report of the failed verification run
     ///
     /// # Returns
     ///
-    /// * `Result<String, ()>` - Device name or error
-    fn read_device_name(&self, path: &str) -> Result<String, ()> {
-        let name_path = alloc::format!("{}/device/name", path);
-        self.read_file_contents(&name_path)
+    /// * `Result<VerificationReport, ()>` - Report of the verification after the recovery
+    ///   attempt (or the failed report if the rebind itself failed), Err if a rescan failed
+    fn recover_by_rebind(&mut self, mut failed: VerificationReport) -> Result<VerificationReport, ()> {
+        kprint!("Touchpad driver bound but unresponsive, attempting driver rebind\n");
+        
+        let sys_path = recovery::sysfs_path(&failed.device_path);
+        let driver = match recovery::rebind_driver(&sys_path) {
+            Ok(driver) => driver,
+            Err(_) => {
+                kprint!("Driver rebind failed\n");
+                failed.recovery = Some(RecoveryAttempt::new(RecoveryAction::Rebind, String::new(), false));
+                return Ok(failed);
+            }
+        };
+        
+        sleep_ms(recovery::REBIND_SETTLE_MS);
+        self.scan_devices()?;
+        
+        let Some(touchpad_path) = self.find_touchpad(&failed.device_path, &failed.device_name, &[])
+            .map(|touchpad| touchpad.path.clone()) else {
+            kprint!("Touchpad did not reappear after driver rebind\n");
+            failed.recovery = Some(RecoveryAttempt::new(RecoveryAction::Rebind, driver, false));
+            return Ok(failed);
+        };
+        
+        let mut report = VerificationReport::new(failed.device_name.clone(), touchpad_path.clone());
+        report.working = self.run_phases(&touchpad_path, &mut report)?;
+        
+        kprint!("Driver rebind of {} {}\n", driver,
+                if report.working { "recovered the touchpad" } else { "did not help" });
+        report.recovery = Some(RecoveryAttempt::new(RecoveryAction::Rebind, driver, report.working));
+        Ok(report)
     }
 
-    /// Determines device type based on capabilities in /proc/bus/input/devices.
+    /// Pulses the reset GPIO of an unresponsive touchpad and verifies it again.
+    ///
+    /// Like a rebind, the reset recreates the input device, so the devices are
+    /// rescanned before the phases run again.
     ///
     /// # Arguments
     ///
-    /// * `path` - Path to device node
+    /// * `failed` - Report of the failed verification run
     ///
     /// # Returns
     ///
-    /// * `Result<DeviceType, ()>` - Device type or error
-    fn determine_device_type(&self, path: &str) -> Result<DeviceType, ()> {
-        unsafe {
-            extern "C" {
-                fn kernel_get_device_capabilities(
-                    path: *const u8,
-                    path_len: usize,
-                    abs_support: *mut u32,     // For EV_ABS support
-                    rel_support: *mut u32,     // For EV_REL support
-                    key_support: *mut u32      // For EV_KEY support
-                ) -> i32;
-            }
-            
-            let path_bytes = path.as_bytes();
-            let mut abs_support: u32 = 0;
-            let mut rel_support: u32 = 0;
-            let mut key_support: u32 = 0;
-            
-            let result = kernel_get_device_capabilities(
-                path_bytes.as_ptr(),
-                path_bytes.len(),
-                &mut abs_support,
-                &mut rel_support,
-                &mut key_support
-            );
-            
-            if result < 0 {
-                return Err(());
-            }
-            
-            // Check for ABS_MT_POSITION_X (0x35) and ABS_MT_POSITION_Y (0x36) due them absolute
-            // positioning
-            if (abs_support & (1 << 0x35)) != 0 && (abs_support & (1 << 0x36)) != 0 {
-                return Ok(DeviceType::Touchpad);
-            }
-            
-            // Check for REL_X (0x00) and REL_Y (0x01) due the possibility to have a relative positioning
-            if (rel_support & (1 << 0x00)) != 0 && (rel_support & (1 << 0x01)) != 0 {
-                return Ok(DeviceType::Mouse);
-            }
-            
-            // Keyboard check might involve KEY_A through KEY_Z
-            let has_letter_keys = (0x04..=0x1D).any(|key_code| (key_support & (1 << key_code)) != 0);
-            if has_letter_keys {
-                return Ok(DeviceType::Keyboard);
-            }
-            
-            Ok(DeviceType::Unknown)
+    /// * `Result<VerificationReport, ()>` - Report of the verification after the reset (or
+    ///   the failed report if the reset itself failed), Err if a rescan failed
+    fn recover_by_reset_gpio(&mut self, mut failed: VerificationReport) -> Result<VerificationReport, ()> {
+        kprint!("Touchpad still unresponsive, pulsing its reset GPIO\n");
+
+        let target = failed.fw_node.as_ref().and_then(|fw_node| fw_node.id.clone()).unwrap_or_default();
+        if recovery::reset_device(&failed.device_path, recovery::RESET_ASSERT_MS).is_err() {
+            kprint!("Reset GPIO pulse failed\n");
+            failed.recovery = Some(RecoveryAttempt::new(RecoveryAction::ResetGpio, target, false));
+            return Ok(failed);
         }
+
+        sleep_ms(recovery::REBIND_SETTLE_MS);
+        self.scan_devices()?;
+
+        let Some(touchpad_path) = self.find_touchpad(&failed.device_path, &failed.device_name, &[])
+            .map(|touchpad| touchpad.path.clone()) else {
+            kprint!("Touchpad did not reappear after the reset, the hardware is likely dead\n");
+            failed.recovery = Some(RecoveryAttempt::new(RecoveryAction::ResetGpio, target, false));
+            return Ok(failed);
+        };
+
+        let mut report = VerificationReport::new(failed.device_name.clone(), touchpad_path.clone());
+        report.working = self.run_phases(&touchpad_path, &mut report)?;
+
+        kprint!("Reset GPIO pulse {}\n", if report.working {
+            "recovered the touchpad, its controller firmware had hung"
+        } else {
+            "did not help, the hardware is likely dead"
+        });
+        report.recovery = Some(RecoveryAttempt::new(RecoveryAction::ResetGpio, target, report.working));
+        Ok(report)
     }
 
-    /// Reads sysfs directory entries.
+    /// Logs a finished report and keeps it as the latest report of its touchpad.
     ///
     /// # Arguments
     ///
-    /// * `path` - Directory path
+    /// * `report` - Report of the verification run that just finished
     ///
     /// # Returns
     ///
-    /// * `Result<Vec<String>, ()>` - Directory entries or error
-    fn read_sysfs_directory(&self, path: &str) -> Result<Vec<String>, ()> {
-        unsafe {
-            extern "C" {
-                fn kernel_read_directory(
-                    path: *const u8,
-                    path_len: usize,
-                    callback: unsafe extern "C" fn(*const u8, usize, *mut Vec<String>) -> i32,
-                    output: *mut Vec<String>
-                ) -> i32;
-            }
-            
-            unsafe extern "C" fn dir_callback(entry: *const u8, entry_len: usize, output: *mut Vec<String>) -> i32 {
-                let entry_slice = core::slice::from_raw_parts(entry, entry_len);
-                if let Ok(entry_str) = core::str::from_utf8(entry_slice) {
-                    if !entry_str.starts_with(".") {  // Skip hidden files
-                        (*output).push(entry_str.to_string());
-                    }
-                }
-                0  
-            }
-            
-            let mut entries = Vec::new();
-            let path_bytes = path.as_bytes();
-            
-            let result = kernel_read_directory(
-                path_bytes.as_ptr(),
-                path_bytes.len(),
-                dir_callback,
-                &mut entries as *mut Vec<String>
-            );
-            
-            if result < 0 {
-                kprint!("Failed to read directory: {}\n", path);
-                return Err(());
+    /// * `bool` - The working flag recorded in the report
+    fn finish_report(&mut self, mut report: VerificationReport) -> bool {
+        let working = report.working;
+        report.profile = self.profile;
+        report.safety = self.safety;
+        if let Some(device) = self.devices.iter().find(|dev| dev.path == report.device_path) {
+            report.stable_name = device.stable_name().map(|name| name.to_string());
+            report.device_type = device.device_type;
+            report.udev = device.udev;
+            report.props = device.props;
+            report.firmware = Some(device.firmware.clone());
+            report.note = self.expectations.note_for(device);
+            report.seat = device.seat.clone();
+        }
+        // Machine wide, recorded so a report shows mixed userspace breakage without the module log
+        report.evdev_abi = self.evdev_abi.clone();
+        report.kernel_messages = self.read_kernel_messages(&report);
+        if self.verbosity >= Verbosity::Normal {
+            report.log();
+        }
+
+        notify(Notification::VerificationFinished, &report.device_path, working as i32);
+        if let Some(recovery) = report.recovery.as_ref() {
+            let detail = alloc::format!("{}\t{}", recovery.action.token(), recovery.target);
+            self.incidents.record(IncidentKind::RecoveryAttempted, &report.device_path,
+                                  (!recovery.succeeded).then_some(Reason::RecoveryFailed), detail);
+        }
+
+        let Some(touchpad) = self.touchpads.iter_mut()
+            .find(|touchpad| touchpad.path == report.device_path) else {
+            return working;
+        };
+        if touchpad.working().is_some_and(|previous| previous != working) {
+            notify(Notification::StatusChanged, &report.device_path, working as i32);
+            if !working {
+                let reason = report.reasons().first().copied().unwrap_or(Reason::VerificationFailed);
+                self.incidents.record(IncidentKind::VerificationRegressed, &report.device_path, Some(reason),
+                                      String::new());
             }
-            
-            Ok(entries)
         }
+
+        touchpad.report = Some(report);
+        working
     }
-    
-    /// Reads file contents from sysfs or proc.
+
+    /// Checks whether the touchpad generates input events.
+    ///
+    /// With the interactive profile the user is prompted to touch the touchpad and
+    /// the input handler is watched for up to the configured window; the check passes
+    /// with `Verified` only if events actually arrive. The deep profile watches for
+    /// events without prompting and falls back to the capabilities if none arrive.
+    /// Otherwise no events are waited for and the check passes with `CapabilityOnly`
+    /// if the device advertises absolute X/Y axes.
     ///
     /// # Arguments
     ///
-    /// * `path` - File path
+    /// * `path` - Path to the touchpad device node
+    /// * `report` - Report to record the event check outcome in
     ///
     /// # Returns
     ///
-    /// * `Result<String, ()>` - File contents or error
-    fn read_file_contents(&self, path: &str) -> Result<String, ()> {
-        unsafe {
-            extern "C" {
-                fn kernel_read_file(
-                    path: *const u8,
-                    path_len: usize,
-                    buffer: *mut u8,
-                    buffer_size: usize,
-                    bytes_read: *mut usize
-                ) -> i32;
-            }
-            
-            let path_bytes = path.as_bytes();
-            let mut buffer = alloc::vec![0u8; 256];  
-            let mut bytes_read: usize = 0;
-            
-            let result = kernel_read_file(
-                path_bytes.as_ptr(),
-                path_bytes.len(),
-                buffer.as_mut_ptr(),
-                buffer.len(),
-                &mut bytes_read
-            );
-            
-            if result < 0 || bytes_read == 0 {
-                return Err(());
-            }
-            
-            // Truncate buffer to actual size and remove any trailing whitespace
-            buffer.truncate(bytes_read);
-            while buffer.last() == Some(&b'\n') || buffer.last() == Some(&b'\r') || buffer.last() == Some(&b' ') {
-                buffer.pop();
+    /// * `Result<EventCheckResult, ()>` - Outcome of the check, or Err if it could not run
+    fn check_input_events(&self, path: &str, report: &mut VerificationReport) -> Result<EventCheckResult, ()> {
+        // Events of a grabbed device reach the grabbing client only, waiting for them proves nothing
+        if report.grab.held_by.is_some() {
+            report.event_check = EventCheckResult::Grabbed;
+            return Ok(EventCheckResult::Grabbed);
+        }
+
+        let window_ms = self.profile.event_window_ms(self.event_window_ms);
+        let events = if window_ms > 0 {
+            EVENT_MONITOR.attach(TOUCHPAD_SLOT, path)?;
+            EVENT_MONITOR.set_large_touch_threshold(TOUCHPAD_SLOT, self.large_touch_threshold(path));
+
+            let grab = if self.exclusive_grab && self.permits(GuardedAction::ExclusiveGrab) {
+                let grab = ExclusiveGrab::acquire(path).ok();
+                if grab.is_none() {
+                    kprint!("Could not grab {}, checking events without exclusive access\n", path);
+                }
+                grab
+            } else {
+                None
+            };
+            report.grab.exclusive = grab.is_some();
+
+            if self.profile == Profile::Interactive {
+                kprint!("Touch the touchpad now ({} ms)...\n", window_ms);
+            } else {
+                kprint!("Watching the touchpad for events ({} ms)...\n", window_ms);
             }
-            
-            match String::from_utf8(buffer) {
-                Ok(contents) => Ok(contents),
-                Err(_) => Err(())
+            let started_ms = now_ms();
+            let events = EVENT_MONITOR.wait_for_events(TOUCHPAD_SLOT, window_ms);
+            // The wait ends at the first frame, the palm phase needs the sizes of whole contacts
+            if events > 0 && self.runs(Phase::Palm) {
+                let elapsed_ms = now_ms().saturating_sub(started_ms) as u32;
+                EVENT_MONITOR.wait_for_lift(TOUCHPAD_SLOT, window_ms.saturating_sub(elapsed_ms));
             }
-        }
+            drop(grab);
+            EVENT_MONITOR.detach(TOUCHPAD_SLOT);
+
+            report.events_observed = events;
+            report.event_window_ms = window_ms;
+            events
+        } else {
+            0
+        };
+
+        let result = if events > 0 {
+            EventCheckResult::Verified
+        } else if self.profile == Profile::Interactive {
+            EventCheckResult::NoEvents
+        } else if self.has_touchpad_axes(path)? {
+            EventCheckResult::CapabilityOnly
+        } else {
+            EventCheckResult::NotCapable
+        };
+
+        report.event_check = result;
+        Ok(result)
     }
 
-    /// Identifies a touchpad device from a list of input devices.
+    /// Checks whether the touchpad provides the data needed for palm rejection.
+    ///
+    /// The contact size and tool type axes are read from the capabilities. If the
+    /// event check monitored real events, which it then keeps doing until the
+    /// contacts lifted or its window ended, the touch sizes seen are included and
+    /// an advertised ABS_MT_TOUCH_MAJOR axis only counts as usable if it actually
+    /// reported non-zero sizes.
     ///
     /// # Arguments
     ///
-    /// * `devices` - List of device information structures
+    /// * `path` - Path to the touchpad device node
+    /// * `report` - Report to record the palm data in
     ///
     /// # Returns
     ///
-    /// * `Result<(bool, Option<String>, Option<String>), ()>` - Tuple with: 
-    ///   - found flag
-    ///   - optional device path
-    ///   - optional device name
-    fn identify_touchpad(&self, devices: &[DeviceInfo]) -> Result<(bool, Option<String>, Option<String>), ()> {
-        // First check for devices already identified as touchpads
-        if let Some(device) = devices.iter().find(|dev| dev.device_type == DeviceType::Touchpad) {
-            kprint!("Found explicit touchpad device: {}\n", device.name);
-            return Ok((true, Some(device.path.clone()), Some(device.name.clone())));
+    /// * `Result<(), ()>` - Ok if the check ran, Err if capabilities can't be read
+    fn check_palm_data(&self, path: &str, report: &mut VerificationReport) -> Result<(), ()> {
+        let caps = self.read_capabilities(path)?;
+        let mut palm = PalmCheck {
+            has_touch_major: caps.has_abs(ABS_MT_TOUCH_MAJOR),
+            has_touch_minor: caps.has_abs(ABS_MT_TOUCH_MINOR),
+            has_tool_type: caps.has_abs(ABS_MT_TOOL_TYPE),
+            has_pressure: caps.has_abs(ABS_MT_PRESSURE) || caps.has_abs(ABS_PRESSURE),
+            ..PalmCheck::default()
+        };
+
+        palm.observed = report.event_check == EventCheckResult::Verified;
+        if palm.observed {
+            let counts = EVENT_MONITOR.pattern_counts(TOUCHPAD_SLOT);
+            palm.max_touch_major = counts.max_touch_major;
+            palm.large_contacts = counts.large_contacts;
+            palm.palm_reports = counts.palm_reports;
         }
-        
-        // If not found by type check for it indicators in name
-        for device in devices {
-            if self.is_touchpad_by_name(&device.name) {
-                kprint!("Identified touchpad by name: {}\n", device.name);
-                return Ok((true, Some(device.path.clone()), Some(device.name.clone())));
-            }
+
+        let size_usable = palm.has_touch_major && (!palm.observed || palm.max_touch_major > 0);
+        palm.usable = palm.has_tool_type || size_usable;
+
+        if palm.large_contacts > 0 || palm.palm_reports > 0 {
+            kprint!("Touchpad reported {} implausibly large contacts and {} palm contacts\n",
+                    palm.large_contacts, palm.palm_reports);
         }
-        
-        Ok((false, None, None))
+
+        report.palm = palm;
+        Ok(())
     }
-    
-    /// Checks if a device is a touchpad based on its name.
+
+    /// Collects the recent kernel log errors and warnings about a touchpad.
+    ///
+    /// Driver messages are prefixed with the name of the physical device, which
+    /// for HID touchpads is the HID device while the bus driver logs with the
+    /// name of its parent, so both are looked for along with the input name.
     ///
     /// # Arguments
     ///
-    /// * `name` - Device name to check
+    /// * `report` - Report of the touchpad
     ///
     /// # Returns
     ///
-    /// * `bool` - True if the device name indicates a touchpad
-    fn is_touchpad_by_name(&self, name: &str) -> bool {
-        let name_lower = name.to_lowercase();
-        
-        name_lower.contains("touchpad") ||
-        name_lower.contains("trackpad") ||
-        name_lower.contains("glidepoint") ||
-        name_lower.contains("clickpad") ||
-        // Specific vendors
-        name.contains("ETPS") ||     // Elantech Touchpad
-        name.contains("ELAN") ||     // ELAN Touchpad (common in Acer laptops)
-        name.contains("04F3") ||     // ELAN Vendor ID
-        name.contains("Synaptics") || // Synaptics Touchpad
-        name.contains("ALPS") ||     // ALPS Touchpad
-        // Specific to Acer Nitro 5
-        name.contains("MSFT0001") || // Microsoft Precision Touchpad
-        name.contains("1A58:0271")   // Another common Acer Nitro 5 touchpad ID
+    /// * `Vec<String>` - Last matching log lines, oldest first
+    fn read_kernel_messages(&self, report: &VerificationReport) -> Vec<String> {
+        let sys_path = recovery::sysfs_path(&report.device_path);
+        let physical = recovery::resolve_path(&alloc::format!("{}/device/device", sys_path)).unwrap_or_default();
+        let parent = physical.rsplit_once('/').map(|(parent, _)| recovery::last_component(parent)).unwrap_or("");
+
+        kmsg::collect(&[&report.device_name, recovery::last_component(&physical), parent])
     }
 
-    /// Verifies if the touchpad is functioning correctly.
+    /// Determines the multitouch mode of a touchpad and flags a degraded mode.
+    ///
+    /// SEMI_MT and single-touch touchpads exist, so the mode alone is not a
+    /// failure; it is flagged as degraded when the quirk table knows the
+    /// hardware supports full multitouch with another driver.
+    ///
+    /// # Arguments
     ///
-    /// This function checks if a touchpad was found first, then attempts to
-    /// verify if it's working correctly by interacting with the device driver.
+    /// * `path` - Path to the touchpad device node
+    /// * `report` - Report to record the mode in
     ///
     /// # Returns
     ///
-    /// * `Result<bool, ()>` - Ok with true if touchpad is working, Ok with false if not working
-    ///                       or not found, and Err if verification process fails
-    pub fn verify_touchpad(&mut self) -> Result<bool, ()> {
-        if !self.touchpad_found {
-            kprint!("Touchpad not found, cannot verify\n");
-            return Ok(false);
-        }
-        
-        let Some(touchpad_path) = self.touchpad_path.as_ref() else {
-            kprint!("Touchpad path not available\n");
-            return Ok(false);
+    /// * `Result<(), ()>` - Ok if the check ran, Err if capabilities can't be read
+    fn check_multitouch(&self, path: &str, report: &mut VerificationReport) -> Result<(), ()> {
+        let caps = self.read_capabilities(path)?;
+        let device = self.devices.iter().find(|dev| dev.path == path);
+        let semi_mt = device
+            .and_then(|dev| dev.props)
+            .is_some_and(|props| props.semi_mt);
+
+        let mode = if caps.has_abs(ABS_MT_POSITION_X) {
+            if semi_mt { MultitouchMode::SemiMt } else { MultitouchMode::Full }
+        } else if caps.has_abs(ABS_X) {
+            MultitouchMode::SingleTouch
+        } else {
+            MultitouchMode::Unknown
         };
-        
-        kprint!("Verifying touchpad functionality for: {}\n", 
-                self.touchpad_name.as_ref().unwrap_or(&"Unknown".to_string()));
-        
-        // Check if required kernel modules are loaded
-        match self.check_touchpad_modules() {
-            Ok(true) => kprint!("Touchpad modules are loaded correctly\n"),
-            Ok(false) => {
-                kprint!("Required touchpad modules not loaded\n");
-                self.touchpad_working = false;
-                return Ok(false);
-            },
-            Err(_) => {
-                kprint!("Failed to check touchpad modules\n");
-                return Err(());
-            }
-        }
-        
-        // Verify device node is responsive
-        match self.check_device_responsive(touchpad_path) {
-            Ok(true) => kprint!("Touchpad device node is responsive\n"),
-            Ok(false) => {
-                kprint!("Touchpad device node is not responsive\n");
-                self.touchpad_working = false;
-                return Ok(false);
+
+        let degraded_hint = match mode {
+            MultitouchMode::SemiMt | MultitouchMode::SingleTouch => {
+                device.and_then(|dev| quirks::multitouch_quirk(&dev.raw_name)).map(|quirk| quirk.hint)
             },
-            Err(_) => {
-                kprint!("Failed to check touchpad device node\n");
-                return Err(());
-            }
+            _ => None,
+        };
+        if degraded_hint.is_some() {
+            kprint!("Touchpad runs in {} mode, the hardware supports full multitouch\n", mode.text());
+            STATS.inc(Counter::DegradedMultitouch);
         }
-        
-        // Verify input event capability
-        match self.check_input_events(touchpad_path) {
-            Ok(true) => {
-                kprint!("Touchpad can generate input events\n");
-                self.touchpad_working = true;
-            },
-            Ok(false) => {
-                kprint!("Touchpad cannot generate input events\n");
-                self.touchpad_working = false;
+
+        report.multitouch = MultitouchCheck { mode, degraded_hint };
+        Ok(())
+    }
+
+    /// Returns the ABS_MT_TOUCH_MAJOR value from which a contact is too large for a finger.
+    ///
+    /// Contacts covering more than three quarters of the touch size range are
+    /// treated as palms. Returns `i32::MAX` (never large) if the axis is missing.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the touchpad device node
+    fn large_touch_threshold(&self, path: &str) -> i32 {
+        match self.read_abs_info(path, ABS_MT_TOUCH_MAJOR) {
+            Ok(info) if info.maximum > info.minimum => {
+                info.minimum + (info.maximum - info.minimum) / 4 * 3
             },
-            Err(_) => {
-                kprint!("Failed to check touchpad event generation\n");
-                return Err(());
-            }
+            _ => i32::MAX,
         }
+    }
 
-        kprint!("Touchpad verification complete: {}\n", 
-               if self.touchpad_working { "working" } else { "not working" });
+    /// Checks if the device advertises the absolute X and Y axes of a touchpad.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to device node
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, ()>` - True if ABS_X and ABS_Y are supported, Err if capabilities can't be read
+    fn has_touchpad_axes(&self, path: &str) -> Result<bool, ()> {
+        let caps = self.read_capabilities(path)?;
         
-        Ok(self.touchpad_working)
+        // ABS_X (0x00) and ABS_Y (0x01)
+        Ok(caps.has_abs(0x00) && caps.has_abs(0x01))
     }
 }
 
@@ -454,14 +2810,12 @@ impl InputDeviceVerifier {
 #[macro_export]
 macro_rules! kprint {
     ($($arg:tt)*) => ({
-        extern "C" {
-            fn kernel_print(msg: *const u8, len: usize);
-        }
-        
-        let msg = alloc::format!($($arg)*);
-        let bytes = msg.as_bytes();
-        unsafe {
-            kernel_print(bytes.as_ptr(), bytes.len());
+        if let Some(kernel_print) = $crate::shim::ops().print {
+            let msg = alloc::format!($($arg)*);
+            let bytes = msg.as_bytes();
+            unsafe {
+                kernel_print(bytes.as_ptr(), bytes.len());
+            }
         }
     });
 }
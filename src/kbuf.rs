@@ -0,0 +1,194 @@
+//! Bounds-checked buffers at the FFI boundary.
+//!
+//! Buffers cross the boundary in three ways: C passes bytes in (device names,
+//! sysfs writes), C passes a buffer for Rust to fill (the read-style exports),
+//! and Rust passes a buffer for a shim to fill and report a length back
+//! (file contents, directory listings). Each way has one wrapper here that
+//! checks the pointer and length once on construction; the rest of the crate
+//! only sees slices and never trusts a length reported by C. This module holds
+//! every `slice::from_raw_parts` of the crate.
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use crate::text;
+
+/// Checks that `len` elements of `T` at `ptr` can form a slice.
+///
+/// A NULL pointer is only accepted for an empty buffer, which C callers
+/// commonly pass and `slice::from_raw_parts` does not allow.
+///
+/// # Returns
+///
+/// * `Result<bool, ()>` - Ok with true if the buffer is empty, Err if it can't form a slice
+fn check_raw<T>(ptr: *const T, len: usize) -> Result<bool, ()> {
+    if len == 0 {
+        return Ok(true);
+    }
+    if ptr.is_null() || !ptr.is_aligned() {
+        return Err(());
+    }
+    match len.checked_mul(size_of::<T>()) {
+        Some(bytes) if bytes <= isize::MAX as usize => Ok(false),
+        _ => Err(()),
+    }
+}
+
+/// Borrows an array passed from C.
+///
+/// # Safety
+///
+/// Unless `len` is zero, `ptr` must point to `len` readable elements that stay
+/// valid and unmodified for the lifetime `'a`.
+///
+/// # Returns
+///
+/// * `Result<&[T], ()>` - The elements, Err if the pointer is NULL or misaligned or the
+///   length overflows
+pub unsafe fn ffi_slice<'a, T>(ptr: *const T, len: usize) -> Result<&'a [T], ()> {
+    if check_raw(ptr, len)? {
+        Ok(&[])
+    } else {
+        Ok(core::slice::from_raw_parts(ptr, len))
+    }
+}
+
+/// Bytes passed from C, such as a device name or the text written to a sysfs attribute.
+#[derive(Clone, Copy)]
+pub struct KStr<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> KStr<'a> {
+    /// Borrows bytes passed from C.
+    ///
+    /// # Safety
+    ///
+    /// Unless `len` is zero, `ptr` must point to `len` readable bytes that stay
+    /// valid and unmodified for the lifetime `'a`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<KStr, ()>` - The bytes, Err if the pointer is NULL or the length overflows
+    pub unsafe fn from_raw(ptr: *const u8, len: usize) -> Result<Self, ()> {
+        Ok(Self { bytes: ffi_slice(ptr, len)? })
+    }
+
+    /// Returns the bytes as a string, Err if they aren't valid UTF-8.
+    pub fn to_str(self) -> Result<&'a str, ()> {
+        core::str::from_utf8(self.bytes).map_err(|_| ())
+    }
+
+    /// Returns the bytes as a string, escaping invalid UTF-8 and control characters.
+    pub fn escaped(&self) -> Cow<'a, str> {
+        text::escape_lossy(self.bytes)
+    }
+}
+
+/// A buffer C passes for Rust to fill, such as the page of a sysfs read.
+pub struct KBuf<'a, T: Copy = u8> {
+    slice: &'a mut [T],
+}
+
+impl<'a, T: Copy> KBuf<'a, T> {
+    /// Borrows a buffer passed from C.
+    ///
+    /// # Safety
+    ///
+    /// Unless `len` is zero, `ptr` must point to `len` writable elements that no
+    /// one else accesses for the lifetime `'a`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<KBuf, ()>` - The buffer, Err if the pointer is NULL or misaligned or the
+    ///   length overflows
+    pub unsafe fn from_raw(ptr: *mut T, len: usize) -> Result<Self, ()> {
+        if check_raw(ptr, len)? {
+            Ok(Self { slice: &mut [] })
+        } else {
+            Ok(Self { slice: core::slice::from_raw_parts_mut(ptr, len) })
+        }
+    }
+
+    /// Returns the buffer as a slice.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        self.slice
+    }
+
+    /// Copies as much of `data` as fits.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - Number of elements copied
+    pub fn fill(&mut self, data: &[T]) -> usize {
+        let len = data.len().min(self.slice.len());
+        self.slice[..len].copy_from_slice(&data[..len]);
+        len
+    }
+
+    /// Copies `data` only if it fits completely.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize, ()>` - Number of elements copied, Err if `data` doesn't fit
+    pub fn write_all(&mut self, data: &[T]) -> Result<usize, ()> {
+        let target = self.slice.get_mut(..data.len()).ok_or(())?;
+        target.copy_from_slice(data);
+        Ok(data.len())
+    }
+}
+
+/// A buffer Rust passes to a shim, which fills it and reports how many elements it wrote.
+///
+/// The reported count is kept apart from the contents, so a shim reporting
+/// more than the capacity (a truncated name, a directory that grew) can't
+/// make Rust read past the buffer.
+pub struct ShimBuf<T: Copy = u8> {
+    data: Vec<T>,
+    reported: usize,
+}
+
+impl<T: Copy> ShimBuf<T> {
+    /// Allocates a buffer of `capacity` elements set to `fill`.
+    pub fn new(capacity: usize, fill: T) -> Self {
+        Self { data: alloc::vec![fill; capacity], reported: 0 }
+    }
+
+    /// Reuses an allocation for a buffer of `capacity` elements set to `fill`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ShimBuf, ()>` - The buffer, Err if growing the allocation failed
+    pub fn reuse(mut data: Vec<T>, capacity: usize, fill: T) -> Result<Self, ()> {
+        data.clear();
+        data.try_reserve(capacity).map_err(|_| ())?;
+        data.resize(capacity, fill);
+        Ok(Self { data, reported: 0 })
+    }
+
+    /// Returns the pointer to pass to the shim.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.data.as_mut_ptr()
+    }
+
+    /// Returns the capacity to pass to the shim.
+    pub fn capacity(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns where the shim stores the number of elements it wrote.
+    pub fn reported_mut(&mut self) -> &mut usize {
+        &mut self.reported
+    }
+
+    /// Returns true if the shim reported more elements than fit.
+    pub fn overflowed(&self) -> bool {
+        self.reported > self.data.len()
+    }
+
+    /// Returns the elements the shim wrote, at most the capacity.
+    pub fn into_vec(mut self) -> Vec<T> {
+        self.data.truncate(self.reported);
+        self.data
+    }
+}
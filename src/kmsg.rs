@@ -0,0 +1,80 @@
+//! Kernel log lines related to a device.
+//!
+//! Drivers report probe failures, protocol errors and firmware problems in the
+//! kernel log, which is exactly what a bug report needs next to the verdict.
+//! The log is read line by line through the kmsg dump iterator and the last
+//! errors and warnings that mention the device are kept, so the report stands
+//! on its own. Lines printed by the verifier itself are skipped, since they
+//! mention the device too.
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::matcher;
+use crate::shim;
+use crate::text;
+
+/// Number of lines kept for a report.
+pub const MAX_LINES: usize = 8;
+
+/// Longest log line read; longer lines are truncated.
+const LINE_MAX: usize = 512;
+
+/// Least severe level kept (KERN_WARNING).
+const MAX_LEVEL: u8 = 4;
+
+/// Prefix of the lines the verifier prints.
+const OWN_PREFIX: &[u8] = b"Driver Verifier:";
+
+/// Collects the last error and warning lines that mention any of the keys.
+///
+/// # Arguments
+///
+/// * `keys` - Device names to look for, e.g. the input device name and the
+///   name of the physical device (`i2c-ELAN0504:01`, `serio1`) that `dev_err`
+///   and friends prefix their messages with
+///
+/// # Returns
+///
+/// * `Vec<String>` - Up to `MAX_LINES` lines, oldest first, with the level prefix removed
+pub fn collect(keys: &[&str]) -> Vec<String> {
+    let Some(kernel_kmsg_line) = shim::ops().kmsg_line else {
+        return Vec::new();
+    };
+
+    let mut lines = VecDeque::with_capacity(MAX_LINES);
+    let keys = keys.iter().filter(|key| !key.is_empty());
+    let mut buffer = [0u8; LINE_MAX];
+    let mut seq: u64 = 0;
+    loop {
+        let mut line_len: usize = 0;
+        if unsafe { kernel_kmsg_line(&mut seq, buffer.as_mut_ptr(), buffer.len(), &mut line_len) } <= 0 {
+            break;
+        }
+        let Some((level, line)) = buffer.get(..line_len).and_then(split_level) else {
+            continue;
+        };
+        let line = line.trim_ascii_end();
+        if level > MAX_LEVEL
+            || matcher::contains(line, OWN_PREFIX)
+            || !keys.clone().any(|key| matcher::contains(line, key.as_bytes())) {
+            continue;
+        }
+
+        if lines.len() == MAX_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(text::escape_lossy(line).into_owned());
+    }
+    lines.into()
+}
+
+/// Splits the `<N>` syslog prefix off a line and returns the log level.
+fn split_level(line: &[u8]) -> Option<(u8, &[u8])> {
+    let rest = line.strip_prefix(b"<")?;
+    let end = rest.iter().position(|&byte| byte == b'>')?;
+    let prefix = core::str::from_utf8(rest.get(..end)?).ok()?;
+    // The prefix holds the facility in the upper bits and the level in the lowest three
+    let level = (prefix.parse::<u32>().ok()? & 7) as u8;
+    Some((level, rest.get(end + 1..)?))
+}
@@ -4,12 +4,57 @@
 //! the functionality of input devices, with particular focus on touchpad devices.
 //! It serves as both a diagnostic tool and a reference implementation for
 //! Rust-based Linux kernel drivers.
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 #![feature(allocator_api)]
 
+extern crate alloc;
+
+#[cfg(not(test))]
 use core::panic::PanicInfo;
 
+use kbuf::{KBuf, KStr};
+
+mod boot;
+mod cache;
+mod config;
+mod device_filter;
+mod dir;
+mod evdev_abi;
+mod event_monitor;
+mod expect;
+mod fnkeys;
+mod fwnode;
+mod grab;
+mod handlers;
+mod incidents;
+mod input_core;
 mod input_verifier;
+mod kbuf;
+mod kmsg;
+mod matcher;
+mod metrics;
+mod notify;
+mod pool;
+mod profile;
+mod quirks;
+mod reason;
+mod recorder;
+mod recovery;
+mod report;
+mod safety;
+mod seat;
+mod selftest;
+mod shim;
+mod snapshot;
+mod stable_name;
+mod stats;
+mod text;
+mod trace;
+mod trackpoint;
+mod udev;
+mod verify_all;
+mod virt;
+mod watchdog;
 
 /// Static mutable global instance for our verifier component.
 ///
@@ -17,7 +62,8 @@ mod input_verifier;
 /// our Rust-based verifier from C kernel code.
 static mut VERIFIER: Option<input_verifier::InputDeviceVerifier> = None;
 
-// Panic handler for no_std
+// Panic handler for no_std; unit tests run on the host with std
+#[cfg(not(test))]
 #[panic_handler]
 fn panic(_info: &PanicInfo) -> ! {
     loop {}
@@ -28,17 +74,28 @@ fn panic(_info: &PanicInfo) -> ! {
 ///
 /// This function creates a new InputDeviceVerifier instance and stores it
 /// in the global VERIFIER state for later use by other FFI functions.
+/// The kernel operations table is installed first; features needing an
+/// operation the C side doesn't provide are disabled.
+///
+/// # Arguments
+///
+/// * `ops` - Kernel operations table, see `shim::KernelOps`
 ///
 /// # Safety
 ///
 /// This function is unsafe because it modifies global state and is called
-/// from C code.
+/// from C code. `ops` must be NULL or point to a table of at least the size
+/// it states, and no other FFI function may run concurrently.
 ///
 /// # Returns
 ///
-/// * `i32` - 0 on success, -1 on error
+/// * `i32` - 0 on success, -1 on error or if a required operation is missing
 #[no_mangle]
-pub extern "C" fn rust_init() -> i32 {
+pub extern "C" fn rust_init(ops: *const shim::KernelOps) -> i32 {
+    if unsafe { shim::install(ops) }.is_err() {
+        return -1;
+    }
+
     match input_verifier::InputDeviceVerifier::new() {
         Ok(verifier) => {
             unsafe {
@@ -95,27 +152,1117 @@ pub extern "C" fn rust_scan_devices() -> i32 {
 
 /// Verifies touchpad functionality using the Rust verifier.
 ///
-/// This function calls the verify_touchpad method on the global VERIFIER
-/// instance if it exists.
+/// This function calls the verify_touchpad_cached method on the global VERIFIER
+/// instance if it exists. Every touchpad is verified; the per-touchpad
+/// results are available through `rust_touchpad_status`. Unless forced, the
+/// result of the last verification is returned while it is fresh, so frequent
+/// polling doesn't rerun the whole sequence, and a stale result is verified
+/// again under at most the diagnose safety policy, so polling never repairs.
 ///
 /// # Safety
 ///
 /// This function is unsafe because it accesses global state and is called
 /// from C code.
 ///
+/// # Arguments
+///
+/// * `force` - Non-zero to ignore a cached result and verify again
+///
 /// # Returns
 ///
-/// * `i32` - 1 if touchpad is working, 0 if not working, -1 on error or if VERIFIER is None
+/// * `i32` - 1 if all touchpads are working, 0 if one is not working, -2 if every touchpad
+///   is gone since the last scan, -3 if no touchpad is expected in a virtual machine,
+///   -4 if /sys/class/input is unavailable, -1 on error or if VERIFIER is None
 #[no_mangle]
-pub extern "C" fn rust_verify_touchpad() -> i32 {
+pub extern "C" fn rust_verify_touchpad(force: i32) -> i32 {
     unsafe {
         if let Some(ref mut verifier) = VERIFIER {
-            match verifier.verify_touchpad() {
+            match verifier.verify_touchpad_cached(force != 0) {
                 Ok(working) => if working { 1 } else { 0 },
-                Err(_) => -1, 
+                Err(error) => error.status(),
             }
         } else {
             -1 
         }
     }
 }
+
+/// Starts the concurrent verification of every scanned device.
+///
+/// The caller must hold the verifier lock until `rust_verify_all_end`
+/// returned, and must not call other FFI functions in between except
+/// `rust_verify_device`.
+///
+/// # Returns
+///
+/// * `i32` - Number of devices to verify, or -1 if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_verify_all_begin() -> i32 {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.verify_all_begin() as i32
+        } else {
+            -1
+        }
+    }
+}
+
+/// Verifies one device between `rust_verify_all_begin` and `rust_verify_all_end`.
+///
+/// # Safety
+///
+/// Called from work items running in parallel without the verifier lock.
+/// This is sound because the caller of `rust_verify_all_begin` holds the lock
+/// until all work items finished, so the verifier is only borrowed shared,
+/// and each device writes its verdict under its own slot lock.
+///
+/// # Arguments
+///
+/// * `index` - Index of the device, below the count returned by `rust_verify_all_begin`
+///
+/// # Returns
+///
+/// * `i32` - 1 if the device is working, 0 if it is not responsive, 2 if another client
+///   grabbed it, -2 if it is gone, -1 on error or if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_verify_device(index: u32) -> i32 {
+    unsafe {
+        if let Some(ref verifier) = VERIFIER {
+            match verifier.verify_device(index as usize) {
+                Ok(verify_all::DeviceVerdict::Working) => 1,
+                Ok(verify_all::DeviceVerdict::NotResponsive) => 0,
+                Ok(verify_all::DeviceVerdict::Grabbed) => 2,
+                Ok(verify_all::DeviceVerdict::Gone) => -2,
+                Ok(verify_all::DeviceVerdict::Failed) | Err(_) => -1,
+            }
+        } else {
+            -1
+        }
+    }
+}
+
+/// Collects the results of the concurrent verification of every device.
+///
+/// # Returns
+///
+/// * `i32` - Number of devices not working, or -1 if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_verify_all_end() -> i32 {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.verify_all_end() as i32
+        } else {
+            -1
+        }
+    }
+}
+
+/// Returns the number of touchpads found by the last scan.
+///
+/// # Returns
+///
+/// * `i32` - Number of touchpads, or -1 if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_touchpad_count() -> i32 {
+    unsafe {
+        if let Some(ref verifier) = VERIFIER {
+            verifier.touchpad_count() as i32
+        } else {
+            -1
+        }
+    }
+}
+
+/// Returns the verification result of one touchpad.
+///
+/// # Arguments
+///
+/// * `index` - Index of the touchpad in scan order
+///
+/// # Returns
+///
+/// * `i32` - 1 if the touchpad is working, 0 if not working, -2 if it was gone at its last
+///   verification, -1 if it was not verified, its verification could not run, the index
+///   is out of range or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_touchpad_status(index: u32) -> i32 {
+    unsafe {
+        if let Some(ref verifier) = VERIFIER {
+            match verifier.touchpad(index as usize).and_then(|touchpad| touchpad.result()) {
+                Some(Ok(working)) => if working { 1 } else { 0 },
+                Some(Err(error)) => error.status(),
+                None => -1,
+            }
+        } else {
+            -1
+        }
+    }
+}
+
+/// Copies one line per touchpad (path, status, name) into a caller buffer.
+///
+/// The output is truncated to `buf_len` bytes and is not NUL terminated.
+///
+/// # Safety
+///
+/// `buf` must point to at least `buf_len` writable bytes.
+///
+/// # Returns
+///
+/// * `isize` - Number of bytes written, or -1 if the buffer is invalid or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_touchpads_read(buf: *mut u8, buf_len: usize) -> isize {
+    unsafe {
+        if let Some(ref verifier) = VERIFIER {
+            let status = verifier.touchpads_status();
+            let Ok(mut out) = KBuf::from_raw(buf, buf_len) else {
+                return -1;
+            };
+            out.fill(status.as_bytes()) as isize
+        } else {
+            -1
+        }
+    }
+}
+
+/// Copies the latest verification report of a touchpad into a caller buffer.
+///
+/// The report is serialized as described in `report`, without a NUL terminator.
+/// Nothing is copied if it doesn't fit: the required size is returned instead,
+/// so callers compare the result with `buf_len` and retry with a larger buffer.
+/// A NULL `buf` with a zero `buf_len` only queries the size.
+///
+/// # Arguments
+///
+/// * `device_index` - Index of the touchpad in scan order
+/// * `buf` - Buffer receiving the report
+/// * `buf_len` - Size of `buf`
+///
+/// # Safety
+///
+/// Unless `buf_len` is zero, `buf` must point to at least `buf_len` writable bytes.
+///
+/// # Returns
+///
+/// * `isize` - Size of the report, or -1 if the touchpad was not verified, the index is
+///   out of range, the buffer is invalid or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_get_report(device_index: u32, buf: *mut u8, buf_len: usize) -> isize {
+    unsafe {
+        if let Some(ref verifier) = VERIFIER {
+            let Some(report) = verifier.touchpad(device_index as usize).and_then(|touchpad| touchpad.report.as_ref()) else {
+                return -1;
+            };
+            let Ok(mut out) = KBuf::from_raw(buf, buf_len) else {
+                return -1;
+            };
+            let blob = report.serialize();
+            let _ = out.write_all(blob.as_bytes());
+            blob.len() as isize
+        } else {
+            -1
+        }
+    }
+}
+
+/// Configures the input event check used by touchpad verification.
+///
+/// # Arguments
+///
+/// * `window_ms` - How long to wait for events in interactive mode, in milliseconds
+/// * `interactive` - Non-zero to prompt the user and wait for events, zero to only
+///   validate device capabilities
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_configure_event_check(window_ms: u32, interactive: i32) -> i32 {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.configure_event_check(window_ms, interactive != 0);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+/// Receives an input event from the C input handler.
+///
+/// Called for every event of every input device the handler is connected to,
+/// possibly from interrupt context. The event is only recorded by the event
+/// monitor and never touches the VERIFIER state.
+///
+/// # Arguments
+///
+/// * `slot` - Monitor slot the originating device is bound to
+/// * `ev_type` - Event type
+/// * `code` - Event code
+/// * `value` - Event value
+#[no_mangle]
+pub extern "C" fn rust_input_event(slot: u32, ev_type: u32, code: u32, value: i32) {
+    if slot == event_monitor::RECORDER_SLOT {
+        recorder::RECORDER.record(ev_type, code, value);
+    }
+    event_monitor::EVENT_MONITOR.record(slot, ev_type, code, value);
+}
+
+/// Applies a command to the interactive guided self test.
+///
+/// # Arguments
+///
+/// * `command` - 1 to start, 2 to skip the current step, 3 to abort
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 on an unknown command, failure or if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_selftest_command(command: u32) -> i32 {
+    let Some(command) = selftest::SelftestCommand::from_raw(command) else {
+        return -1;
+    };
+
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            match verifier.selftest_command(command) {
+                Ok(_) => 0,
+                Err(_) => -1,
+            }
+        } else {
+            -1
+        }
+    }
+}
+
+/// Advances the self test and copies its status text into a caller buffer.
+///
+/// The output is truncated to `buf_len` bytes and is not NUL terminated.
+///
+/// # Safety
+///
+/// `buf` must point to at least `buf_len` writable bytes.
+///
+/// # Returns
+///
+/// * `isize` - Number of bytes written, or -1 if the buffer is invalid or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_selftest_read(buf: *mut u8, buf_len: usize) -> isize {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            let status = verifier.selftest_status();
+            let Ok(mut out) = KBuf::from_raw(buf, buf_len) else {
+                return -1;
+            };
+            out.fill(status.as_bytes()) as isize
+        } else {
+            -1
+        }
+    }
+}
+
+/// Receives input device hotplug notifications from the C input handler.
+///
+/// Forwards the change to userspace over netlink and invalidates the cached
+/// verification result. Does not touch the VERIFIER
+/// state, so it is safe to call while the input handler is being registered.
+///
+/// # Arguments
+///
+/// * `name` - Device name (not NUL terminated)
+/// * `name_len` - Length of `name`
+/// * `added` - Non-zero if the device was connected, zero if it was disconnected
+#[no_mangle]
+pub extern "C" fn rust_input_device_changed(name: *const u8, name_len: usize, added: i32) {
+    let Ok(name) = (unsafe { KStr::from_raw(name, name_len) }) else {
+        return;
+    };
+    let name = name.escaped();
+    let kind = if added != 0 {
+        notify::Notification::DeviceAdded
+    } else {
+        notify::Notification::DeviceRemoved
+    };
+    cache::device_changed();
+    notify::notify(kind, &name, 0);
+}
+
+/// Reads a statistics counter.
+///
+/// Counters are atomics and may be read without holding the verifier lock.
+///
+/// # Arguments
+///
+/// * `counter` - Counter id as defined by `stats::Counter`
+///
+/// # Returns
+///
+/// * `u64` - Counter value, or 0 for an unknown counter id
+#[no_mangle]
+pub extern "C" fn rust_stats_read(counter: u32) -> u64 {
+    match stats::Counter::from_raw(counter) {
+        Some(counter) => stats::STATS.get(counter),
+        None => 0,
+    }
+}
+
+/// Resets all statistics counters to zero.
+#[no_mangle]
+pub extern "C" fn rust_stats_reset() {
+    stats::STATS.reset();
+}
+
+/// Configures the touchpad freeze watchdog.
+///
+/// # Arguments
+///
+/// * `enabled` - Non-zero to enable the watchdog
+/// * `freeze_ms` - Touchpad silence period after which a freeze is suspected
+/// * `reverify` - Non-zero to re-verify the touchpad on a suspected freeze
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_configure_watchdog(enabled: i32, freeze_ms: u32, reverify: i32) -> i32 {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.configure_watchdog(enabled != 0, freeze_ms, reverify != 0);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+/// Runs one watchdog check. Called periodically from a C work item.
+///
+/// # Returns
+///
+/// * `i32` - 1 if a freeze was suspected, 0 if not, -1 on error or if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_watchdog_tick() -> i32 {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            match verifier.watchdog_tick() {
+                Ok(suspected) => if suspected { 1 } else { 0 },
+                Err(_) => -1,
+            }
+        } else {
+            -1
+        }
+    }
+}
+
+/// Enables the deferred boot-time verification.
+///
+/// # Arguments
+///
+/// * `retry_ms` - How long a missing touchpad is retried before the final verdict
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_configure_boot_verify(retry_ms: u32) -> i32 {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.configure_boot_verify(retry_ms);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+/// Defers the scan to the first `rust_verify_touchpad` call.
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_configure_lazy_scan() -> i32 {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.configure_lazy_scan();
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+/// Runs one attempt of the boot-time verification. Called from a C work item.
+///
+/// # Returns
+///
+/// * `i32` - Delay in milliseconds before the next attempt, 0 once the final verdict
+///   was issued, -1 if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_boot_verify() -> i32 {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.boot_verify_attempt().map_or(0, |delay| delay.min(i32::MAX as u32) as i32)
+        } else {
+            -1
+        }
+    }
+}
+
+/// Selects the verification profile.
+///
+/// # Arguments
+///
+/// * `profile` - Profile id (0 quick, 1 standard, 2 deep, 3 interactive)
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if the profile is unknown or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_configure_profile(profile: u32) -> i32 {
+    let Some(profile) = profile::Profile::from_raw(profile) else {
+        return -1;
+    };
+
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.configure_profile(profile);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+/// Selects the backend the scan enumerates the devices with.
+///
+/// # Arguments
+///
+/// * `enumeration` - Backend id (0 sysfs, 1 input core)
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if the backend is unknown or unsupported by the shim, or
+///   VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_configure_enumeration(enumeration: u32) -> i32 {
+    let Some(enumeration) = input_core::Enumeration::from_raw(enumeration) else {
+        return -1;
+    };
+
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            match verifier.configure_enumeration(enumeration) {
+                Ok(_) => 0,
+                Err(_) => -1,
+            }
+        } else {
+            -1
+        }
+    }
+}
+
+/// Sets the safety policy limiting the actions that change device state.
+///
+/// Only called at load time and from the privileged `DV_IOC_SET_SAFETY` ioctl.
+///
+/// # Arguments
+///
+/// * `policy` - Policy id (0 read-only, 1 diagnose, 2 repair)
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if the policy is unknown or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_configure_safety(policy: u32) -> i32 {
+    let Some(policy) = safety::SafetyPolicy::from_raw(policy) else {
+        return -1;
+    };
+
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.configure_safety(policy);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+/// Resizes the string pool used by scans.
+///
+/// # Arguments
+///
+/// * `bytes` - Capacity for string data in bytes
+/// * `entries` - Capacity in number of strings
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if the allocation fails or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_configure_pool(bytes: u32, entries: u32) -> i32 {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            match verifier.configure_pool(bytes as usize, entries as usize) {
+                Ok(_) => 0,
+                Err(_) => -1,
+            }
+        } else {
+            -1
+        }
+    }
+}
+
+/// Sets how long a verification result is reused by `rust_verify_touchpad`.
+///
+/// # Arguments
+///
+/// * `ttl_ms` - Time to live of a cached result in milliseconds, 0 to always verify
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_configure_cache(ttl_ms: u32) -> i32 {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.configure_cache(ttl_ms);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+/// Enables or disables automatic recovery of unresponsive touchpads.
+///
+/// # Arguments
+///
+/// * `enabled` - Non-zero to rebind the driver of a bound but unresponsive touchpad
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_configure_recovery(enabled: i32) -> i32 {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.configure_recovery(enabled != 0);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+/// Enables or disables the reset GPIO pulse for unresponsive touchpads.
+///
+/// # Arguments
+///
+/// * `enabled` - Non-zero to reset a bound but unresponsive touchpad through its reset GPIO
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_configure_reset_gpio(enabled: i32) -> i32 {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.configure_reset_gpio(enabled != 0);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+/// Enables or disables loading of missing touchpad modules.
+///
+/// # Arguments
+///
+/// * `enabled` - Non-zero to request missing modules and verify again
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_configure_module_loading(enabled: i32) -> i32 {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.configure_module_loading(enabled != 0);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+/// Enables or disables the exclusive grab during the event check.
+///
+/// # Arguments
+///
+/// * `enabled` - Non-zero to grab the touchpad while waiting for events
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_configure_exclusive_grab(enabled: i32) -> i32 {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.configure_exclusive_grab(enabled != 0);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+/// Configures which devices the scan considers.
+///
+/// # Arguments
+///
+/// * `ignore` - Comma separated name or `vendor:product` patterns of devices to skip
+/// * `ignore_len` - Length of `ignore`
+/// * `only` - Comma separated patterns of the only devices to consider, empty for all
+/// * `only_len` - Length of `only`
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if a list is not valid UTF-8 or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_configure_device_filter(
+    ignore: *const u8,
+    ignore_len: usize,
+    only: *const u8,
+    only_len: usize
+) -> i32 {
+    let ignore = unsafe { KStr::from_raw(ignore, ignore_len) }.and_then(|ignore| ignore.to_str());
+    let only = unsafe { KStr::from_raw(only, only_len) }.and_then(|only| only.to_str());
+    let (Ok(ignore), Ok(only)) = (ignore, only) else {
+        return -1;
+    };
+
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.configure_device_filter(ignore, only);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+/// Limits the scan to the devices of one seat.
+///
+/// # Arguments
+///
+/// * `seat` - Seat name such as "seat1", empty for every seat
+/// * `seat_len` - Length of `seat`
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if the name is not a valid seat name or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_configure_seat(seat: *const u8, seat_len: usize) -> i32 {
+    let Ok(seat) = unsafe { KStr::from_raw(seat, seat_len) }.and_then(|seat| seat.to_str()) else {
+        return -1;
+    };
+
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            match verifier.configure_seat(seat) {
+                Ok(_) => 0,
+                Err(_) => -1,
+            }
+        } else {
+            -1
+        }
+    }
+}
+
+/// Copies the table of the reason codes into a caller buffer.
+///
+/// Each line holds a code and its English description, separated by a tab.
+/// The table is static, so no verifier is needed. The output is truncated to
+/// `buf_len` bytes and is not NUL terminated.
+///
+/// # Safety
+///
+/// `buf` must point to at least `buf_len` writable bytes.
+///
+/// # Returns
+///
+/// * `isize` - Number of bytes written, or -1 if the buffer is invalid
+#[no_mangle]
+pub extern "C" fn rust_reason_codes_read(buf: *mut u8, buf_len: usize) -> isize {
+    let table = reason::table();
+    let Ok(mut out) = (unsafe { KBuf::from_raw(buf, buf_len) }) else {
+        return -1;
+    };
+    out.fill(table.as_bytes()) as isize
+}
+
+/// Copies the seat the scan is limited to into a caller buffer.
+///
+/// The output is truncated to `buf_len` bytes and is not NUL terminated.
+///
+/// # Safety
+///
+/// `buf` must point to at least `buf_len` writable bytes.
+///
+/// # Returns
+///
+/// * `isize` - Number of bytes written, or -1 if the buffer is invalid or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_seat_read(buf: *mut u8, buf_len: usize) -> isize {
+    unsafe {
+        if let Some(ref verifier) = VERIFIER {
+            let status = verifier.seat_status();
+            let Ok(mut out) = KBuf::from_raw(buf, buf_len) else {
+                return -1;
+            };
+            out.fill(status.as_bytes()) as isize
+        } else {
+            -1
+        }
+    }
+}
+
+/// Applies a configuration update at runtime.
+///
+/// Must be called under the verifier lock. The update is applied entirely or
+/// not at all; the monitoring interval is only validated here and applied by
+/// the caller, which schedules the watchdog.
+///
+/// # Arguments
+///
+/// * `config` - Configuration update, see `config::DvConfig`
+///
+/// # Safety
+///
+/// `config` must be NULL or point to a valid `struct dv_config`.
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if the update is invalid, `config` is NULL or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_set_config(config: *const config::DvConfig) -> i32 {
+    let Some(config) = (unsafe { config.as_ref() }) else {
+        return -1;
+    };
+
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            match verifier.apply_config(config) {
+                Ok(()) => 0,
+                Err(_) => -1,
+            }
+        } else {
+            -1
+        }
+    }
+}
+
+/// Serializes a snapshot of the device registry and verification results.
+///
+/// Unlike the self test status, the snapshot is never truncated since a partial
+/// snapshot could not be compared later.
+///
+/// # Safety
+///
+/// `buf` must point to at least `buf_len` writable bytes.
+///
+/// # Returns
+///
+/// * `isize` - Number of bytes written, or -1 if the buffer is too small or invalid or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_snapshot(buf: *mut u8, buf_len: usize) -> isize {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            let Ok(mut out) = KBuf::from_raw(buf, buf_len) else {
+                return -1;
+            };
+            match out.write_all(verifier.snapshot().as_bytes()) {
+                Ok(len) => len as isize,
+                Err(_) => -1,
+            }
+        } else {
+            -1
+        }
+    }
+}
+
+/// Compares a previously taken snapshot with the current state.
+///
+/// # Arguments
+///
+/// * `blob` - Serialized snapshot as produced by `rust_snapshot`
+/// * `blob_len` - Length of `blob`
+///
+/// # Returns
+///
+/// * `i32` - Number of differences found, or -1 if the snapshot is invalid or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_compare_snapshot(blob: *const u8, blob_len: usize) -> i32 {
+    let Ok(blob) = unsafe { KStr::from_raw(blob, blob_len) }.and_then(|blob| blob.to_str()) else {
+        return -1;
+    };
+
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            match verifier.compare_snapshot(blob) {
+                Ok(count) => count as i32,
+                Err(_) => -1,
+            }
+        } else {
+            -1
+        }
+    }
+}
+
+/// Copies the incident log into a caller buffer.
+///
+/// The output is truncated to `buf_len` bytes and is not NUL terminated.
+///
+/// # Safety
+///
+/// `buf` must point to at least `buf_len` writable bytes.
+///
+/// # Returns
+///
+/// * `isize` - Number of bytes written, or -1 if the buffer is invalid or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_incidents_read(buf: *mut u8, buf_len: usize) -> isize {
+    unsafe {
+        if let Some(ref verifier) = VERIFIER {
+            let status = verifier.incidents_status();
+            let Ok(mut out) = KBuf::from_raw(buf, buf_len) else {
+                return -1;
+            };
+            out.fill(status.as_bytes()) as isize
+        } else {
+            -1
+        }
+    }
+}
+
+/// Clears the incident log.
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_incidents_clear() -> i32 {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            verifier.clear_incidents();
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+/// Copies the metrics in the Prometheus text format into a caller buffer.
+///
+/// The output is truncated to `buf_len` bytes and is not NUL terminated.
+///
+/// # Safety
+///
+/// `buf` must point to at least `buf_len` writable bytes.
+///
+/// # Returns
+///
+/// * `isize` - Number of bytes written, or -1 if the buffer is invalid or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_metrics_read(buf: *mut u8, buf_len: usize) -> isize {
+    unsafe {
+        if let Some(ref verifier) = VERIFIER {
+            let metrics = verifier.metrics();
+            let Ok(mut out) = KBuf::from_raw(buf, buf_len) else {
+                return -1;
+            };
+            out.fill(metrics.as_bytes()) as isize
+        } else {
+            -1
+        }
+    }
+}
+
+/// Replaces the declared expectations and device annotations.
+///
+/// # Arguments
+///
+/// * `text` - Declarations, one per line, in the syntax described in `expect`
+/// * `text_len` - Length of `text`
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, the 1-based number of the first invalid line if a declaration
+///   is invalid, -1 if the text is not valid UTF-8 or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_set_expectations(text: *const u8, text_len: usize) -> i32 {
+    let Ok(text) = unsafe { KStr::from_raw(text, text_len) }.and_then(|text| text.to_str()) else {
+        return -1;
+    };
+
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            match verifier.declare_expectations(text) {
+                Ok(_) => 0,
+                Err(line) => line.min(i32::MAX as usize) as i32,
+            }
+        } else {
+            -1
+        }
+    }
+}
+
+/// Copies the declared expectations into a caller buffer.
+///
+/// The output is truncated to `buf_len` bytes and is not NUL terminated.
+///
+/// # Safety
+///
+/// `buf` must point to at least `buf_len` writable bytes.
+///
+/// # Returns
+///
+/// * `isize` - Number of bytes written, or -1 if the buffer is invalid or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_expectations_read(buf: *mut u8, buf_len: usize) -> isize {
+    unsafe {
+        if let Some(ref verifier) = VERIFIER {
+            let status = verifier.expectations_status();
+            let Ok(mut out) = KBuf::from_raw(buf, buf_len) else {
+                return -1;
+            };
+            out.fill(status.as_bytes()) as isize
+        } else {
+            -1
+        }
+    }
+}
+
+/// Copies the deviations found by the last verification into a caller buffer.
+///
+/// The output is truncated to `buf_len` bytes and is not NUL terminated.
+///
+/// # Safety
+///
+/// `buf` must point to at least `buf_len` writable bytes.
+///
+/// # Returns
+///
+/// * `isize` - Number of bytes written, or -1 if the buffer is invalid or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_deviations_read(buf: *mut u8, buf_len: usize) -> isize {
+    unsafe {
+        if let Some(ref verifier) = VERIFIER {
+            let status = verifier.deviations_status();
+            let Ok(mut out) = KBuf::from_raw(buf, buf_len) else {
+                return -1;
+            };
+            out.fill(status.as_bytes()) as isize
+        } else {
+            -1
+        }
+    }
+}
+
+/// Copies the result of the last snapshot comparison into a caller buffer.
+///
+/// The output is truncated to `buf_len` bytes and is not NUL terminated.
+///
+/// # Safety
+///
+/// `buf` must point to at least `buf_len` writable bytes.
+///
+/// # Returns
+///
+/// * `isize` - Number of bytes written, or -1 if the buffer is invalid or VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_comparison_read(buf: *mut u8, buf_len: usize) -> isize {
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            let status = verifier.comparison_status();
+            let Ok(mut out) = KBuf::from_raw(buf, buf_len) else {
+                return -1;
+            };
+            out.fill(status.as_bytes()) as isize
+        } else {
+            -1
+        }
+    }
+}
+
+/// Applies a command to the event recorder.
+///
+/// # Arguments
+///
+/// * `command` - 1 to start recording the touchpad, 2 to stop, 3 to replay the buffer
+///
+/// # Returns
+///
+/// * `i32` - Number of events recorded or replayed, or -1 on an unknown command, failure
+///   or if VERIFIER is None
+#[no_mangle]
+pub extern "C" fn rust_recorder_command(command: u32) -> i32 {
+    let Some(command) = recorder::RecorderCommand::from_raw(command) else {
+        return -1;
+    };
+
+    unsafe {
+        if let Some(ref mut verifier) = VERIFIER {
+            match verifier.recorder_command(command) {
+                Ok(count) => count as i32,
+                Err(_) => -1,
+            }
+        } else {
+            -1
+        }
+    }
+}
+
+/// Copies the recorded events, oldest first, into a caller buffer.
+///
+/// # Safety
+///
+/// `events` must point to at least `capacity` writable records. Must be called
+/// under the verifier lock, like the recorder commands.
+///
+/// # Returns
+///
+/// * `isize` - Number of events copied, or -1 while recording or if the buffer is invalid
+#[no_mangle]
+pub extern "C" fn rust_recorder_read(events: *mut recorder::RecordedEvent, capacity: usize) -> isize {
+    let Ok(mut out) = (unsafe { KBuf::from_raw(events, capacity) }) else {
+        return -1;
+    };
+    match recorder::RECORDER.read(out.as_mut_slice()) {
+        Ok(count) => count as isize,
+        Err(_) => -1,
+    }
+}
+
+/// Replaces the recorder buffer with a stored trace for replay.
+///
+/// # Safety
+///
+/// `events` must point to `count` readable records. Must be called under the
+/// verifier lock, so the load can't race a start or a replay.
+///
+/// # Returns
+///
+/// * `i32` - 0 on success, -1 while recording or if the trace is too long or invalid
+#[no_mangle]
+pub extern "C" fn rust_recorder_load(events: *const recorder::RecordedEvent, count: usize) -> i32 {
+    let Ok(events) = (unsafe { kbuf::ffi_slice(events, count) }) else {
+        return -1;
+    };
+    match recorder::RECORDER.load(events) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
@@ -0,0 +1,216 @@
+//! Allocation-free name matching.
+//!
+//! Device names are matched on every scan, for every device, so matching must
+//! not allocate. Case folding is ASCII only: input device names come from
+//! firmware and drivers and the patterns of interest are ASCII, so Unicode
+//! case rules would only add cost. Names are matched as raw bytes, so devices
+//! whose name isn't valid UTF-8 are still classified.
+
+/// How a name rule compares its text with a device name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    /// The name contains the text, ignoring ASCII case.
+    Contains,
+    /// The name contains the text exactly, for vendor tags and ids whose case matters.
+    ContainsExact,
+    /// The whole name matches the text as a glob pattern, ignoring ASCII case.
+    Glob,
+}
+
+/// A rule matching device names.
+pub struct NameRule {
+    pub text: &'static str,
+    pub kind: MatchKind,
+}
+
+impl NameRule {
+    pub const fn new(text: &'static str, kind: MatchKind) -> Self {
+        Self { text, kind }
+    }
+
+    /// Returns true if the rule matches the device name.
+    pub fn matches(&self, name: &[u8]) -> bool {
+        match self.kind {
+            MatchKind::Contains => contains_ignore_ascii_case(name, self.text.as_bytes()),
+            MatchKind::ContainsExact => contains(name, self.text.as_bytes()),
+            MatchKind::Glob => glob_match(self.text.as_bytes(), name),
+        }
+    }
+}
+
+/// Rules recognizing touchpads by their device name.
+pub const TOUCHPAD_NAME_RULES: &[NameRule] = &[
+    NameRule::new("touchpad", MatchKind::Contains),
+    NameRule::new("trackpad", MatchKind::Contains),
+    NameRule::new("glidepoint", MatchKind::Contains),
+    NameRule::new("clickpad", MatchKind::Contains),
+    // Specific vendors
+    NameRule::new("ETPS", MatchKind::ContainsExact),      // Elantech Touchpad
+    NameRule::new("ELAN", MatchKind::ContainsExact),      // ELAN Touchpad (common in Acer laptops)
+    NameRule::new("04F3", MatchKind::ContainsExact),      // ELAN Vendor ID
+    NameRule::new("Synaptics", MatchKind::ContainsExact), // Synaptics Touchpad
+    NameRule::new("ALPS", MatchKind::ContainsExact),      // ALPS Touchpad
+    // Specific to Acer Nitro 5
+    NameRule::new("MSFT0001", MatchKind::ContainsExact),  // Microsoft Precision Touchpad
+    NameRule::new("1A58:0271", MatchKind::ContainsExact), // Another common Acer Nitro 5 touchpad ID
+];
+
+/// Rules recognizing pointing sticks by their device name, for drivers that
+/// don't set INPUT_PROP_POINTING_STICK. Checked before the touchpad rules, which
+/// match the vendor tag of e.g. "AlpsPS/2 ALPS DualPoint Stick".
+pub const POINTING_STICK_NAME_RULES: &[NameRule] = &[
+    NameRule::new("trackpoint", MatchKind::Contains),  // "TPPS/2 IBM TrackPoint", "TPPS/2 Elan TrackPoint"
+    NameRule::new("dualpoint stick", MatchKind::Contains),
+    NameRule::new("pointing stick", MatchKind::Contains),
+];
+
+/// Rules recognizing keyboards by their device name, after the capability and
+/// touchpad checks, e.g. "AT Translated Set 2 keyboard" or "USB KEYBOARD".
+pub const KEYBOARD_NAME_RULES: &[NameRule] = &[
+    NameRule::new("keyboard", MatchKind::Contains),
+];
+
+/// Rules recognizing mice by their device name, checked after the keyboard rules.
+pub const MOUSE_NAME_RULES: &[NameRule] = &[
+    NameRule::new("mouse", MatchKind::Contains),
+];
+
+/// Returns true if any rule matches the device name.
+///
+/// # Arguments
+///
+/// * `rules` - Rules to try
+/// * `name` - Raw device name
+pub fn matches_any(rules: &[NameRule], name: &[u8]) -> bool {
+    rules.iter().any(|rule| rule.matches(name))
+}
+
+/// Checks if `haystack` contains `needle`.
+pub fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    needle.is_empty() || haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+/// Checks if `haystack` contains `needle`, ignoring ASCII case.
+pub fn contains_ignore_ascii_case(haystack: &[u8], needle: &[u8]) -> bool {
+    needle.is_empty() || haystack.windows(needle.len()).any(|window| window.eq_ignore_ascii_case(needle))
+}
+
+/// Checks if a pattern contains glob wildcards.
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// Matches a whole name against a glob pattern, ignoring ASCII case.
+///
+/// `*` matches any run of bytes, including none, and `?` matches a single byte.
+/// Runs in linear space and without recursion, backtracking to the last `*` on
+/// a mismatch.
+///
+/// # Arguments
+///
+/// * `pattern` - Glob pattern, e.g. `*Touchpad*` or `ELAN0???:00*`
+/// * `name` - Raw name to match
+pub fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and of the name byte it is currently extended to
+    let mut star: Option<(usize, usize)> = None;
+
+    while let Some(&byte) = name.get(n) {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, n));
+                p += 1;
+            },
+            Some(&wanted) if wanted == b'?' || wanted.eq_ignore_ascii_case(&byte) => {
+                p += 1;
+                n += 1;
+            },
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                },
+                None => return false,
+            },
+        }
+    }
+
+    pattern.get(p..).is_some_and(|rest| rest.iter().all(|&byte| byte == b'*'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matches_literals_ignoring_ascii_case() {
+        assert!(glob_match(b"ELAN0501:00", b"elan0501:00"));
+        assert!(!glob_match(b"ELAN0501:00", b"ELAN0501:0"));
+        assert!(!glob_match(b"ELAN0501:0", b"ELAN0501:00"));
+    }
+
+    #[test]
+    fn glob_question_mark_matches_exactly_one_byte() {
+        assert!(glob_match(b"ELAN0???:00", b"ELAN0501:00"));
+        assert!(!glob_match(b"ELAN0???:00", b"ELAN051:00"));
+        assert!(!glob_match(b"ELAN0???:00", b"ELAN05011:00"));
+        assert!(!glob_match(b"?", b""));
+    }
+
+    #[test]
+    fn glob_star_matches_any_run_including_none() {
+        assert!(glob_match(b"*Touchpad*", b"ELAN0501:00 04F3:3140 Touchpad"));
+        assert!(glob_match(b"*Touchpad*", b"Touchpad"));
+        assert!(glob_match(b"ELAN*", b"ELAN"));
+        assert!(!glob_match(b"*Touchpad", b"Touchpad Mouse"));
+    }
+
+    #[test]
+    fn glob_backtracks_to_the_last_star() {
+        assert!(glob_match(b"*pad*pad", b"touchpad clickpad"));
+        assert!(glob_match(b"*a*b", b"aaab"));
+        assert!(!glob_match(b"*a*b", b"aaba"));
+        assert!(glob_match(b"*ab?d", b"abxabcd"));
+        assert!(glob_match(b"a*?*c", b"abc"));
+        assert!(!glob_match(b"a*?*c", b"ac"));
+    }
+
+    #[test]
+    fn glob_boundaries() {
+        assert!(glob_match(b"", b""));
+        assert!(!glob_match(b"", b"a"));
+        assert!(glob_match(b"*", b""));
+        assert!(glob_match(b"**", b""));
+        assert!(glob_match(b"***", b"abc"));
+        assert!(!glob_match(b"*?", b""));
+        assert!(glob_match(b"*?", b"x"));
+        assert!(!glob_match(b"abc*", b"ab"));
+    }
+
+    #[test]
+    fn glob_malformed_input_is_matched_literally() {
+        // No bracket expressions or escapes, and names need not be UTF-8
+        assert!(glob_match(b"[abc]*", b"[abc] device"));
+        assert!(!glob_match(b"[abc]*", b"a device"));
+        assert!(glob_match(b"\\*", b"\\anything"));
+        assert!(glob_match(b"?\xfe*", b"\xff\xfe\x01"));
+        assert!(!glob_match(b"\xff", b"\xdf"));
+    }
+
+    #[test]
+    fn name_rules_match_raw_bytes() {
+        assert!(matches_any(TOUCHPAD_NAME_RULES, b"SYNA\xff TouchPad"));
+        assert!(!matches_any(TOUCHPAD_NAME_RULES, b"elan keyboard"));
+        assert!(matches_any(POINTING_STICK_NAME_RULES, b"TPPS/2 IBM TrackPoint"));
+    }
+
+    #[test]
+    fn keyboard_and_mouse_rules_ignore_ascii_case() {
+        assert!(matches_any(KEYBOARD_NAME_RULES, b"AT Translated Set 2 keyboard"));
+        assert!(matches_any(KEYBOARD_NAME_RULES, b"USB KEYBOARD"));
+        assert!(matches_any(MOUSE_NAME_RULES, b"USB MOUSE"));
+        assert!(matches_any(MOUSE_NAME_RULES, b"Logitech Wireless Mouse"));
+        assert!(!matches_any(MOUSE_NAME_RULES, b"Logitech Wireless Keyboard"));
+    }
+}
@@ -0,0 +1,93 @@
+//! Metrics in the Prometheus text exposition format.
+//!
+//! Monitoring stacks scrape text files, e.g. through the textfile collector of
+//! node-exporter, so the statistics counters and the health of the touchpads
+//! are rendered in that format for the `metrics` debugfs file instead of
+//! making every consumer parse sysfs. Counters are exported with a `_total`
+//! suffix, high-water marks as gauges.
+//!
+//! The health score of a touchpad is 100 if its last verification passed, 50
+//! if it passed but the touchpad runs in a degraded multitouch mode, and 0 if
+//! it failed. The machine score is the lowest touchpad score and is left out
+//! until a touchpad was verified.
+use alloc::string::String;
+use core::fmt::Write;
+
+use crate::input_verifier::Touchpad;
+use crate::stats::{Counter, STATS};
+
+/// Prefix of every metric name.
+const PREFIX: &str = "driver_verifier";
+
+/// Returns the health score of a touchpad, None if it was not verified yet.
+pub fn health_score(touchpad: &Touchpad) -> Option<u32> {
+    let report = touchpad.report.as_ref()?;
+    Some(match (report.working, report.multitouch.degraded()) {
+        (true, false) => 100,
+        (true, true) => 50,
+        (false, _) => 0,
+    })
+}
+
+/// Writes a label value, escaping backslashes, quotes and newlines.
+fn write_label(out: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Renders the metrics.
+///
+/// # Arguments
+///
+/// * `touchpads` - Touchpads found by the last scan
+/// * `deviations` - Number of deviations from the declared expectations
+///
+/// # Returns
+///
+/// * `String` - Metrics in the Prometheus text exposition format
+pub fn render(touchpads: &[Touchpad], deviations: usize) -> String {
+    let mut out = String::new();
+    for counter in Counter::all() {
+        if counter.is_high_water() {
+            let _ = writeln!(out, "# TYPE {}_{} gauge", PREFIX, counter.name());
+            let _ = writeln!(out, "{}_{} {}", PREFIX, counter.name(), STATS.get(counter));
+        } else {
+            let _ = writeln!(out, "# TYPE {}_{}_total counter", PREFIX, counter.name());
+            let _ = writeln!(out, "{}_{}_total {}", PREFIX, counter.name(), STATS.get(counter));
+        }
+    }
+
+    let _ = writeln!(out, "# HELP {}_touchpads Touchpads found by the last scan", PREFIX);
+    let _ = writeln!(out, "# TYPE {}_touchpads gauge", PREFIX);
+    let _ = writeln!(out, "{}_touchpads {}", PREFIX, touchpads.len());
+
+    let _ = writeln!(out, "# HELP {}_touchpad_health_score Health of a touchpad, 0 to 100", PREFIX);
+    let _ = writeln!(out, "# TYPE {}_touchpad_health_score gauge", PREFIX);
+    for touchpad in touchpads.iter() {
+        let Some(score) = health_score(touchpad) else {
+            continue;
+        };
+        let _ = write!(out, "{}_touchpad_health_score{{path=\"", PREFIX);
+        write_label(&mut out, &touchpad.path);
+        out.push_str("\",name=\"");
+        write_label(&mut out, &touchpad.name);
+        let _ = writeln!(out, "\"}} {}", score);
+    }
+
+    let _ = writeln!(out, "# HELP {}_health_score Lowest health score of the touchpads, 0 to 100", PREFIX);
+    let _ = writeln!(out, "# TYPE {}_health_score gauge", PREFIX);
+    if let Some(score) = touchpads.iter().filter_map(health_score).min() {
+        let _ = writeln!(out, "{}_health_score {}", PREFIX, score);
+    }
+
+    let _ = writeln!(out, "# HELP {}_expectation_deviations Deviations from the declared expectations", PREFIX);
+    let _ = writeln!(out, "# TYPE {}_expectation_deviations gauge", PREFIX);
+    let _ = writeln!(out, "{}_expectation_deviations {}", PREFIX, deviations);
+    out
+}
@@ -0,0 +1,67 @@
+//! Asynchronous notifications pushed to userspace over generic netlink.
+//!
+//! The C side registers the `driver_verifier` generic netlink family with an
+//! `events` multicast group. Each notification carries an event id, a subject
+//! (device name or path, may be empty) and an event specific value, so a
+//! userspace daemon can react to changes instead of polling.
+//!
+//! Building a netlink message allocates, so `notify` must not be called on the
+//! event path. SYN_DROPPED, the one notification raised there, goes through
+//! `notify_syn_dropped`, which only flags the slot for a C work item to send.
+use crate::shim;
+
+/// Kinds of notifications. The numeric values are part of the userspace ABI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Notification {
+    /// An input device was connected. Subject: device name.
+    DeviceAdded = 1,
+    /// An input device was disconnected. Subject: device name.
+    DeviceRemoved = 2,
+    /// A touchpad verification run started. Subject: device path.
+    VerificationStarted = 3,
+    /// A touchpad verification run finished. Subject: device path, value: 1 if working.
+    VerificationFinished = 4,
+    /// The working status changed from the previous run. Subject: device path, value: 1 if working.
+    StatusChanged = 5,
+    /// A monitored device reported SYN_DROPPED. Value: monitor slot.
+    SynDropped = 6,
+    /// The watchdog suspects the touchpad froze. Subject: device path, value: silence in ms.
+    FreezeSuspected = 7,
+    /// The touchpad disappeared or its node was reused since the last scan. Subject: device path.
+    DeviceGone = 8,
+}
+
+/// Sends a notification to the netlink multicast group.
+///
+/// Delivery is best effort: if there are no listeners or the message can't be
+/// allocated, the notification is dropped.
+///
+/// # Arguments
+///
+/// * `kind` - Kind of notification
+/// * `subject` - Device name or path the notification is about, may be empty
+/// * `value` - Event specific value
+pub fn notify(kind: Notification, subject: &str, value: i32) {
+    if let Some(kernel_notify) = shim::ops().notify {
+        let subject_bytes = subject.as_bytes();
+        unsafe { kernel_notify(kind as u32, subject_bytes.as_ptr(), subject_bytes.len(), value) };
+    }
+}
+
+// The C work item sends SynDropped by its id, DV_NOTIFY_SYN_DROPPED in driver_verifier_netlink.c
+const _: () = assert!(Notification::SynDropped as u32 == 6);
+
+/// Queues a `SynDropped` notification for a monitor slot.
+///
+/// Safe to call from interrupt context. Drops reported before the
+/// notification is sent are coalesced into one per slot.
+///
+/// # Arguments
+///
+/// * `slot` - Monitor slot that reported SYN_DROPPED
+pub fn notify_syn_dropped(slot: u32) {
+    if let Some(kernel_notify_syn_dropped) = shim::ops().notify_syn_dropped {
+        unsafe { kernel_notify_syn_dropped(slot) };
+    }
+}
@@ -0,0 +1,98 @@
+//! Fixed-capacity string pool for the directory listing of a scan.
+//!
+//! Every scan lists /sys/class/input, which holds several entries per device.
+//! Allocating a `String` per entry fragments the heap of memory constrained
+//! targets until a later scan fails to allocate. The pool is allocated once,
+//! sized by the `scan_pool_bytes` and `scan_pool_entries` module parameters,
+//! and reused by every scan, which borrows the entries from it. It never grows:
+//! entries that don't fit are dropped and counted, and the high-water marks
+//! are exported as statistics to help sizing the pool.
+//!
+//! Only the listing is pooled. The devices the scan keeps outlive it and are
+//! still owned `DeviceInfo` records with their own strings (paths, name, stable
+//! links), allocated once per input device rather than once per entry.
+use alloc::vec::Vec;
+
+use crate::stats::{Counter, STATS};
+
+/// Default number of bytes of string data the pool holds.
+pub const DEFAULT_POOL_BYTES: usize = 8192;
+
+/// Default number of strings the pool holds.
+pub const DEFAULT_POOL_ENTRIES: usize = 256;
+
+/// Location of a string in the pool.
+#[derive(Clone, Copy)]
+struct Span {
+    start: usize,
+    len: usize,
+}
+
+/// Preallocated storage for the strings of one scan.
+pub struct StringPool {
+    bytes: Vec<u8>,
+    spans: Vec<Span>,
+    /// Strings dropped since the last clear because the pool was full.
+    dropped: usize,
+}
+
+impl StringPool {
+    /// Allocates a pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - Capacity for string data in bytes
+    /// * `entries` - Capacity in number of strings
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, ()>` - The pool, or Err if the allocation fails
+    pub fn new(bytes: usize, entries: usize) -> Result<Self, ()> {
+        let mut pool = Self { bytes: Vec::new(), spans: Vec::new(), dropped: 0 };
+        pool.bytes.try_reserve_exact(bytes).map_err(|_| ())?;
+        pool.spans.try_reserve_exact(entries).map_err(|_| ())?;
+        Ok(pool)
+    }
+
+    /// Removes all strings, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+        self.spans.clear();
+        self.dropped = 0;
+    }
+
+    /// Adds a string without allocating.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ()>` - Ok if the string was added, Err if the pool is full
+    pub fn push(&mut self, string: &str) -> Result<(), ()> {
+        let full = self.spans.len() == self.spans.capacity()
+            || self.bytes.capacity() - self.bytes.len() < string.len();
+        if full {
+            self.dropped += 1;
+            STATS.inc(Counter::PoolExhausted);
+            return Err(());
+        }
+
+        self.spans.push(Span { start: self.bytes.len(), len: string.len() });
+        self.bytes.extend_from_slice(string.as_bytes());
+        STATS.max(Counter::PoolHighWaterBytes, self.bytes.len() as u64);
+        STATS.max(Counter::PoolHighWaterEntries, self.spans.len() as u64);
+        Ok(())
+    }
+
+    /// Returns the number of strings dropped since the last clear.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Iterates over the strings in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.spans.iter().map(|span| {
+            self.bytes.get(span.start..span.start + span.len)
+                .and_then(|bytes| core::str::from_utf8(bytes).ok())
+                .unwrap_or("")
+        })
+    }
+}
@@ -0,0 +1,67 @@
+//! Verification profiles.
+//!
+//! A profile selects which verification phases run and how long the event
+//! check waits, trading thoroughness for speed:
+//!
+//! - `quick` checks the modules and the device node only, for boot-time checks
+//! - `standard` adds the capability based event check, the palm data check
+//!   and the special function key check
+//! - `deep` also watches the touchpad for events without prompting, twice as
+//!   long as the interactive window, and uses them when any arrive
+//! - `interactive` prompts the user to touch the touchpad and requires events
+use crate::trace::Phase;
+
+/// A verification profile. The numeric values identify profiles over FFI and
+/// must match `DV_PROFILE_*` in driver_verifier_ioctl.h.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Profile {
+    Quick = 0,
+    Standard = 1,
+    Deep = 2,
+    Interactive = 3,
+}
+
+impl Profile {
+    /// Converts a raw profile id received over FFI.
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(Profile::Quick),
+            1 => Some(Profile::Standard),
+            2 => Some(Profile::Deep),
+            3 => Some(Profile::Interactive),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the profile.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Profile::Quick => "quick",
+            Profile::Standard => "standard",
+            Profile::Deep => "deep",
+            Profile::Interactive => "interactive",
+        }
+    }
+
+    /// Returns true if the profile runs the given phase.
+    pub fn runs(&self, phase: Phase) -> bool {
+        match phase {
+            Phase::Modules | Phase::Responsive => true,
+            Phase::Events | Phase::Palm | Phase::Multitouch | Phase::FnKeys => *self != Profile::Quick,
+        }
+    }
+
+    /// Returns how long the event check watches for events, 0 if it doesn't.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_ms` - Configured interactive event window
+    pub fn event_window_ms(&self, window_ms: u32) -> u32 {
+        match self {
+            Profile::Quick | Profile::Standard => 0,
+            Profile::Deep => window_ms.saturating_mul(2),
+            Profile::Interactive => window_ms,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Known hardware whose capabilities the driver can under-report.
+//!
+//! Several touchpad families are multitouch capable, but fall back to a
+//! degraded mode when the driver with full support isn't used: PS/2 Synaptics
+//! and Elantech touchpads only report semi-MT or single touch through psmouse
+//! unless they are handed to their SMBus driver, and I2C HID touchpads report
+//! as a plain mouse when hid-multitouch doesn't bind. To the user this shows up
+//! as "scrolling stopped working" while the touchpad still moves the pointer.
+//! The verifier matches touchpads against this table so a degraded mode is
+//! flagged instead of passing.
+use crate::matcher::{MatchKind, NameRule};
+
+/// A touchpad family that supports full multitouch.
+pub struct MultitouchQuirk {
+    pub rule: NameRule,
+    /// How to get the touchpad out of a degraded mode.
+    pub hint: &'static str,
+}
+
+impl MultitouchQuirk {
+    const fn new(text: &'static str, kind: MatchKind, hint: &'static str) -> Self {
+        Self { rule: NameRule::new(text, kind), hint }
+    }
+}
+
+/// Touchpads known to support full multitouch with the right driver.
+pub const MULTITOUCH_QUIRKS: &[MultitouchQuirk] = &[
+    MultitouchQuirk::new(
+        "SynPS/2 Synaptics TouchPad", MatchKind::Contains,
+        "boot with psmouse.synaptics_intertouch=1 and load rmi_smbus to use the SMBus interface",
+    ),
+    MultitouchQuirk::new(
+        "ETPS/2 Elantech Touchpad", MatchKind::Contains,
+        "boot with psmouse.elantech_smbus=1 and load elan_i2c to use the SMBus interface",
+    ),
+    // I2C HID touchpads are named "<ACPI id>:00 <vendor>:<product> Touchpad"
+    MultitouchQuirk::new(
+        "*:00 ????:???? Touchpad", MatchKind::Glob,
+        "load hid_multitouch so it binds instead of hid-generic",
+    ),
+];
+
+/// Finds the multitouch quirk matching a device name.
+///
+/// # Arguments
+///
+/// * `name` - Raw device name
+pub fn multitouch_quirk(name: &[u8]) -> Option<&'static MultitouchQuirk> {
+    MULTITOUCH_QUIRKS.iter().find(|quirk| quirk.rule.matches(name))
+}
@@ -0,0 +1,240 @@
+//! Stable reason codes for the findings of a verification.
+//!
+//! Reports and the kernel log used to explain failures in English sentences
+//! only, which userspace had to match by text, and which broke whenever a
+//! message was reworded. Every finding now has a numeric code that never
+//! changes meaning; the English text lives in the table below only and may
+//! be reworded freely. Serialized reports carry the codes, the log shows the
+//! code next to the text, and the whole table is exported through sysfs so
+//! tooling and translated frontends can map codes they don't know yet.
+//!
+//! Codes are grouped by hundreds: 1xx modules, 2xx device node, 3xx events,
+//! 4xx delivery to userspace, 5xx firmware node, 6xx evdev ABI, 7xx recovery,
+//! 8xx expectations and snapshots, 9xx verification status. Codes are never
+//! reused; a retired reason keeps its number reserved.
+//!
+//! Report values that are not findings but one of a fixed set, such as the
+//! multitouch mode, are serialized as snake_case tokens. Their English text
+//! lives here as well and is part of the exported table.
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+use crate::report::{MultitouchMode, RecoveryAction};
+
+/// A finding of a verification. The discriminant is the stable code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u16)]
+pub enum Reason {
+    ModulesMissing = 100,
+    NodeOpenFailed = 200,
+    CapabilityQueryFailed = 201,
+    SynRoundtripFailed = 202,
+    NoEvents = 300,
+    NotCapable = 301,
+    CapabilityOnly = 302,
+    FreezeSuspected = 303,
+    GrabbedByOther = 400,
+    NoOpenHandler = 401,
+    PointerPropMissing = 402,
+    UdevDisagrees = 403,
+    MultitouchDegraded = 404,
+    PalmDataUnusable = 405,
+    IrqNotMapped = 500,
+    IrqUnclaimed = 501,
+    IrqGpioMissing = 502,
+    ResetGpioMissing = 503,
+    ResetGpioUnclaimed = 504,
+    EventSizeMismatch = 600,
+    TimeSizeMismatch = 601,
+    FieldOffsetMismatch = 602,
+    ByteOrderMismatch = 603,
+    CompatSizeMismatch = 604,
+    CompatHeaderRisk = 610,
+    NoCompat = 611,
+    RecoveryFailed = 700,
+    CountMismatch = 800,
+    NameMismatch = 801,
+    Disappeared = 810,
+    DriverChanged = 811,
+    Regressed = 812,
+    VerificationFailed = 900,
+    DeviceGone = 901,
+    NotExpected = 902,
+    EnvironmentUnsupported = 903,
+}
+
+impl Reason {
+    /// Every reason, in code order.
+    pub const ALL: [Reason; 36] = [
+        Reason::ModulesMissing,
+        Reason::NodeOpenFailed,
+        Reason::CapabilityQueryFailed,
+        Reason::SynRoundtripFailed,
+        Reason::NoEvents,
+        Reason::NotCapable,
+        Reason::CapabilityOnly,
+        Reason::FreezeSuspected,
+        Reason::GrabbedByOther,
+        Reason::NoOpenHandler,
+        Reason::PointerPropMissing,
+        Reason::UdevDisagrees,
+        Reason::MultitouchDegraded,
+        Reason::PalmDataUnusable,
+        Reason::IrqNotMapped,
+        Reason::IrqUnclaimed,
+        Reason::IrqGpioMissing,
+        Reason::ResetGpioMissing,
+        Reason::ResetGpioUnclaimed,
+        Reason::EventSizeMismatch,
+        Reason::TimeSizeMismatch,
+        Reason::FieldOffsetMismatch,
+        Reason::ByteOrderMismatch,
+        Reason::CompatSizeMismatch,
+        Reason::CompatHeaderRisk,
+        Reason::NoCompat,
+        Reason::RecoveryFailed,
+        Reason::CountMismatch,
+        Reason::NameMismatch,
+        Reason::Disappeared,
+        Reason::DriverChanged,
+        Reason::Regressed,
+        Reason::VerificationFailed,
+        Reason::DeviceGone,
+        Reason::NotExpected,
+        Reason::EnvironmentUnsupported,
+    ];
+
+    /// Returns the stable code of the reason.
+    pub fn code(&self) -> u16 {
+        *self as u16
+    }
+
+    /// Returns the English description of the reason.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Reason::ModulesMissing => "required touchpad module not loaded",
+            Reason::NodeOpenFailed => "device node can't be opened",
+            Reason::CapabilityQueryFailed => "device doesn't answer the capability query",
+            Reason::SynRoundtripFailed => "injected EV_SYN frame not delivered back through the input core",
+            Reason::NoEvents => "no events observed within the wait window",
+            Reason::NotCapable => "device doesn't advertise the absolute axes of a touchpad",
+            Reason::CapabilityOnly => "capabilities validated only, no events observed",
+            Reason::FreezeSuspected => "touchpad silent while the keyboard is active",
+            Reason::GrabbedByOther => "another client grabbed the device, other consumers receive no events",
+            Reason::NoOpenHandler => "no handler has the device open, nothing reads its events",
+            Reason::PointerPropMissing => "INPUT_PROP_POINTER not set, userspace may not treat the device as a touchpad",
+            Reason::UdevDisagrees => "classification differs from udev, userspace may treat the device differently",
+            Reason::MultitouchDegraded => "degraded multitouch mode, the hardware supports full multitouch",
+            Reason::PalmDataUnusable => "no contact size or tool type data for palm rejection",
+            Reason::IrqNotMapped => "interrupt described but not mapped",
+            Reason::IrqUnclaimed => "interrupt not requested by any driver",
+            Reason::IrqGpioMissing => "interrupt GPIO described but its controller is missing",
+            Reason::ResetGpioMissing => "reset GPIO described but its controller is missing",
+            Reason::ResetGpioUnclaimed => "reset GPIO not claimed by the driver",
+            Reason::EventSizeMismatch => "event size differs from the layout the verifier was built for",
+            Reason::TimeSizeMismatch => "time field size differs from the word size",
+            Reason::FieldOffsetMismatch => "type, code or value offset differs from the expected layout",
+            Reason::ByteOrderMismatch => "byte order differs from the one the verifier was built for",
+            Reason::CompatSizeMismatch => "compat event size is not 16 bytes",
+            Reason::CompatHeaderRisk => "32-bit clients read 16-byte events, one built with 64-bit event \
+                                         headers misreads every event",
+            Reason::NoCompat => "no 32-bit compat support, 32-bit clients can't read events",
+            Reason::RecoveryFailed => "recovery action did not restore the touchpad",
+            Reason::CountMismatch => "number of devices of a type differs from the declared expectation",
+            Reason::NameMismatch => "device name doesn't match the declared pattern",
+            Reason::Disappeared => "device of the older snapshot is no longer present",
+            Reason::DriverChanged => "device is bound to another driver than in the older snapshot",
+            Reason::Regressed => "device that was working is no longer working or verified",
+            Reason::VerificationFailed => "verification failed",
+            Reason::DeviceGone => "device gone",
+            Reason::NotExpected => "not expected (virtual environment)",
+            Reason::EnvironmentUnsupported => "environment unsupported (no /sys/class/input)",
+        }
+    }
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.code(), self.as_str())
+    }
+}
+
+/// A report value serialized as a stable snake_case token.
+///
+/// Like the codes, tokens never change meaning and are never reused.
+pub trait Token: Copy + 'static {
+    /// Report field the values are serialized in.
+    const FIELD: &'static str;
+    /// Every value, in table order.
+    const VALUES: &'static [Self];
+
+    /// Returns the stable token of the value.
+    fn token(&self) -> &'static str;
+
+    /// Returns the English description of the value.
+    fn text(&self) -> &'static str;
+}
+
+impl Token for MultitouchMode {
+    const FIELD: &'static str = "multitouch";
+    const VALUES: &'static [Self] =
+        &[MultitouchMode::Unknown, MultitouchMode::Full, MultitouchMode::SemiMt, MultitouchMode::SingleTouch];
+
+    fn token(&self) -> &'static str {
+        match self {
+            MultitouchMode::Unknown => "unknown",
+            MultitouchMode::Full => "full",
+            MultitouchMode::SemiMt => "semi_mt",
+            MultitouchMode::SingleTouch => "single_touch",
+        }
+    }
+
+    fn text(&self) -> &'static str {
+        match self {
+            MultitouchMode::Unknown => "unknown",
+            MultitouchMode::Full => "full multitouch",
+            MultitouchMode::SemiMt => "semi-MT",
+            MultitouchMode::SingleTouch => "single touch",
+        }
+    }
+}
+
+impl Token for RecoveryAction {
+    const FIELD: &'static str = "recovery";
+    const VALUES: &'static [Self] = &[RecoveryAction::Rebind, RecoveryAction::LoadModule, RecoveryAction::ResetGpio];
+
+    fn token(&self) -> &'static str {
+        match self {
+            RecoveryAction::Rebind => "rebind",
+            RecoveryAction::LoadModule => "load_module",
+            RecoveryAction::ResetGpio => "reset_gpio",
+        }
+    }
+
+    fn text(&self) -> &'static str {
+        match self {
+            RecoveryAction::Rebind => "driver rebind",
+            RecoveryAction::LoadModule => "module load",
+            RecoveryAction::ResetGpio => "reset GPIO pulse",
+        }
+    }
+}
+
+/// Renders the code table, one `code<TAB>description` line per reason, followed by one
+/// `field:token<TAB>description` line per token.
+pub fn table() -> String {
+    let mut table = String::new();
+    for reason in Reason::ALL {
+        let _ = writeln!(table, "{}\t{}", reason.code(), reason.as_str());
+    }
+    tokens::<MultitouchMode>(&mut table);
+    tokens::<RecoveryAction>(&mut table);
+    table
+}
+
+/// Appends the table lines of the tokens of one field.
+fn tokens<T: Token>(table: &mut String) {
+    for value in T::VALUES {
+        let _ = writeln!(table, "{}:{}\t{}", T::FIELD, value.token(), value.text());
+    }
+}
@@ -0,0 +1,286 @@
+//! Event recording and replay for regression testing.
+//!
+//! While recording, every event of the touchpad is captured with its timestamp
+//! into a fixed size ring buffer; once it is full the oldest events are
+//! overwritten. A trace is read out through the ioctl interface so userspace
+//! can store it, and a stored trace can be loaded back and replayed through a
+//! virtual input device cloning the touchpad capabilities, which reproduces an
+//! issue deterministically.
+//!
+//! Events are captured from interrupt context, so the ring buffer is a static
+//! array of atomics written by a single producer, the recorded device.
+use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+
+use crate::event_monitor::{now_us, sleep_ms, EVENT_MONITOR, RECORDER_SLOT};
+use crate::kprint;
+use crate::shim;
+
+/// Number of events the ring buffer holds. Must match `DV_RECORDER_CAPACITY`.
+pub const RECORDER_CAPACITY: usize = 4096;
+
+/// Longest pause replayed between two events, in milliseconds.
+///
+/// Idle periods in a trace are shortened so a replay never stalls for long.
+const MAX_REPLAY_GAP_MS: u64 = 1000;
+
+/// A recorded input event. The layout matches `struct dv_event_record`.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct RecordedEvent {
+    /// Time since the recording started, in microseconds.
+    pub time_us: u64,
+    pub ev_type: u16,
+    pub code: u16,
+    pub value: i32,
+}
+
+/// Commands accepted by the recorder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecorderCommand {
+    Start,
+    Stop,
+    Replay,
+}
+
+impl RecorderCommand {
+    /// Converts a raw command number received over FFI.
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            1 => Some(RecorderCommand::Start),
+            2 => Some(RecorderCommand::Stop),
+            3 => Some(RecorderCommand::Replay),
+            _ => None,
+        }
+    }
+}
+
+/// One ring buffer entry.
+struct RecordSlot {
+    time_us: AtomicU64,
+    /// Event type in the high and event code in the low 16 bits.
+    type_code: AtomicU32,
+    value: AtomicI32,
+}
+
+impl RecordSlot {
+    const fn new() -> Self {
+        Self {
+            time_us: AtomicU64::new(0),
+            type_code: AtomicU32::new(0),
+            value: AtomicI32::new(0),
+        }
+    }
+
+    fn store(&self, event: &RecordedEvent) {
+        self.time_us.store(event.time_us, Ordering::Relaxed);
+        self.type_code.store(((event.ev_type as u32) << 16) | event.code as u32, Ordering::Relaxed);
+        self.value.store(event.value, Ordering::Relaxed);
+    }
+
+    fn load(&self) -> RecordedEvent {
+        let type_code = self.type_code.load(Ordering::Relaxed);
+        RecordedEvent {
+            time_us: self.time_us.load(Ordering::Relaxed),
+            ev_type: (type_code >> 16) as u16,
+            code: type_code as u16,
+            value: self.value.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Ring buffer of recorded events.
+pub struct Recorder {
+    entries: [RecordSlot; RECORDER_CAPACITY],
+    /// Events written since the buffer was last cleared, including overwritten ones.
+    written: AtomicUsize,
+    recording: AtomicBool,
+    start_us: AtomicU64,
+}
+
+/// Global recorder instance, written from the input handler.
+pub static RECORDER: Recorder = Recorder::new();
+
+impl Recorder {
+    const fn new() -> Self {
+        Self {
+            entries: [const { RecordSlot::new() }; RECORDER_CAPACITY],
+            written: AtomicUsize::new(0),
+            recording: AtomicBool::new(false),
+            start_us: AtomicU64::new(0),
+        }
+    }
+
+    /// Clears the buffer and starts recording the events of a device.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the device node to record
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ()>` - Ok if recording started, Err if already recording or the device
+    ///   can't be monitored
+    pub fn start(&self, path: &str) -> Result<(), ()> {
+        if self.is_recording() {
+            return Err(());
+        }
+
+        self.written.store(0, Ordering::Release);
+        self.start_us.store(now_us(), Ordering::Relaxed);
+        self.recording.store(true, Ordering::Release);
+
+        if EVENT_MONITOR.attach(RECORDER_SLOT, path).is_err() {
+            self.recording.store(false, Ordering::Release);
+            return Err(());
+        }
+
+        kprint!("Recording events of {}\n", path);
+        Ok(())
+    }
+
+    /// Stops recording.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - Number of events in the buffer
+    pub fn stop(&self) -> usize {
+        if self.recording.swap(false, Ordering::AcqRel) {
+            EVENT_MONITOR.detach(RECORDER_SLOT);
+            kprint!("Recording stopped, {} events captured\n", self.event_count());
+        }
+        self.event_count()
+    }
+
+    /// Returns true while events are being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recording.load(Ordering::Acquire)
+    }
+
+    /// Returns the number of events in the buffer.
+    pub fn event_count(&self) -> usize {
+        self.written.load(Ordering::Acquire).min(RECORDER_CAPACITY)
+    }
+
+    /// Records an event delivered by the input handler.
+    ///
+    /// Called from interrupt context; must not block or allocate.
+    ///
+    /// # Arguments
+    ///
+    /// * `ev_type` - Event type (EV_*)
+    /// * `code` - Event code
+    /// * `value` - Event value
+    pub fn record(&self, ev_type: u32, code: u32, value: i32) {
+        if !self.is_recording() {
+            return;
+        }
+
+        let index = self.written.load(Ordering::Relaxed);
+        let event = RecordedEvent {
+            time_us: now_us().saturating_sub(self.start_us.load(Ordering::Relaxed)),
+            ev_type: ev_type as u16,
+            code: code as u16,
+            value,
+        };
+        self.entries[index % RECORDER_CAPACITY].store(&event);
+        self.written.store(index + 1, Ordering::Release);
+    }
+
+    /// Copies the recorded events, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - Receives the events; at most `out.len()` events are copied
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize, ()>` - Number of events copied, Err while recording
+    pub fn read(&self, out: &mut [RecordedEvent]) -> Result<usize, ()> {
+        if self.is_recording() {
+            return Err(());
+        }
+
+        let written = self.written.load(Ordering::Acquire);
+        let first = written - written.min(RECORDER_CAPACITY);
+        let count = self.event_count().min(out.len());
+        for (i, event) in out.iter_mut().take(count).enumerate() {
+            *event = self.entries[(first + i) % RECORDER_CAPACITY].load();
+        }
+
+        Ok(count)
+    }
+
+    /// Replaces the buffer with a stored trace.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - Events to load, oldest first
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ()>` - Ok if loaded, Err while recording or if the trace doesn't fit
+    pub fn load(&self, events: &[RecordedEvent]) -> Result<(), ()> {
+        if self.is_recording() || events.len() > RECORDER_CAPACITY {
+            return Err(());
+        }
+
+        for (slot, event) in self.entries.iter().zip(events.iter()) {
+            slot.store(event);
+        }
+        self.written.store(events.len(), Ordering::Release);
+        Ok(())
+    }
+
+    /// Replays the buffer through a virtual input device.
+    ///
+    /// The virtual device clones the capabilities of the given device, and the
+    /// events are injected with their original spacing, except that pauses are
+    /// capped at `MAX_REPLAY_GAP_MS`. Blocks until the whole trace is replayed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the device node whose capabilities the virtual device clones
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize, ()>` - Number of events replayed, Err while recording or if the
+    ///   virtual device can't be created
+    pub fn replay(&self, path: &str) -> Result<usize, ()> {
+        let ops = shim::ops();
+        let (Some(kernel_replay_create), Some(kernel_replay_event), Some(kernel_replay_destroy)) =
+            (ops.replay_create, ops.replay_event, ops.replay_destroy) else {
+            return Err(());
+        };
+        if self.is_recording() {
+            return Err(());
+        }
+
+        let path_bytes = path.as_bytes();
+        if unsafe { kernel_replay_create(path_bytes.as_ptr(), path_bytes.len()) } < 0 {
+            kprint!("Failed to create replay device for {}\n", path);
+            return Err(());
+        }
+
+        let written = self.written.load(Ordering::Acquire);
+        let first = written - written.min(RECORDER_CAPACITY);
+        let count = self.event_count();
+        kprint!("Replaying {} events\n", count);
+
+        let mut replay_us = self.entries[first % RECORDER_CAPACITY].load().time_us;
+        for i in 0..count {
+            let event = self.entries[(first + i) % RECORDER_CAPACITY].load();
+
+            // Sub-millisecond gaps accumulate until a whole millisecond can be slept
+            let gap_ms = event.time_us.saturating_sub(replay_us) / 1000;
+            if gap_ms > 0 {
+                sleep_ms(gap_ms.min(MAX_REPLAY_GAP_MS) as u32);
+                replay_us = event.time_us;
+            }
+
+            unsafe { kernel_replay_event(event.ev_type as u32, event.code as u32, event.value) };
+        }
+
+        unsafe { kernel_replay_destroy() };
+        Ok(count)
+    }
+}
@@ -0,0 +1,228 @@
+//! Recovery actions for touchpads that fail verification.
+//!
+//! Recovery is opt-in since it changes device state: rebinding a driver briefly
+//! removes the input device, which userspace sees as an unplug and replug, and
+//! loading a module changes which driver handles the device.
+//!
+//! Pulsing the reset GPIO goes further: it power cycles the touchpad
+//! controller through the reset GPIO its firmware node describes. A touchpad
+//! that works again afterwards had a hung controller firmware, one that stays
+//! silent most likely has dead hardware or wiring.
+use alloc::string::String;
+
+use crate::fwnode::ResourceState;
+use crate::kbuf::ShimBuf;
+use crate::kprint;
+use crate::report::{EventCheckResult, VerificationReport};
+use crate::shim;
+
+/// Time to wait after rebinding a driver for the input device to reappear, in milliseconds.
+pub const REBIND_SETTLE_MS: u32 = 1000;
+
+/// Longest time to wait for the touchpad to appear after loading modules, in milliseconds.
+pub const MODULE_LOAD_WAIT_MS: u32 = 5000;
+
+/// Interval between rescans while waiting for the touchpad after loading modules.
+pub const MODULE_POLL_MS: u32 = 500;
+
+/// How long the reset line is held asserted, in milliseconds.
+///
+/// Touchpad controllers need between 1 and 10 ms, a longer pulse is harmless.
+pub const RESET_ASSERT_MS: u32 = 20;
+
+/// Checks if a failed verification looks like a driver that is bound but unresponsive.
+///
+/// The modules must be loaded, and either the device node didn't respond or no
+/// events arrived while waiting for them. A device grabbed by another client
+/// never qualifies: its silence is the grab, and recovering it would disrupt a
+/// working device.
+///
+/// # Arguments
+///
+/// * `report` - Report of the failed verification run
+pub fn is_driver_unresponsive(report: &VerificationReport) -> bool {
+    report.modules_loaded
+        && report.grab.held_by.is_none()
+        && (!report.node_responsive || report.event_check == EventCheckResult::NoEvents)
+}
+
+/// Checks if the firmware node of a touchpad describes a reset line that resolves.
+///
+/// # Arguments
+///
+/// * `report` - Report of the failed verification run
+pub fn has_reset_gpio(report: &VerificationReport) -> bool {
+    report.fw_node.as_ref()
+        .is_some_and(|fw_node| matches!(fw_node.reset_gpio, ResourceState::Unclaimed | ResourceState::Claimed))
+}
+
+/// Returns the sysfs directory of an input device node.
+///
+/// # Arguments
+///
+/// * `device_path` - Path to the device node, e.g. `/dev/input/event5`
+pub fn sysfs_path(device_path: &str) -> String {
+    let node = device_path.rsplit('/').next().unwrap_or(device_path);
+    alloc::format!("/sys/class/input/{}", node)
+}
+
+/// Unbinds and rebinds the driver of the physical device behind an input device.
+///
+/// The physical device is the parent of the input device (e.g. the HID device
+/// for an i2c-hid touchpad). Its driver is found through the `driver` link, and
+/// the device name is written to the driver's `unbind` and then `bind` files.
+///
+/// # Arguments
+///
+/// * `sys_path` - sysfs directory of the input device node, e.g. `/sys/class/input/event5`
+///
+/// # Returns
+///
+/// * `Result<String, ()>` - Name of the rebound driver, or Err if the rebind failed
+pub fn rebind_driver(sys_path: &str) -> Result<String, ()> {
+    let device_dir = resolve_path(&alloc::format!("{}/device/device", sys_path))?;
+    let driver_dir = resolve_path(&alloc::format!("{}/device/device/driver", sys_path))?;
+
+    let device_name = last_component(&device_dir);
+    let driver_name = String::from(last_component(&driver_dir));
+
+    kprint!("Rebinding {} to driver {}\n", device_name, driver_name);
+
+    write_file(&alloc::format!("{}/unbind", driver_dir), device_name)?;
+    write_file(&alloc::format!("{}/bind", driver_dir), device_name)?;
+
+    Ok(driver_name)
+}
+
+/// Returns the name of the driver bound to the physical device behind an input device.
+///
+/// # Arguments
+///
+/// * `sys_path` - sysfs directory of the input device node, e.g. `/sys/class/input/event5`
+///
+/// # Returns
+///
+/// * `Result<String, ()>` - Driver name, or Err if no driver is bound
+pub fn bound_driver(sys_path: &str) -> Result<String, ()> {
+    let driver_dir = resolve_path(&alloc::format!("{}/device/device/driver", sys_path))?;
+    Ok(String::from(last_component(&driver_dir)))
+}
+
+/// Pulses the reset line of the physical device behind an input device and probes it again.
+///
+/// The shim unbinds the driver of the closest parent with a firmware node so
+/// it releases the reset GPIO, holds the line asserted for `assert_ms`,
+/// releases it and probes the device again. The input device disappears
+/// meanwhile and is registered anew if the driver binds.
+///
+/// # Arguments
+///
+/// * `device_path` - Path to the device node, e.g. `/dev/input/event5`
+/// * `assert_ms` - How long the reset line is held asserted
+///
+/// # Returns
+///
+/// * `Result<(), ()>` - Ok if the line was pulsed, Err if the device, its reset line or the shim is missing
+pub fn reset_device(device_path: &str, assert_ms: u32) -> Result<(), ()> {
+    let kernel_reset_fw_device = shim::ops().reset_fw_device.ok_or(())?;
+
+    unsafe {
+        let path_bytes = device_path.as_bytes();
+        if kernel_reset_fw_device(path_bytes.as_ptr(), path_bytes.len(), assert_ms) < 0 {
+            return Err(());
+        }
+
+        Ok(())
+    }
+}
+
+/// Requests a kernel module to be loaded.
+///
+/// # Arguments
+///
+/// * `name` - Module name
+///
+/// # Returns
+///
+/// * `Result<(), ()>` - Ok if modprobe succeeded, Err otherwise
+pub fn request_module(name: &str) -> Result<(), ()> {
+    let kernel_request_module = shim::ops().request_module.ok_or(())?;
+
+    unsafe {
+        let name_bytes = name.as_bytes();
+        if kernel_request_module(name_bytes.as_ptr(), name_bytes.len()) < 0 {
+            return Err(());
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the last component of a path.
+pub fn last_component(path: &str) -> &str {
+    path.trim_end_matches('/').rsplit('/').next().unwrap_or(path)
+}
+
+/// Resolves a path, following symlinks, to its canonical form.
+///
+/// # Arguments
+///
+/// * `path` - Path to resolve
+///
+/// # Returns
+///
+/// * `Result<String, ()>` - Canonical path or error
+pub fn resolve_path(path: &str) -> Result<String, ()> {
+    let kernel_resolve_path = shim::ops().resolve_path.ok_or(())?;
+
+    let path_bytes = path.as_bytes();
+    let mut buffer = ShimBuf::new(256, 0u8);
+    let capacity = buffer.capacity();
+    let result = unsafe {
+        kernel_resolve_path(path_bytes.as_ptr(), path_bytes.len(), buffer.as_mut_ptr(), capacity,
+                            buffer.reported_mut())
+    };
+
+    // A truncated path would name a different file
+    if result < 0 || buffer.overflowed() {
+        return Err(());
+    }
+    let buffer = buffer.into_vec();
+    if buffer.is_empty() {
+        return Err(());
+    }
+    String::from_utf8(buffer).map_err(|_| ())
+}
+
+/// Writes a string to a sysfs file.
+///
+/// # Arguments
+///
+/// * `path` - File path
+/// * `contents` - Contents to write
+///
+/// # Returns
+///
+/// * `Result<(), ()>` - Ok if the whole string was written, Err otherwise
+pub fn write_file(path: &str, contents: &str) -> Result<(), ()> {
+    let kernel_write_file = shim::ops().write_file.ok_or(())?;
+
+    unsafe {
+        let path_bytes = path.as_bytes();
+        let contents_bytes = contents.as_bytes();
+
+        let result = kernel_write_file(
+            path_bytes.as_ptr(),
+            path_bytes.len(),
+            contents_bytes.as_ptr(),
+            contents_bytes.len()
+        );
+
+        if result < 0 {
+            kprint!("Failed to write {}\n", path);
+            return Err(());
+        }
+
+        Ok(())
+    }
+}
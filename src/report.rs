@@ -0,0 +1,640 @@
+//! Verification report describing the outcome of a single touchpad verification run.
+//!
+//! Every call to `verify_touchpad` produces a `VerificationReport` which is kept
+//! by the verifier, so the result of each phase can be inspected after the run
+//! instead of only the final working/not-working flag.
+//!
+//! Reports are handed to C and userspace serialized as a flat text blob, one
+//! field per line with the key and the value separated by a tab. Fields that
+//! are not known are left out and list fields repeat their key:
+//!
+//! ```text
+//! dv-report 2
+//! name<TAB>SYNA7DB5:01 06CB:CD41 Touchpad
+//! path<TAB>/dev/input/event5
+//! missing_module<TAB>i2c_hid_acpi
+//! timing<TAB>modules<TAB>412
+//! reason<TAB>100
+//! working<TAB>0
+//! ```
+//!
+//! Findings are serialized as the stable codes of `reason::Reason` rather than
+//! as text, one `reason` line each, and values from a fixed set as the tokens
+//! of `reason::Token`. No serialized value is English text; names, paths and
+//! kernel log lines are copied from the device and the kernel as they are.
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::evdev_abi::EvdevAbi;
+use crate::event_monitor;
+use crate::fnkeys::FnKeyCheck;
+use crate::fwnode::FwDescription;
+use crate::handlers::{self, DeviceHandler};
+use crate::input_verifier::{DeviceType, FirmwareInfo};
+use crate::kprint;
+use crate::profile::Profile;
+use crate::reason::{Reason, Token};
+use crate::safety::SafetyPolicy;
+use crate::stats::{Counter, STATS};
+use crate::trace::{Phase, PHASE_COUNT};
+use crate::trackpoint::TrackpointInfo;
+use crate::udev::{InputProps, UdevClass};
+
+/// Outcome of the input event check.
+///
+/// The event check runs in one of two modes. In interactive mode the user is
+/// asked to touch the touchpad and the verifier waits a bounded window for events
+/// to arrive through the input handler, so a pass means events were actually seen.
+/// In non-interactive mode no events are waited for and only the advertised
+/// capabilities are validated, so a pass only means the device claims to be able
+/// to generate touchpad events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventCheckResult {
+    /// Events from the device were observed within the wait window.
+    Verified,
+    /// The device advertises the axes of a touchpad, but no events were observed or waited for.
+    CapabilityOnly,
+    /// The wait window elapsed without any event from the device.
+    NoEvents,
+    /// The device does not advertise the absolute axes a touchpad needs.
+    NotCapable,
+    /// Another client grabbed the device, so its events reach that client only
+    /// and can't be observed. The device isn't known to be broken.
+    Grabbed,
+    /// The event check did not run because an earlier phase failed.
+    NotRun,
+}
+
+impl EventCheckResult {
+    /// Returns true if the result counts as a passing event check.
+    pub fn is_pass(&self) -> bool {
+        matches!(self, EventCheckResult::Verified | EventCheckResult::CapabilityOnly)
+    }
+
+    /// Returns a short human readable name for the result.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventCheckResult::Verified => "Verified",
+            EventCheckResult::CapabilityOnly => "CapabilityOnly",
+            EventCheckResult::NoEvents => "NoEvents",
+            EventCheckResult::NotCapable => "NotCapable",
+            EventCheckResult::Grabbed => "Grabbed",
+            EventCheckResult::NotRun => "NotRun",
+        }
+    }
+}
+
+/// Outcome of one step of a multi-step check.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// The step did not run because an earlier step failed, or it could not
+    /// test anything on this device.
+    #[default]
+    NotRun,
+    Passed,
+    Failed,
+}
+
+impl StepOutcome {
+    /// Returns a short human readable name for the outcome.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepOutcome::NotRun => "not run",
+            StepOutcome::Passed => "passed",
+            StepOutcome::Failed => "failed",
+        }
+    }
+}
+
+/// Steps of the device node responsiveness check.
+///
+/// The steps run in order through the input handle the verifier holds on the
+/// device, and stop at the first failure:
+///
+/// 1. `opened` - the node belongs to a connected device whose driver accepts an
+///    open. Not run when something else already has the device open, because
+///    opening it again doesn't reach the driver; the later steps still run
+/// 2. `capabilities` - the device answers a capability query and supports EV_SYN
+/// 3. `syn_roundtrip` - an EV_SYN frame injected into the device is delivered
+///    back through the input core, so events flow from the device to its handlers.
+///    Not run under the read-only safety policy, which forbids the injection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResponsiveCheck {
+    pub opened: StepOutcome,
+    pub capabilities: StepOutcome,
+    pub syn_roundtrip: StepOutcome,
+}
+
+impl ResponsiveCheck {
+    /// Returns true if no step failed and the device answered the capability query.
+    pub fn passed(&self) -> bool {
+        self.capabilities == StepOutcome::Passed && self.failed_reason().is_none()
+    }
+
+    /// Returns the reason of the first failed step, if any.
+    pub fn failed_reason(&self) -> Option<Reason> {
+        [(Reason::NodeOpenFailed, self.opened), (Reason::CapabilityQueryFailed, self.capabilities),
+         (Reason::SynRoundtripFailed, self.syn_roundtrip)]
+            .iter()
+            .find(|(_, step)| *step == StepOutcome::Failed)
+            .map(|(reason, _)| *reason)
+    }
+}
+
+/// Grabs on the device seen during the verification.
+#[derive(Debug, Clone, Default)]
+pub struct GrabCheck {
+    /// Handler that held a grab on the device when it was verified, e.g. "evdev"
+    /// for a userspace client. Other consumers, including the desktop, receive no
+    /// events from a grabbed device.
+    pub held_by: Option<String>,
+    /// Whether the event check ran with the device grabbed by the verifier.
+    pub exclusive: bool,
+}
+
+/// How a touchpad reports its contacts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MultitouchMode {
+    /// The capabilities could not be read.
+    #[default]
+    Unknown,
+    /// Every contact is reported with its own position.
+    Full,
+    /// Only the bounding box of the contacts is reported (INPUT_PROP_SEMI_MT).
+    SemiMt,
+    /// A single position is reported, without any ABS_MT axes.
+    SingleTouch,
+}
+
+/// Multitouch mode of a touchpad and whether it is below what the hardware supports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MultitouchCheck {
+    pub mode: MultitouchMode,
+    /// Set when the quirk table says the hardware supports full multitouch but it
+    /// runs in a degraded mode: how to restore full multitouch.
+    pub degraded_hint: Option<&'static str>,
+}
+
+impl MultitouchCheck {
+    /// Returns true if the touchpad runs in a degraded mode.
+    pub fn degraded(&self) -> bool {
+        self.degraded_hint.is_some()
+    }
+}
+
+/// Availability and quality of the data needed for palm rejection.
+///
+/// Palm rejection in userspace relies on contact size (ABS_MT_TOUCH_MAJOR/MINOR)
+/// or on the firmware marking contacts as palms (ABS_MT_TOOL_TYPE). The axes
+/// are read from the device capabilities; the observed values are only filled
+/// in when events were monitored during an interactive run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PalmCheck {
+    pub has_touch_major: bool,
+    pub has_touch_minor: bool,
+    pub has_tool_type: bool,
+    pub has_pressure: bool,
+    /// Largest ABS_MT_TOUCH_MAJOR value observed during monitoring.
+    pub max_touch_major: i32,
+    /// Touch size reports flagged as implausibly large for a finger.
+    pub large_contacts: u32,
+    /// Contacts the firmware reported with MT_TOOL_PALM.
+    pub palm_reports: u32,
+    /// Whether events were monitored, so the observed values are meaningful.
+    pub observed: bool,
+    /// Whether the firmware provides data usable for palm rejection.
+    pub usable: bool,
+}
+
+/// First line of every serialized report, including the format version.
+const SERIALIZED_HEADER: &str = "dv-report 2";
+
+/// Phase durations above which a phase is reported as slow, in microseconds.
+pub const SLOW_PHASE_US: u64 = 1_000_000;
+
+/// Monotonic time spent in each verification phase.
+///
+/// Phases reading sysfs or talking to the device can block, e.g. on a wedged
+/// i2c bus, so the durations show which phase made a run slow and help tuning
+/// the timeouts. Every recorded duration is also added to the cumulative phase
+/// statistics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseTimings {
+    /// Duration of each phase in microseconds, indexed by `Phase`, None if it didn't run.
+    durations_us: [Option<u64>; PHASE_COUNT],
+}
+
+impl PhaseTimings {
+    /// Records the duration of a phase that just ended.
+    ///
+    /// # Arguments
+    ///
+    /// * `phase` - Phase that ended
+    /// * `started_us` - Monotonic time the phase started at, from `event_monitor::now_us`
+    pub fn record(&mut self, phase: Phase, started_us: u64) {
+        let duration_us = event_monitor::now_us().saturating_sub(started_us);
+        if let Some(slot) = self.durations_us.get_mut(phase as usize) {
+            *slot = Some(duration_us);
+        }
+
+        let (total, longest) = Counter::for_phase(phase);
+        STATS.add(total, duration_us);
+        STATS.max(longest, duration_us);
+        if duration_us >= SLOW_PHASE_US {
+            kprint!("Phase {} took {} ms\n", phase.as_str(), duration_us / 1000);
+        }
+    }
+
+    /// Returns the duration of a phase in microseconds, None if it didn't run.
+    pub fn get(&self, phase: Phase) -> Option<u64> {
+        self.durations_us.get(phase as usize).copied().flatten()
+    }
+
+    /// Returns the time spent in all phases that ran, in microseconds.
+    pub fn total_us(&self) -> u64 {
+        self.durations_us.iter().flatten().sum()
+    }
+}
+
+/// Recovery actions the verifier can take on a failing touchpad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryAction {
+    /// The driver was unbound from the device and bound again.
+    Rebind,
+    /// Missing modules were loaded.
+    LoadModule,
+    /// The reset line described by the firmware node was pulsed and the device probed again.
+    ResetGpio,
+}
+
+/// A recovery attempt and whether it restored the touchpad.
+pub struct RecoveryAttempt {
+    pub action: RecoveryAction,
+    /// What the action operated on, e.g. the driver name (may be empty).
+    pub target: String,
+    pub succeeded: bool,
+}
+
+impl RecoveryAttempt {
+    /// Creates a recovery attempt record.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - Recovery action taken
+    /// * `target` - What the action operated on
+    /// * `succeeded` - Whether the touchpad worked after the action
+    pub fn new(action: RecoveryAction, target: String, succeeded: bool) -> Self {
+        Self { action, target, succeeded }
+    }
+}
+
+/// Result of a touchpad verification run.
+pub struct VerificationReport {
+    pub device_name: String,
+    pub device_path: String,
+    /// Stable `by-path` or `by-id` name of the device, if one could be built.
+    pub stable_name: Option<String>,
+    /// Our classification of the device.
+    pub device_type: DeviceType,
+    /// Properties udev assigns to the device, None if the capabilities can't be read.
+    pub udev: Option<UdevClass>,
+    /// Input properties the driver sets, None if they can't be read.
+    pub props: Option<InputProps>,
+    /// Firmware information of the device, for correlating failures with firmware revisions.
+    pub firmware: Option<FirmwareInfo>,
+    /// DT or ACPI description of the device, None if no firmware node describes it.
+    pub fw_node: Option<FwDescription>,
+    /// Seat udev assigned the device to, None if unknown.
+    pub seat: Option<String>,
+    /// Profile the verification ran with.
+    pub profile: Profile,
+    /// Safety policy the verification ran under.
+    pub safety: SafetyPolicy,
+    pub modules_loaded: bool,
+    /// Required modules that were not loaded.
+    pub missing_modules: Vec<String>,
+    /// Whether every step of the responsiveness check passed.
+    pub node_responsive: bool,
+    /// Outcome of each step of the responsiveness check.
+    pub responsive: ResponsiveCheck,
+    /// Grabs held on the device.
+    pub grab: GrabCheck,
+    /// Input handlers connected to the device, other than the verifier.
+    pub handlers: Vec<DeviceHandler>,
+    pub event_check: EventCheckResult,
+    /// Number of events observed during the wait window (0 in non-interactive mode).
+    pub events_observed: u32,
+    /// Length of the wait window in milliseconds (0 in non-interactive mode).
+    pub event_window_ms: u32,
+    pub palm: PalmCheck,
+    pub multitouch: MultitouchCheck,
+    /// Time spent in each phase.
+    pub timings: PhaseTimings,
+    /// Pointing stick of the machine, if the scan found one.
+    pub trackpoint: Option<TrackpointInfo>,
+    /// Special function keys of the laptop keyboard, None without an internal keyboard.
+    pub fn_keys: Option<FnKeyCheck>,
+    /// evdev event layout of the running kernel, None if the shim can't report it.
+    pub evdev_abi: Option<EvdevAbi>,
+    /// Recovery attempted after the initial verification failed, if any.
+    pub recovery: Option<RecoveryAttempt>,
+    /// Last kernel log errors and warnings mentioning the device, oldest first.
+    pub kernel_messages: Vec<String>,
+    /// Notes userspace attached to the device, if any.
+    pub note: Option<String>,
+    pub working: bool,
+}
+
+impl VerificationReport {
+    /// Creates an empty report for the given device with no phases run yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `device_name` - Name of the device being verified
+    /// * `device_path` - Path to the device node
+    pub fn new(device_name: String, device_path: String) -> Self {
+        Self {
+            device_name,
+            device_path,
+            stable_name: None,
+            device_type: DeviceType::Touchpad,
+            udev: None,
+            props: None,
+            firmware: None,
+            fw_node: None,
+            seat: None,
+            profile: Profile::Standard,
+            safety: SafetyPolicy::Repair,
+            modules_loaded: false,
+            missing_modules: Vec::new(),
+            node_responsive: false,
+            responsive: ResponsiveCheck::default(),
+            grab: GrabCheck::default(),
+            handlers: Vec::new(),
+            event_check: EventCheckResult::NotRun,
+            events_observed: 0,
+            event_window_ms: 0,
+            palm: PalmCheck::default(),
+            multitouch: MultitouchCheck::default(),
+            timings: PhaseTimings::default(),
+            trackpoint: None,
+            fn_keys: None,
+            evdev_abi: None,
+            recovery: None,
+            kernel_messages: Vec::new(),
+            note: None,
+            working: false,
+        }
+    }
+
+    /// Lists the findings of the run, failures first in phase order, then the warnings.
+    pub fn reasons(&self) -> Vec<Reason> {
+        let mut reasons = Vec::new();
+        if !self.missing_modules.is_empty() {
+            reasons.push(Reason::ModulesMissing);
+        }
+        reasons.extend(self.responsive.failed_reason());
+        match self.event_check {
+            EventCheckResult::NoEvents => reasons.push(Reason::NoEvents),
+            EventCheckResult::NotCapable => reasons.push(Reason::NotCapable),
+            EventCheckResult::CapabilityOnly => reasons.push(Reason::CapabilityOnly),
+            _ => {},
+        }
+        if self.recovery.as_ref().is_some_and(|recovery| !recovery.succeeded) {
+            reasons.push(Reason::RecoveryFailed);
+        }
+        if self.grab.held_by.is_some() {
+            reasons.push(Reason::GrabbedByOther);
+        }
+        if self.node_responsive && !handlers::any_open(&self.handlers) {
+            reasons.push(Reason::NoOpenHandler);
+        }
+        if self.props.as_ref().is_some_and(|props| !props.pointer) {
+            reasons.push(Reason::PointerPropMissing);
+        }
+        if self.udev.as_ref().is_some_and(|udev| !udev.agrees_with(self.device_type)) {
+            reasons.push(Reason::UdevDisagrees);
+        }
+        if self.multitouch.degraded() {
+            reasons.push(Reason::MultitouchDegraded);
+        }
+        if self.timings.get(Phase::Palm).is_some() && !self.palm.usable {
+            reasons.push(Reason::PalmDataUnusable);
+        }
+        if let Some(fw_node) = self.fw_node.as_ref() {
+            reasons.extend(fw_node.mismatches());
+        }
+        if let Some(evdev_abi) = self.evdev_abi.as_ref() {
+            reasons.extend(evdev_abi.mismatches.iter().copied());
+        }
+        reasons
+    }
+
+    /// Serializes the report into the flat text format described in the module documentation.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Serialized report
+    pub fn serialize(&self) -> String {
+        let mut blob = String::new();
+        let _ = writeln!(blob, "{}", SERIALIZED_HEADER);
+        let _ = writeln!(blob, "name\t{}", self.device_name);
+        let _ = writeln!(blob, "path\t{}", self.device_path);
+        if let Some(stable_name) = self.stable_name.as_ref() {
+            let _ = writeln!(blob, "stable_name\t{}", stable_name);
+        }
+        if let Some(note) = self.note.as_ref() {
+            let _ = writeln!(blob, "note\t{}", note);
+        }
+        let _ = writeln!(blob, "type\t{}", self.device_type.as_str());
+        if let Some(fw_node) = self.fw_node.as_ref() {
+            let _ = writeln!(blob, "fw_node\t{}\t{}", fw_node.kind.as_str(), fw_node.id.as_deref().unwrap_or("-"));
+            for mismatch in fw_node.mismatches() {
+                let _ = writeln!(blob, "fw_mismatch\t{}", mismatch.code());
+            }
+        }
+        if let Some(seat) = self.seat.as_ref() {
+            let _ = writeln!(blob, "seat\t{}", seat);
+        }
+        let _ = writeln!(blob, "profile\t{}", self.profile.as_str());
+        let _ = writeln!(blob, "safety\t{}", self.safety.as_str());
+        let _ = writeln!(blob, "modules_loaded\t{}", self.modules_loaded as u8);
+        for module in self.missing_modules.iter() {
+            let _ = writeln!(blob, "missing_module\t{}", module);
+        }
+        let _ = writeln!(blob, "node_responsive\t{}", self.node_responsive as u8);
+        if let Some(holder) = self.grab.held_by.as_ref() {
+            let _ = writeln!(blob, "grabbed_by\t{}", holder);
+        }
+        for handler in self.handlers.iter() {
+            let _ = writeln!(blob, "handler\t{}\t{}\t{}", handler.handler, handler.handle, handler.open as u8);
+        }
+        let _ = writeln!(blob, "event_check\t{}", self.event_check.as_str());
+        let _ = writeln!(blob, "events_observed\t{}", self.events_observed);
+        let _ = writeln!(blob, "event_window_ms\t{}", self.event_window_ms);
+        let _ = writeln!(blob, "palm_usable\t{}", self.palm.usable as u8);
+        let _ = writeln!(blob, "multitouch\t{}", self.multitouch.mode.token());
+        let _ = writeln!(blob, "multitouch_degraded\t{}", self.multitouch.degraded() as u8);
+        for phase in Phase::ALL {
+            if let Some(duration_us) = self.timings.get(phase) {
+                let _ = writeln!(blob, "timing\t{}\t{}", phase.as_str(), duration_us);
+            }
+        }
+        if let Some(fn_keys) = self.fn_keys.as_ref() {
+            for key in fn_keys.unmapped.iter() {
+                let _ = writeln!(blob, "fn_key_unmapped\t{}", key);
+            }
+            for key in fn_keys.pressed.iter().flatten() {
+                let _ = writeln!(blob, "fn_key_pressed\t{}", key);
+            }
+        }
+        if let Some(evdev_abi) = self.evdev_abi.as_ref() {
+            let _ = writeln!(blob, "evdev_abi\t{}\t{}\t{}\t{}", evdev_abi.event_size, evdev_abi.time_size,
+                             if evdev_abi.big_endian { "be" } else { "le" }, evdev_abi.compat_event_size.unwrap_or(0));
+            for mismatch in evdev_abi.mismatches.iter() {
+                let _ = writeln!(blob, "evdev_abi_mismatch\t{}", mismatch.code());
+            }
+        }
+        if let Some(recovery) = self.recovery.as_ref() {
+            let _ = writeln!(blob, "recovery\t{}\t{}\t{}", recovery.action.token(), recovery.succeeded as u8,
+                             recovery.target);
+        }
+        for line in self.kernel_messages.iter() {
+            let _ = writeln!(blob, "kernel_message\t{}", line);
+        }
+        for reason in self.reasons() {
+            let _ = writeln!(blob, "reason\t{}", reason.code());
+        }
+        let _ = writeln!(blob, "working\t{}", self.working as u8);
+        blob
+    }
+
+    /// Writes the report to the kernel log.
+    pub fn log(&self) {
+        kprint!("Verification report for {} ({})\n", self.device_name, self.device_path);
+        if let Some(stable_name) = self.stable_name.as_ref() {
+            kprint!("  stable name:     {}\n", stable_name);
+        }
+        if let Some(note) = self.note.as_ref() {
+            kprint!("  note:            {}\n", note);
+        }
+        if let Some(firmware) = self.firmware.as_ref().filter(|firmware| !firmware.is_empty()) {
+            kprint!("  firmware:        id version {:04x}, version {}, id {}\n", firmware.id_version,
+                    firmware.version.as_deref().unwrap_or("-"), firmware.id.as_deref().unwrap_or("-"));
+        }
+        if let Some(fw_node) = self.fw_node.as_ref() {
+            fw_node.log();
+        }
+        if let Some(seat) = self.seat.as_ref() {
+            kprint!("  seat:            {}\n", seat);
+        }
+        kprint!("  profile:         {}\n", self.profile.as_str());
+        kprint!("  safety policy:   {}\n", self.safety.as_str());
+        kprint!("  classification:  {}\n", self.device_type.as_str());
+        if let Some(udev) = self.udev.as_ref() {
+            let names = udev.names();
+            kprint!("    udev: {}\n", if names.is_empty() { "none".into() } else { names.join(" ") });
+            if !udev.agrees_with(self.device_type) {
+                kprint!("    {}\n", Reason::UdevDisagrees);
+            }
+        }
+        if let Some(props) = self.props.as_ref() {
+            let names = props.names();
+            kprint!("  properties:      {}\n", if names.is_empty() { "none".into() } else { names.join(" ") });
+            if !props.pointer {
+                kprint!("    {}\n", Reason::PointerPropMissing);
+            }
+        }
+        kprint!("  modules loaded:  {}\n", self.modules_loaded);
+        for module in self.missing_modules.iter() {
+            kprint!("    missing: {}\n", module);
+        }
+        kprint!("  node responsive: {}\n", self.node_responsive);
+        kprint!(
+            "    open: {}, capability query: {}, EV_SYN roundtrip: {}\n",
+            self.responsive.opened.as_str(), self.responsive.capabilities.as_str(),
+            self.responsive.syn_roundtrip.as_str()
+        );
+        if let Some(reason) = self.responsive.failed_reason() {
+            kprint!("    {}\n", reason);
+        }
+        kprint!("  handlers:        {}\n", handlers::describe(&self.handlers));
+        if !handlers::any_open(&self.handlers) {
+            kprint!("    {}\n", Reason::NoOpenHandler);
+        }
+        if let Some(holder) = self.grab.held_by.as_ref() {
+            kprint!("  grabbed by:      {}\n", holder);
+            kprint!("    {}\n", Reason::GrabbedByOther);
+        }
+        kprint!("  event check:     {}, exclusive grab: {}\n", self.event_check.as_str(), self.grab.exclusive);
+        kprint!("    events: {}, window: {} ms\n", self.events_observed, self.event_window_ms);
+        match self.event_check {
+            EventCheckResult::CapabilityOnly => kprint!("    {}\n", Reason::CapabilityOnly),
+            EventCheckResult::NoEvents => kprint!("    {}\n", Reason::NoEvents),
+            EventCheckResult::NotCapable => kprint!("    {}\n", Reason::NotCapable),
+            _ => {}
+        }
+        kprint!("  palm data usable: {}\n", self.palm.usable);
+        if self.timings.get(Phase::Palm).is_some() && !self.palm.usable {
+            kprint!("    {}\n", Reason::PalmDataUnusable);
+        }
+        kprint!(
+            "    touch major: {}, touch minor: {}, tool type: {}, pressure: {}\n",
+            self.palm.has_touch_major, self.palm.has_touch_minor,
+            self.palm.has_tool_type, self.palm.has_pressure
+        );
+        if self.palm.observed {
+            kprint!(
+                "    max touch major: {}, large contacts: {}, palm reports: {}\n",
+                self.palm.max_touch_major, self.palm.large_contacts, self.palm.palm_reports
+            );
+        }
+        kprint!("  multitouch:      {}\n", self.multitouch.mode.text());
+        if let Some(hint) = self.multitouch.degraded_hint {
+            kprint!("    {}: {}\n", Reason::MultitouchDegraded, hint);
+        }
+        if let Some(trackpoint) = self.trackpoint.as_ref() {
+            trackpoint.log();
+        }
+        if let Some(fn_keys) = self.fn_keys.as_ref() {
+            fn_keys.log();
+        }
+        if let Some(evdev_abi) = self.evdev_abi.as_ref() {
+            evdev_abi.log();
+        }
+        if let Some(recovery) = self.recovery.as_ref() {
+            kprint!("  recovery:        {} ({}), succeeded: {}\n", recovery.action.text(), recovery.target,
+                    recovery.succeeded);
+            if !recovery.succeeded {
+                kprint!("    {}\n", Reason::RecoveryFailed);
+            }
+        }
+        if self.timings.total_us() > 0 {
+            let mut timings = String::new();
+            for phase in Phase::ALL {
+                if let Some(duration_us) = self.timings.get(phase) {
+                    let _ = write!(timings, "{}{} {} us", if timings.is_empty() { "" } else { ", " },
+                                   phase.as_str(), duration_us);
+                }
+            }
+            kprint!("  phase timing:    {} (total {} us)\n", timings, self.timings.total_us());
+        }
+        if !self.kernel_messages.is_empty() {
+            kprint!("  kernel messages: last {} errors and warnings about the device\n", self.kernel_messages.len());
+            for line in self.kernel_messages.iter() {
+                kprint!("    {}\n", line);
+            }
+        }
+        let reasons = self.reasons();
+        if !reasons.is_empty() {
+            let codes: Vec<String> = reasons.iter().map(|reason| alloc::format!("{}", reason.code())).collect();
+            kprint!("  reasons:         {}\n", codes.join(" "));
+        }
+        kprint!("  result:          {}\n", match (self.working, self.multitouch.degraded()) {
+            (true, true) => "working, degraded mode",
+            (true, false) => "working",
+            (false, _) => "not working",
+        });
+    }
+}
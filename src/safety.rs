@@ -0,0 +1,92 @@
+//! Safety policy limiting what the verifier may change.
+//!
+//! Most of the verifier only observes, but some options act on the system:
+//! an exclusive grab takes the touchpad away from userspace, a replay
+//! registers a virtual input device, and the recovery actions rebind drivers,
+//! load modules and reset hardware. Fleets that deploy the module for
+//! monitoring only need a guarantee that none of this happens whatever else is
+//! configured, so every such action is checked against one policy:
+//!
+//! - `read-only` never changes device or input state
+//! - `diagnose` also allows intrusive diagnostics (EV_SYN probe, exclusive
+//!   grab, replay)
+//! - `repair` also allows the recovery actions
+//!
+//! The policy is set at load time or through the privileged
+//! `DV_IOC_SET_SAFETY` ioctl.
+
+/// A safety policy. The numeric values identify policies over FFI and must
+/// match `DV_SAFETY_*` in driver_verifier_ioctl.h.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum SafetyPolicy {
+    ReadOnly = 0,
+    Diagnose = 1,
+    Repair = 2,
+}
+
+impl SafetyPolicy {
+    /// Converts a raw policy id received over FFI.
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(SafetyPolicy::ReadOnly),
+            1 => Some(SafetyPolicy::Diagnose),
+            2 => Some(SafetyPolicy::Repair),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the policy.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SafetyPolicy::ReadOnly => "read-only",
+            SafetyPolicy::Diagnose => "diagnose",
+            SafetyPolicy::Repair => "repair",
+        }
+    }
+
+    /// Returns true if the policy allows the action.
+    pub fn permits(&self, action: GuardedAction) -> bool {
+        *self >= action.required()
+    }
+}
+
+/// An action that changes device or input state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardedAction {
+    /// Injecting the empty EV_SYN frame of the responsiveness check, which
+    /// every client of the touchpad receives.
+    SynProbe,
+    /// Grabbing the touchpad during the event check.
+    ExclusiveGrab,
+    /// Replaying recorded events through a virtual touchpad.
+    Replay,
+    /// Loading missing modules.
+    LoadModule,
+    /// Rebinding the touchpad driver.
+    Rebind,
+    /// Pulsing the reset GPIO of the touchpad.
+    ResetGpio,
+}
+
+impl GuardedAction {
+    /// Returns a short human readable name for the action.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GuardedAction::SynProbe => "EV_SYN probe",
+            GuardedAction::ExclusiveGrab => "exclusive grab",
+            GuardedAction::Replay => "event replay",
+            GuardedAction::LoadModule => "module load",
+            GuardedAction::Rebind => "driver rebind",
+            GuardedAction::ResetGpio => "reset GPIO pulse",
+        }
+    }
+
+    /// Returns the least permissive policy allowing the action.
+    fn required(&self) -> SafetyPolicy {
+        match self {
+            GuardedAction::SynProbe | GuardedAction::ExclusiveGrab | GuardedAction::Replay => SafetyPolicy::Diagnose,
+            GuardedAction::LoadModule | GuardedAction::Rebind | GuardedAction::ResetGpio => SafetyPolicy::Repair,
+        }
+    }
+}
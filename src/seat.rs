@@ -0,0 +1,61 @@
+//! Seat assignment of input devices.
+//!
+//! On multi-seat machines logind assigns every input device to a seat, so
+//! one box drives several sets of screens, keyboards and touchpads. The
+//! assignment lives in the `ID_SEAT` property udev stores in its database
+//! under /run/udev/data, keyed by the device number of the event node; a
+//! device without the property belongs to `seat0`. Reading the database
+//! needs no userspace help, but it only exists once udev ran, so the seat of
+//! a device scanned earlier is unknown.
+//!
+//! When the verifier is scoped to a seat, the scan skips the devices of every
+//! other seat, so "seat1's keyboard" is never confused with seat0 hardware.
+//! Devices with an unknown seat count as `seat0`, like logind treats them.
+use alloc::string::String;
+
+/// Seat of devices that udev didn't assign to one.
+pub const DEFAULT_SEAT: &str = "seat0";
+
+/// Directory of the udev database.
+const UDEV_DATA: &str = "/run/udev/data";
+
+/// Most bytes of a database entry read, enough for the properties of an input device.
+pub const UDEV_DB_MAX: usize = 4096;
+
+/// Returns the udev database file of a character device.
+///
+/// # Arguments
+///
+/// * `dev` - Contents of the node's sysfs `dev` attribute, e.g. "13:69"
+pub fn udev_db_path(dev: &str) -> String {
+    alloc::format!("{}/c{}", UDEV_DATA, dev.trim())
+}
+
+/// Returns the seat recorded in a udev database entry.
+///
+/// # Arguments
+///
+/// * `db` - Contents of the database file, one `<type>:<data>` record per line
+pub fn from_udev_db(db: &str) -> String {
+    db.lines()
+        .find_map(|line| line.strip_prefix("E:ID_SEAT="))
+        .filter(|seat| is_valid(seat))
+        .unwrap_or(DEFAULT_SEAT)
+        .into()
+}
+
+/// Checks a seat name the way logind does: "seat" followed by letters, digits, `-` or `_`.
+pub fn is_valid(seat: &str) -> bool {
+    seat.strip_prefix("seat")
+        .is_some_and(|rest| rest.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'))
+}
+
+/// Checks if a device belongs to a seat.
+///
+/// # Arguments
+///
+/// * `device_seat` - Seat of the device, None if unknown
+/// * `seat` - Seat the verifier is scoped to
+pub fn belongs_to(device_seat: Option<&str>, seat: &str) -> bool {
+    device_seat.unwrap_or(DEFAULT_SEAT) == seat
+}
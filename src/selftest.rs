@@ -0,0 +1,284 @@
+//! Interactive guided self test for the touchpad.
+//!
+//! The self test walks the user through a fixed sequence of gestures. For each
+//! step a prompt is shown through the debugfs `selftest` file, and the event
+//! monitor is checked for the event pattern the gesture should produce. The
+//! state machine is advanced whenever its status is read, so no kernel thread
+//! is needed to drive it.
+use alloc::string::String;
+use core::fmt::Write;
+
+use crate::event_monitor::{now_ms, PatternCounts, EVENT_MONITOR, SELFTEST_SLOT};
+use crate::kprint;
+
+/// Gestures exercised by the self test, in the order they are prompted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    MoveFinger,
+    Tap,
+    TwoFingerScroll,
+    TwoFingerTap,
+    Click,
+}
+
+impl Gesture {
+    /// All gestures in prompt order.
+    pub const ALL: [Gesture; 5] = [
+        Gesture::MoveFinger,
+        Gesture::Tap,
+        Gesture::TwoFingerScroll,
+        Gesture::TwoFingerTap,
+        Gesture::Click,
+    ];
+
+    /// Returns the short name used in the status output.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Gesture::MoveFinger => "move-finger",
+            Gesture::Tap => "tap",
+            Gesture::TwoFingerScroll => "two-finger-scroll",
+            Gesture::TwoFingerTap => "two-finger-tap",
+            Gesture::Click => "click",
+        }
+    }
+
+    /// Returns the instruction shown to the user for this gesture.
+    ///
+    /// # Arguments
+    ///
+    /// * `buttonpad` - Whether the touchpad is a clickpad without separate buttons
+    pub fn prompt(&self, buttonpad: bool) -> &'static str {
+        match self {
+            Gesture::MoveFinger => "Move one finger across the touchpad",
+            Gesture::Tap => "Tap the touchpad once with one finger",
+            Gesture::TwoFingerScroll => "Scroll with two fingers on the touchpad",
+            Gesture::TwoFingerTap => "Tap the touchpad once with two fingers",
+            Gesture::Click if buttonpad => "Press the touchpad down until it clicks",
+            Gesture::Click => "Click the left touchpad button",
+        }
+    }
+
+    /// Checks if the event pattern for this gesture arrived between two snapshots.
+    ///
+    /// # Arguments
+    ///
+    /// * `before` - Counters captured when the prompt was shown
+    /// * `now` - Current counters
+    fn detected(&self, before: &PatternCounts, now: &PatternCounts) -> bool {
+        match self {
+            Gesture::MoveFinger => now.motion.wrapping_sub(before.motion) >= MIN_MOTION_EVENTS,
+            Gesture::Tap => now.taps != before.taps,
+            Gesture::TwoFingerScroll => {
+                now.two_finger_motion.wrapping_sub(before.two_finger_motion) >= MIN_TWO_FINGER_FRAMES
+            },
+            Gesture::TwoFingerTap => now.two_finger_taps != before.two_finger_taps,
+            Gesture::Click => now.clicks != before.clicks,
+        }
+    }
+}
+
+/// Minimum number of position updates that count as a deliberate movement.
+const MIN_MOTION_EVENTS: u32 = 10;
+
+/// Minimum number of two-contact frames with motion that count as a scroll.
+const MIN_TWO_FINGER_FRAMES: u32 = 5;
+
+/// Result of a single self test step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GestureResult {
+    NotRun,
+    Passed,
+    TimedOut,
+    Skipped,
+}
+
+impl GestureResult {
+    /// Returns a short human readable name for the result.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GestureResult::NotRun => "not run",
+            GestureResult::Passed => "passed",
+            GestureResult::TimedOut => "timed out",
+            GestureResult::Skipped => "skipped",
+        }
+    }
+}
+
+/// State of the self test state machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelftestState {
+    /// No self test has been started.
+    Idle,
+    /// Waiting for the gesture at `step` since `started_ms`.
+    Running {
+        step: usize,
+        started_ms: u64,
+        baseline: PatternCounts,
+    },
+    /// All steps have a result.
+    Finished,
+    /// The self test was aborted before all steps ran.
+    Aborted,
+}
+
+/// Commands accepted by the self test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelftestCommand {
+    Start,
+    Skip,
+    Abort,
+}
+
+impl SelftestCommand {
+    /// Converts a raw command number received over FFI.
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            1 => Some(SelftestCommand::Start),
+            2 => Some(SelftestCommand::Skip),
+            3 => Some(SelftestCommand::Abort),
+            _ => None,
+        }
+    }
+}
+
+/// Guided self test state machine.
+pub struct Selftest {
+    state: SelftestState,
+    results: [GestureResult; Gesture::ALL.len()],
+    step_timeout_ms: u32,
+    /// Whether the touchpad under test is a clickpad (INPUT_PROP_BUTTONPAD).
+    buttonpad: bool,
+}
+
+impl Selftest {
+    /// Creates an idle self test.
+    pub fn new() -> Self {
+        Self {
+            state: SelftestState::Idle,
+            results: [GestureResult::NotRun; Gesture::ALL.len()],
+            step_timeout_ms: 0,
+            buttonpad: false,
+        }
+    }
+
+    /// Starts the self test on the given touchpad.
+    ///
+    /// Any previous results are discarded and the first prompt is shown.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the touchpad device node
+    /// * `step_timeout_ms` - How long to wait for each gesture
+    /// * `large_touch_threshold` - Touch major value from which a contact is flagged as a palm
+    /// * `buttonpad` - Whether the touchpad is a clickpad, which changes the click prompt
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ()>` - Ok if the touchpad could be monitored, Err otherwise
+    pub fn start(&mut self, path: &str, step_timeout_ms: u32, large_touch_threshold: i32,
+                 buttonpad: bool) -> Result<(), ()> {
+        if matches!(self.state, SelftestState::Running { .. }) {
+            EVENT_MONITOR.detach(SELFTEST_SLOT);
+        }
+
+        EVENT_MONITOR.attach(SELFTEST_SLOT, path)?;
+        EVENT_MONITOR.set_large_touch_threshold(SELFTEST_SLOT, large_touch_threshold);
+        kprint!("Starting touchpad self test on {}\n", path);
+
+        self.results = [GestureResult::NotRun; Gesture::ALL.len()];
+        self.step_timeout_ms = step_timeout_ms;
+        self.buttonpad = buttonpad;
+        self.enter_step(0);
+        Ok(())
+    }
+
+    /// Skips the current step and moves on to the next one.
+    pub fn skip(&mut self) {
+        if let SelftestState::Running { step, .. } = self.state {
+            self.complete_step(step, GestureResult::Skipped);
+        }
+    }
+
+    /// Aborts a running self test, keeping the results collected so far.
+    pub fn abort(&mut self) {
+        if matches!(self.state, SelftestState::Running { .. }) {
+            EVENT_MONITOR.detach(SELFTEST_SLOT);
+            self.state = SelftestState::Aborted;
+            kprint!("Touchpad self test aborted\n");
+        }
+    }
+
+    /// Advances the state machine based on the events seen so far.
+    ///
+    /// Completes the current step when its gesture was detected or its timeout
+    /// elapsed; does nothing when no self test is running.
+    pub fn poll(&mut self) {
+        let SelftestState::Running { step, started_ms, baseline } = self.state else {
+            return;
+        };
+
+        let gesture = Gesture::ALL[step];
+        let counts = EVENT_MONITOR.pattern_counts(SELFTEST_SLOT);
+
+        if gesture.detected(&baseline, &counts) {
+            self.complete_step(step, GestureResult::Passed);
+        } else if now_ms().saturating_sub(started_ms) >= self.step_timeout_ms as u64 {
+            self.complete_step(step, GestureResult::TimedOut);
+        }
+    }
+
+    /// Renders the current prompt and per-gesture results as text.
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - String to append the status to
+    pub fn render(&self, out: &mut String) {
+        let _ = match self.state {
+            SelftestState::Idle => writeln!(out, "state: idle"),
+            SelftestState::Running { step, .. } => {
+                let _ = writeln!(out, "state: running (step {}/{})", step + 1, Gesture::ALL.len());
+                writeln!(out, "prompt: {}", Gesture::ALL[step].prompt(self.buttonpad))
+            },
+            SelftestState::Finished => writeln!(out, "state: finished"),
+            SelftestState::Aborted => writeln!(out, "state: aborted"),
+        };
+
+        for (gesture, result) in Gesture::ALL.iter().zip(self.results.iter()) {
+            let _ = writeln!(out, "{}: {}", gesture.name(), result.as_str());
+        }
+
+        if self.state != SelftestState::Idle {
+            let counts = EVENT_MONITOR.pattern_counts(SELFTEST_SLOT);
+            let _ = writeln!(out, "max contacts: {}", counts.max_contacts);
+            let _ = writeln!(out, "max touch major: {}", counts.max_touch_major);
+            let _ = writeln!(out, "large contacts: {}", counts.large_contacts);
+            let _ = writeln!(out, "palm reports: {}", counts.palm_reports);
+        }
+    }
+
+    /// Starts waiting for the gesture at `step` and prompts the user.
+    fn enter_step(&mut self, step: usize) {
+        let gesture = Gesture::ALL[step];
+        kprint!("Self test step {}/{}: {}\n", step + 1, Gesture::ALL.len(), gesture.prompt(self.buttonpad));
+
+        self.state = SelftestState::Running {
+            step,
+            started_ms: now_ms(),
+            baseline: EVENT_MONITOR.pattern_counts(SELFTEST_SLOT),
+        };
+    }
+
+    /// Records the result of `step` and moves on to the next step or finishes.
+    fn complete_step(&mut self, step: usize, result: GestureResult) {
+        self.results[step] = result;
+        kprint!("Self test {}: {}\n", Gesture::ALL[step].name(), result.as_str());
+
+        if step + 1 < Gesture::ALL.len() {
+            self.enter_step(step + 1);
+        } else {
+            EVENT_MONITOR.detach(SELFTEST_SLOT);
+            self.state = SelftestState::Finished;
+            kprint!("Touchpad self test finished\n");
+        }
+    }
+}
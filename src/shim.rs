@@ -0,0 +1,212 @@
+//! Table of the kernel operations the C shim provides.
+//!
+//! Rust doesn't link against global `kernel_*` symbols: C passes a
+//! `KernelOps` table of function pointers to `rust_init`, which copies it
+//! once, before any other call into Rust. This avoids symbol clashes with the
+//! kernel and link-order issues, and a test backend only has to fill a table.
+//!
+//! The table starts with its size as built by C, so an older and shorter
+//! table is accepted: the operations it lacks read as None. Optional
+//! operations may also be left NULL, e.g. when the kernel is built without
+//! tracepoints or printk, and the features needing them are disabled; callers
+//! fail cleanly instead of calling into C. The required operations (printing,
+//! file reads, time, node checks and the event monitor) must be present.
+//!
+//! The table is global, like the verifier, since the event path and `kprint!`
+//! use it outside of the verifier.
+use core::mem::size_of;
+
+use crate::dir::DirEntry;
+use crate::evdev_abi::EvdevLayout;
+use crate::fwnode::FwResources;
+use crate::handlers::HandlerEntry;
+use crate::input_core::InputDeviceEntry;
+
+/// Kernel operations. Mirrors `struct dv_kernel_ops`, new operations are only ever
+/// appended.
+#[repr(C)]
+pub struct KernelOps {
+    /// Size of the table as built by C.
+    pub size: usize,
+
+    // Required operations
+    pub print: Option<unsafe extern "C" fn(msg: *const u8, len: usize)>,
+    pub read_file: Option<
+        unsafe extern "C" fn(path: *const u8, path_len: usize, buffer: *mut u8, buffer_size: usize,
+                             bytes_read: *mut usize) -> i32,
+    >,
+    pub get_time_ms: Option<unsafe extern "C" fn() -> u64>,
+    pub get_time_us: Option<unsafe extern "C" fn() -> u64>,
+    pub msleep: Option<unsafe extern "C" fn(ms: u32)>,
+    pub check_node: Option<unsafe extern "C" fn(path: *const u8, path_len: usize, inject: bool) -> i32>,
+    pub monitor_attach: Option<unsafe extern "C" fn(node: *const u8, node_len: usize, slot: u32) -> i32>,
+    pub monitor_detach: Option<unsafe extern "C" fn(slot: u32)>,
+
+    // Optional operations
+    pub get_device_capabilities: Option<
+        unsafe extern "C" fn(path: *const u8, path_len: usize, abs_support: *mut u64, rel_support: *mut u32,
+                             key_support: *mut u32) -> i32,
+    >,
+    pub get_abs_info: Option<
+        unsafe extern "C" fn(path: *const u8, path_len: usize, code: u32, minimum: *mut i32, maximum: *mut i32,
+                             resolution: *mut i32) -> i32,
+    >,
+    pub read_directory: Option<
+        unsafe extern "C" fn(path: *const u8, path_len: usize, entries: *mut DirEntry, capacity: usize,
+                             count: *mut usize) -> i32,
+    >,
+    pub get_handlers: Option<
+        unsafe extern "C" fn(path: *const u8, path_len: usize, entries: *mut HandlerEntry, capacity: usize,
+                             count: *mut usize) -> i32,
+    >,
+    pub get_grab: Option<
+        unsafe extern "C" fn(path: *const u8, path_len: usize, buffer: *mut u8, buffer_size: usize,
+                             holder_len: *mut usize) -> i32,
+    >,
+    pub grab_node: Option<unsafe extern "C" fn(path: *const u8, path_len: usize, grab: i32) -> i32>,
+    pub kmsg_line: Option<
+        unsafe extern "C" fn(seq: *mut u64, buffer: *mut u8, buffer_size: usize, line_len: *mut usize) -> i32,
+    >,
+    pub replay_create: Option<unsafe extern "C" fn(path: *const u8, path_len: usize) -> i32>,
+    pub replay_event: Option<unsafe extern "C" fn(ev_type: u32, code: u32, value: i32)>,
+    pub replay_destroy: Option<unsafe extern "C" fn()>,
+    pub trace_scan_start: Option<unsafe extern "C" fn()>,
+    pub trace_scan_end: Option<unsafe extern "C" fn(devices: u32, result: i32)>,
+    pub trace_phase: Option<unsafe extern "C" fn(path: *const u8, path_len: usize, phase: u32, outcome: i32)>,
+    pub trace_classify: Option<
+        unsafe extern "C" fn(path: *const u8, path_len: usize, device_type: u32, method: u32),
+    >,
+    pub notify: Option<unsafe extern "C" fn(event: u32, subject: *const u8, subject_len: usize, value: i32) -> i32>,
+    pub request_module: Option<unsafe extern "C" fn(name: *const u8, name_len: usize) -> i32>,
+    pub resolve_path: Option<
+        unsafe extern "C" fn(path: *const u8, path_len: usize, buffer: *mut u8, buffer_size: usize,
+                             resolved_len: *mut usize) -> i32,
+    >,
+    pub write_file: Option<
+        unsafe extern "C" fn(path: *const u8, path_len: usize, buffer: *const u8, buffer_len: usize) -> i32,
+    >,
+    pub get_hypervisor: Option<unsafe extern "C" fn(buffer: *mut u8, buffer_size: usize, name_len: *mut usize) -> i32>,
+    pub get_fw_resources: Option<unsafe extern "C" fn(path: *const u8, path_len: usize, res: *mut FwResources) -> i32>,
+    pub reset_fw_device: Option<unsafe extern "C" fn(path: *const u8, path_len: usize, assert_ms: u32) -> i32>,
+    pub get_evdev_layout: Option<unsafe extern "C" fn(layout: *mut EvdevLayout)>,
+    pub next_input_device: Option<unsafe extern "C" fn(cursor: *mut u32, entry: *mut InputDeviceEntry) -> i32>,
+    pub notify_syn_dropped: Option<unsafe extern "C" fn(slot: u32)>,
+}
+
+impl KernelOps {
+    /// A table without any operation.
+    const EMPTY: Self = Self {
+        size: 0,
+        print: None,
+        read_file: None,
+        get_time_ms: None,
+        get_time_us: None,
+        msleep: None,
+        check_node: None,
+        monitor_attach: None,
+        monitor_detach: None,
+        get_device_capabilities: None,
+        get_abs_info: None,
+        read_directory: None,
+        get_handlers: None,
+        get_grab: None,
+        grab_node: None,
+        kmsg_line: None,
+        replay_create: None,
+        replay_event: None,
+        replay_destroy: None,
+        trace_scan_start: None,
+        trace_scan_end: None,
+        trace_phase: None,
+        trace_classify: None,
+        notify: None,
+        request_module: None,
+        resolve_path: None,
+        write_file: None,
+        get_hypervisor: None,
+        get_fw_resources: None,
+        reset_fw_device: None,
+        get_evdev_layout: None,
+        next_input_device: None,
+        notify_syn_dropped: None,
+    };
+
+    /// Returns true if every required operation is present.
+    fn has_required(&self) -> bool {
+        self.print.is_some()
+            && self.read_file.is_some()
+            && self.get_time_ms.is_some()
+            && self.get_time_us.is_some()
+            && self.msleep.is_some()
+            && self.check_node.is_some()
+            && self.monitor_attach.is_some()
+            && self.monitor_detach.is_some()
+    }
+
+    /// Returns the optional features and whether the table provides what they need.
+    fn features(&self) -> [(&'static str, bool); 16] {
+        [
+            ("capabilities", self.get_device_capabilities.is_some() && self.get_abs_info.is_some()),
+            ("directory listing", self.read_directory.is_some()),
+            ("input handlers", self.get_handlers.is_some()),
+            ("grabs", self.get_grab.is_some() && self.grab_node.is_some()),
+            ("kernel log", self.kmsg_line.is_some()),
+            ("replay", self.replay_create.is_some() && self.replay_event.is_some() && self.replay_destroy.is_some()),
+            ("tracepoints", self.trace_scan_start.is_some() && self.trace_scan_end.is_some()
+                && self.trace_phase.is_some() && self.trace_classify.is_some()),
+            ("netlink notifications", self.notify.is_some()),
+            ("module loading", self.request_module.is_some()),
+            ("sysfs writes", self.resolve_path.is_some() && self.write_file.is_some()),
+            ("hypervisor detection", self.get_hypervisor.is_some()),
+            ("firmware descriptions", self.get_fw_resources.is_some()),
+            ("reset GPIO recovery", self.reset_fw_device.is_some()),
+            ("evdev layout", self.get_evdev_layout.is_some()),
+            ("input core enumeration", self.next_input_device.is_some()),
+            ("SYN_DROPPED notifications", self.notify_syn_dropped.is_some()),
+        ]
+    }
+}
+
+/// The installed table. Written once by `install` before C calls anything else.
+static mut OPS: KernelOps = KernelOps::EMPTY;
+
+/// Installs the table passed by C and logs the features it lacks.
+///
+/// # Arguments
+///
+/// * `table` - Table built by C, valid for the duration of the call
+///
+/// # Safety
+///
+/// `table` must be NULL or point to a table of at least the size it states.
+/// Must be called before any other operation is used, with no concurrent caller.
+///
+/// # Returns
+///
+/// * `Result<(), ()>` - Ok if the table holds every required operation, Err otherwise
+pub unsafe fn install(table: *const KernelOps) -> Result<(), ()> {
+    let Some(size) = table.as_ref().map(|table| table.size) else {
+        return Err(());
+    };
+
+    // Operations beyond the end of a shorter table stay None; a size that isn't
+    // a whole number of pointers would leave one half copied
+    let mut ops = KernelOps::EMPTY;
+    let copied = size.min(size_of::<KernelOps>()) / size_of::<usize>() * size_of::<usize>();
+    core::ptr::copy_nonoverlapping(table as *const u8, &mut ops as *mut KernelOps as *mut u8, copied);
+    ops.size = copied;
+    if !ops.has_required() {
+        return Err(());
+    }
+
+    *core::ptr::addr_of_mut!(OPS) = ops;
+    for (name, _) in self::ops().features().iter().filter(|(_, present)| !present) {
+        crate::kprint!("C shim lacks {}, disabling the features that need it\n", name);
+    }
+    Ok(())
+}
+
+/// Returns the installed table, empty before `install`.
+pub fn ops() -> &'static KernelOps {
+    unsafe { &*core::ptr::addr_of!(OPS) }
+}
@@ -0,0 +1,345 @@
+//! Snapshots of the device registry and verification results.
+//!
+//! A snapshot taken before a driver or kernel update can be compared with the
+//! state after the update to flag devices that disappeared, changed drivers or
+//! regressed from working to not working. Snapshots are serialized to a small
+//! line based text blob so userspace can keep them across reboots:
+//!
+//! ```text
+//! dv-snapshot 1
+//! device<TAB><type><TAB><driver><TAB><path><TAB><name>
+//! report<TAB><working><TAB><path><TAB><name>
+//! ```
+//!
+//! Devices are matched by name, since node numbers are not stable across boots.
+//! The path field holds the stable `by-path` or `by-id` name when one is known
+//! and the event node otherwise.
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::input_verifier::{DeviceInfo, DeviceType};
+use crate::reason::Reason;
+use crate::report::VerificationReport;
+
+/// First line of every serialized snapshot, including the format version.
+const HEADER: &str = "dv-snapshot 1";
+
+/// Placeholder for a device without a bound driver.
+const NO_DRIVER: &str = "-";
+
+/// A device as recorded in a snapshot.
+pub struct DeviceEntry {
+    pub name: String,
+    pub path: String,
+    pub device_type: DeviceType,
+    /// Driver bound to the physical device, empty if none.
+    pub driver: String,
+}
+
+/// A verification result as recorded in a snapshot.
+pub struct ReportEntry {
+    pub name: String,
+    pub path: String,
+    pub working: bool,
+}
+
+/// Device registry and verification results at one point in time.
+pub struct Snapshot {
+    pub devices: Vec<DeviceEntry>,
+    pub reports: Vec<ReportEntry>,
+}
+
+/// A difference between an older and a newer snapshot.
+pub enum Difference {
+    /// A device of the old snapshot is no longer present.
+    Disappeared { name: String },
+    /// A device is bound to a different driver.
+    DriverChanged { name: String, old: String, new: String },
+    /// A device that was working is now not working or no longer verified.
+    Regressed { name: String },
+}
+
+impl Snapshot {
+    /// Captures the device registry and verification results.
+    ///
+    /// # Arguments
+    ///
+    /// * `devices` - Devices found by the last scan; stale entries are left out
+    /// * `drivers` - Driver bound to each device, in the same order as `devices`
+    /// * `reports` - Reports of the last verification runs
+    pub fn capture<'a>(
+        devices: &[DeviceInfo],
+        drivers: Vec<String>,
+        reports: impl Iterator<Item = &'a VerificationReport>,
+    ) -> Self {
+        let devices = devices.iter().zip(drivers)
+            .filter(|(dev, _)| !dev.stale)
+            .map(|(dev, driver)| DeviceEntry {
+                name: dev.name.clone(),
+                path: dev.stable_name().unwrap_or(&dev.path).to_string(),
+                device_type: dev.device_type,
+                driver,
+            })
+            .collect();
+
+        let reports = reports
+            .map(|report| ReportEntry {
+                name: report.device_name.clone(),
+                path: report.stable_name.clone().unwrap_or_else(|| report.device_path.clone()),
+                working: report.working,
+            })
+            .collect();
+
+        Self { devices, reports }
+    }
+
+    /// Serializes the snapshot into its text form.
+    pub fn serialize(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "{}", HEADER);
+
+        for dev in self.devices.iter() {
+            let driver = if dev.driver.is_empty() { NO_DRIVER } else { dev.driver.as_str() };
+            let _ = writeln!(out, "device\t{}\t{}\t{}\t{}", dev.device_type.as_str(),
+                             sanitize(driver), sanitize(&dev.path), sanitize(&dev.name));
+        }
+        for report in self.reports.iter() {
+            let _ = writeln!(out, "report\t{}\t{}\t{}", report.working as u8,
+                             sanitize(&report.path), sanitize(&report.name));
+        }
+
+        out
+    }
+
+    /// Parses a serialized snapshot.
+    ///
+    /// # Arguments
+    ///
+    /// * `blob` - Text form produced by `serialize`
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, ()>` - Parsed snapshot, or Err if the header or a line is malformed
+    pub fn parse(blob: &str) -> Result<Self, ()> {
+        let mut lines = blob.lines();
+        if lines.next().map(str::trim_end) != Some(HEADER) {
+            return Err(());
+        }
+
+        let mut snapshot = Self { devices: Vec::new(), reports: Vec::new() };
+        for line in lines.filter(|line| !line.is_empty()) {
+            let mut fields = line.splitn(5, '\t');
+            match fields.next() {
+                Some("device") => {
+                    let device_type = DeviceType::from_name(fields.next().ok_or(())?).ok_or(())?;
+                    let driver = fields.next().ok_or(())?;
+                    let path = fields.next().ok_or(())?;
+                    let name = fields.next().ok_or(())?;
+                    snapshot.devices.push(DeviceEntry {
+                        name: name.to_string(),
+                        path: path.to_string(),
+                        device_type,
+                        driver: if driver == NO_DRIVER { String::new() } else { driver.to_string() },
+                    });
+                },
+                Some("report") => {
+                    let working = match fields.next() {
+                        Some("1") => true,
+                        Some("0") => false,
+                        _ => return Err(()),
+                    };
+                    let path = fields.next().ok_or(())?;
+                    let name = fields.next().ok_or(())?;
+                    snapshot.reports.push(ReportEntry {
+                        name: name.to_string(),
+                        path: path.to_string(),
+                        working,
+                    });
+                },
+                _ => return Err(()),
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Compares this (older) snapshot with a newer one.
+    ///
+    /// Devices with the same name are matched one to one, so several devices
+    /// sharing a name are compared in order.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - Newer snapshot
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Difference>` - Differences found, empty if nothing regressed
+    pub fn compare(&self, current: &Snapshot) -> Vec<Difference> {
+        let mut differences = Vec::new();
+        let mut matched = alloc::vec![false; current.devices.len()];
+
+        for old in self.devices.iter() {
+            let found = current.devices.iter().enumerate()
+                .find(|(i, dev)| !matched[*i] && dev.name == old.name);
+
+            match found {
+                Some((i, dev)) => {
+                    matched[i] = true;
+                    if dev.driver != old.driver {
+                        differences.push(Difference::DriverChanged {
+                            name: old.name.clone(),
+                            old: old.driver.clone(),
+                            new: dev.driver.clone(),
+                        });
+                    }
+                },
+                None => differences.push(Difference::Disappeared { name: old.name.clone() }),
+            }
+        }
+
+        for old in self.reports.iter().filter(|report| report.working) {
+            let still_working = current.reports.iter()
+                .any(|report| report.name == old.name && report.working);
+            if !still_working {
+                differences.push(Difference::Regressed { name: old.name.clone() });
+            }
+        }
+
+        differences
+    }
+}
+
+impl Difference {
+    /// Returns the reason code of the difference.
+    pub fn reason(&self) -> Reason {
+        match self {
+            Difference::Disappeared { .. } => Reason::Disappeared,
+            Difference::DriverChanged { .. } => Reason::DriverChanged,
+            Difference::Regressed { .. } => Reason::Regressed,
+        }
+    }
+
+    /// Writes the difference as one line: the reason code and the device name,
+    /// followed by the old and the new driver for a driver change, tab separated.
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - String to append the line to
+    pub fn render(&self, out: &mut String) {
+        let _ = match self {
+            Difference::Disappeared { name } | Difference::Regressed { name } => {
+                writeln!(out, "{}\t{}", self.reason().code(), sanitize(name))
+            },
+            Difference::DriverChanged { name, old, new } => writeln!(
+                out, "{}\t{}\t{}\t{}", self.reason().code(), sanitize(name),
+                if old.is_empty() { NO_DRIVER } else { old.as_str() },
+                if new.is_empty() { NO_DRIVER } else { new.as_str() }
+            ),
+        };
+    }
+}
+
+/// Replaces the field and line separators in a serialized value.
+fn sanitize(value: &str) -> String {
+    value.replace(['\t', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(name: &str, driver: &str) -> DeviceEntry {
+        DeviceEntry {
+            name: name.to_string(),
+            path: "platform-i8042-serio-1-event-mouse".to_string(),
+            device_type: DeviceType::Touchpad,
+            driver: driver.to_string(),
+        }
+    }
+
+    fn report(name: &str, working: bool) -> ReportEntry {
+        ReportEntry { name: name.to_string(), path: "/dev/input/event5".to_string(), working }
+    }
+
+    fn rendered(differences: &[Difference]) -> String {
+        let mut out = String::new();
+        for difference in differences {
+            difference.render(&mut out);
+        }
+        out
+    }
+
+    #[test]
+    fn parse_reads_what_serialize_writes() {
+        let snapshot = Snapshot {
+            devices: alloc::vec![device("SynPS/2 Synaptics\tTouchPad", "psmouse"), device("ELAN Touchpad", "")],
+            reports: alloc::vec![report("SynPS/2 Synaptics TouchPad", true), report("ELAN Touchpad", false)],
+        };
+        let blob = snapshot.serialize();
+        let parsed = Snapshot::parse(&blob).unwrap();
+
+        assert_eq!(parsed.devices.len(), 2);
+        assert_eq!(parsed.devices[0].name, "SynPS/2 Synaptics TouchPad");
+        assert_eq!(parsed.devices[0].driver, "psmouse");
+        assert_eq!(parsed.devices[0].device_type, DeviceType::Touchpad);
+        assert_eq!(parsed.devices[1].driver, "");
+        assert_eq!(parsed.reports.len(), 2);
+        assert!(parsed.reports[0].working && !parsed.reports[1].working);
+        assert_eq!(parsed.serialize(), blob);
+    }
+
+    #[test]
+    fn parse_accepts_crlf_and_blank_lines() {
+        let parsed = Snapshot::parse("dv-snapshot 1\r\n\ndevice\tTouchpad\t-\tevent5\tname with\ttab\r\n").unwrap();
+        assert!(parsed.reports.is_empty());
+        assert_eq!(parsed.devices[0].name, "name with\ttab");
+    }
+
+    #[test]
+    fn parse_rejects_malformed_blobs() {
+        assert!(Snapshot::parse("").is_err());
+        assert!(Snapshot::parse("dv-snapshot 2\n").is_err());
+        assert!(Snapshot::parse("report\t1\tevent5\tname\n").is_err());
+        assert!(Snapshot::parse("dv-snapshot 1\nreport\t2\tevent5\tname\n").is_err());
+        assert!(Snapshot::parse("dv-snapshot 1\nreport\t1\tevent5\n").is_err());
+        assert!(Snapshot::parse("dv-snapshot 1\ndevice\tTablet\t-\tevent5\tname\n").is_err());
+        assert!(Snapshot::parse("dv-snapshot 1\ndevice\tTouchpad\t-\tevent5\n").is_err());
+        assert!(Snapshot::parse("dv-snapshot 1\nbogus\n").is_err());
+    }
+
+    #[test]
+    fn compare_flags_disappeared_changed_and_regressed_devices() {
+        let old = Snapshot {
+            devices: alloc::vec![device("Touchpad", "i2c_hid_acpi"), device("Keyboard", "atkbd"), device("Mouse", "")],
+            reports: alloc::vec![report("Touchpad", true), report("Keyboard", true), report("Mouse", false)],
+        };
+        let new = Snapshot {
+            devices: alloc::vec![device("Touchpad", "psmouse"), device("Mouse", "usbhid")],
+            reports: alloc::vec![report("Touchpad", false), report("Mouse", false)],
+        };
+
+        let differences = old.compare(&new);
+        assert_eq!(
+            rendered(&differences),
+            "811\tTouchpad\ti2c_hid_acpi\tpsmouse\n810\tKeyboard\n811\tMouse\t-\tusbhid\n\
+             812\tTouchpad\n812\tKeyboard\n"
+        );
+        assert!(old.compare(&old).is_empty());
+    }
+
+    #[test]
+    fn compare_matches_devices_sharing_a_name_one_to_one() {
+        let old = Snapshot {
+            devices: alloc::vec![device("Touchpad", "hid"), device("Touchpad", "hid")],
+            reports: Vec::new(),
+        };
+        let new = Snapshot { devices: alloc::vec![device("Touchpad", "hid")], reports: Vec::new() };
+
+        let differences = old.compare(&new);
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].reason(), Reason::Disappeared);
+        assert!(new.compare(&old).is_empty());
+    }
+}
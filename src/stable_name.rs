@@ -0,0 +1,119 @@
+//! Stable `by-path` and `by-id` names for input device nodes.
+//!
+//! Event nodes are numbered in probe order, so `/dev/input/event5` may be a
+//! different device after the next boot. udev creates stable links under
+//! `/dev/input/by-path` and `/dev/input/by-id`; the same names are built here
+//! from the sysfs parents of the device, so reports and snapshots can refer to
+//! a device in a way that survives renumbering without depending on udev.
+//!
+//! `by-path` names follow udev's `path_id` for the buses a laptop touchpad or
+//! keyboard sits on (PCI, platform, serio, USB), e.g.
+//! `platform-i8042-serio-1-event-mouse`. `by-id` names are built from the bus
+//! type and the device name, e.g. `usb-Logitech_USB_Receiver-event-mouse`;
+//! udev only creates those for USB and Bluetooth devices, while here every
+//! known bus gets one.
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::input_verifier::DeviceType;
+use crate::recovery::{last_component, resolve_path};
+
+/// Root of the sysfs device hierarchy.
+const SYSFS_DEVICES: &str = "/sys/devices";
+
+/// Builds the `by-path` name of an input device.
+///
+/// Walks from the input device up to the root of the device hierarchy and
+/// adds a component for every parent on a bus with a stable address.
+///
+/// # Arguments
+///
+/// * `sys_path` - sysfs directory of the input device node, e.g. `/sys/class/input/event5`
+/// * `device_type` - Classification of the device, selects the node suffix
+///
+/// # Returns
+///
+/// * `Option<String>` - `by-path` name, or None if no parent has a stable address
+pub fn by_path(sys_path: &str, device_type: DeviceType) -> Option<String> {
+    let input_dir = resolve_path(&alloc::format!("{}/device", sys_path)).ok()?;
+
+    let mut components = Vec::new();
+    let mut usb_seen = false;
+    let mut dir = input_dir.as_str();
+    while dir.len() > SYSFS_DEVICES.len() && dir.starts_with(SYSFS_DEVICES) {
+        let name = last_component(dir);
+        let subsystem = resolve_path(&alloc::format!("{}/subsystem", dir)).ok();
+
+        match subsystem.as_deref().map(last_component) {
+            Some("pci") => components.push(alloc::format!("pci-{}", name)),
+            Some("platform") => components.push(alloc::format!("platform-{}", name)),
+            Some("serio") => {
+                let port = name.trim_start_matches("serio");
+                components.push(alloc::format!("serio-{}", port));
+            },
+            // Only the interface carries the port; the devices and hubs above it are skipped
+            Some("usb") if !usb_seen && name.contains(':') => {
+                let port = name.split_once('-').map(|(_, port)| port).unwrap_or(name);
+                components.push(alloc::format!("usb-0:{}", port));
+                usb_seen = true;
+            },
+            _ => {},
+        }
+
+        dir = match dir.rfind('/') {
+            Some(end) => &dir[..end],
+            None => break,
+        };
+    }
+
+    if components.is_empty() {
+        return None;
+    }
+
+    components.reverse();
+    let mut name = components.join("-");
+    name.push_str(event_suffix(device_type));
+    Some(name)
+}
+
+/// Builds the `by-id` name of an input device.
+///
+/// # Arguments
+///
+/// * `bustype` - Bus type of the input device (BUS_*), as in `id/bustype`
+/// * `name` - Name of the input device
+/// * `device_type` - Classification of the device, selects the node suffix
+///
+/// # Returns
+///
+/// * `Option<String>` - `by-id` name, or None if the bus type is not known
+pub fn by_id(bustype: u16, name: &str, device_type: DeviceType) -> Option<String> {
+    let bus = match bustype {
+        0x03 => "usb",
+        0x05 => "bluetooth",
+        0x11 => "i8042",
+        0x18 => "i2c",
+        0x19 => "host",
+        0x1c => "spi",
+        0x1d => "rmi",
+        _ => return None,
+    };
+
+    let name: String = name.trim().chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "#+-.:=@_".contains(c) { c } else { '_' })
+        .collect();
+    if name.is_empty() {
+        return None;
+    }
+
+    Some(alloc::format!("{}-{}{}", bus, name, event_suffix(device_type)))
+}
+
+/// Returns the node suffix udev uses for the input class of a device.
+fn event_suffix(device_type: DeviceType) -> &'static str {
+    match device_type {
+        DeviceType::Keyboard => "-event-kbd",
+        DeviceType::Touchpad | DeviceType::Mouse | DeviceType::PointingStick => "-event-mouse",
+        DeviceType::Touchscreen | DeviceType::Sensor | DeviceType::Unknown => "-event",
+    }
+}
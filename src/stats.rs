@@ -0,0 +1,215 @@
+//! Statistics counters for long-running monitoring.
+//!
+//! Counters are plain atomics so they can be bumped from any context, including
+//! the event path, and read through sysfs without taking the verifier lock.
+//! They persist across scans and verification runs until explicitly reset.
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::input_verifier::DeviceType;
+use crate::trace::Phase;
+
+/// Statistics counters. The numeric values identify counters over FFI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Counter {
+    ScansPerformed = 0,
+    TouchpadsFound = 1,
+    KeyboardsFound = 2,
+    MiceFound = 3,
+    UnknownFound = 4,
+    VerificationsRun = 5,
+    FailuresModulesMissing = 6,
+    FailuresNodeUnresponsive = 7,
+    FailuresNoEvents = 8,
+    FailuresNotCapable = 9,
+    FailuresError = 10,
+    EventsObserved = 11,
+    SensorsFound = 12,
+    /// Highest number of bytes used in the scan string pool.
+    PoolHighWaterBytes = 13,
+    /// Highest number of strings held by the scan string pool.
+    PoolHighWaterEntries = 14,
+    /// Strings dropped because the scan string pool was full.
+    PoolExhausted = 15,
+    PointingSticksFound = 16,
+    TouchscreensFound = 17,
+    /// Touchpads running in semi-MT or single-touch mode although they support multitouch.
+    DegradedMultitouch = 18,
+    /// Cumulative and longest time spent in each verification phase, in microseconds.
+    PhaseModulesUs = 19,
+    PhaseResponsiveUs = 20,
+    PhaseEventsUs = 21,
+    PhasePalmUs = 22,
+    PhaseMultitouchUs = 23,
+    PhaseModulesMaxUs = 24,
+    PhaseResponsiveMaxUs = 25,
+    PhaseEventsMaxUs = 26,
+    PhasePalmMaxUs = 27,
+    PhaseMultitouchMaxUs = 28,
+    PhaseFnKeysUs = 29,
+    PhaseFnKeysMaxUs = 30,
+}
+
+/// Number of counters.
+pub const COUNTER_COUNT: usize = 31;
+
+impl Counter {
+    /// Converts a raw counter id received over FFI.
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        let counter = match raw {
+            0 => Counter::ScansPerformed,
+            1 => Counter::TouchpadsFound,
+            2 => Counter::KeyboardsFound,
+            3 => Counter::MiceFound,
+            4 => Counter::UnknownFound,
+            5 => Counter::VerificationsRun,
+            6 => Counter::FailuresModulesMissing,
+            7 => Counter::FailuresNodeUnresponsive,
+            8 => Counter::FailuresNoEvents,
+            9 => Counter::FailuresNotCapable,
+            10 => Counter::FailuresError,
+            11 => Counter::EventsObserved,
+            12 => Counter::SensorsFound,
+            13 => Counter::PoolHighWaterBytes,
+            14 => Counter::PoolHighWaterEntries,
+            15 => Counter::PoolExhausted,
+            16 => Counter::PointingSticksFound,
+            17 => Counter::TouchscreensFound,
+            18 => Counter::DegradedMultitouch,
+            19 => Counter::PhaseModulesUs,
+            20 => Counter::PhaseResponsiveUs,
+            21 => Counter::PhaseEventsUs,
+            22 => Counter::PhasePalmUs,
+            23 => Counter::PhaseMultitouchUs,
+            24 => Counter::PhaseModulesMaxUs,
+            25 => Counter::PhaseResponsiveMaxUs,
+            26 => Counter::PhaseEventsMaxUs,
+            27 => Counter::PhasePalmMaxUs,
+            28 => Counter::PhaseMultitouchMaxUs,
+            29 => Counter::PhaseFnKeysUs,
+            30 => Counter::PhaseFnKeysMaxUs,
+            _ => return None,
+        };
+        Some(counter)
+    }
+
+    /// Returns every counter in id order.
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..COUNTER_COUNT as u32).filter_map(Self::from_raw)
+    }
+
+    /// Returns the name of the counter, as its sysfs attribute.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Counter::ScansPerformed => "scans_performed",
+            Counter::TouchpadsFound => "touchpads_found",
+            Counter::KeyboardsFound => "keyboards_found",
+            Counter::MiceFound => "mice_found",
+            Counter::UnknownFound => "unknown_found",
+            Counter::VerificationsRun => "verifications_run",
+            Counter::FailuresModulesMissing => "failures_modules_missing",
+            Counter::FailuresNodeUnresponsive => "failures_node_unresponsive",
+            Counter::FailuresNoEvents => "failures_no_events",
+            Counter::FailuresNotCapable => "failures_not_capable",
+            Counter::FailuresError => "failures_error",
+            Counter::EventsObserved => "events_observed",
+            Counter::SensorsFound => "sensors_found",
+            Counter::PoolHighWaterBytes => "pool_high_water_bytes",
+            Counter::PoolHighWaterEntries => "pool_high_water_entries",
+            Counter::PoolExhausted => "pool_exhausted",
+            Counter::PointingSticksFound => "pointing_sticks_found",
+            Counter::TouchscreensFound => "touchscreens_found",
+            Counter::DegradedMultitouch => "degraded_multitouch",
+            Counter::PhaseModulesUs => "phase_modules_us",
+            Counter::PhaseResponsiveUs => "phase_responsive_us",
+            Counter::PhaseEventsUs => "phase_events_us",
+            Counter::PhasePalmUs => "phase_palm_us",
+            Counter::PhaseMultitouchUs => "phase_multitouch_us",
+            Counter::PhaseModulesMaxUs => "phase_modules_max_us",
+            Counter::PhaseResponsiveMaxUs => "phase_responsive_max_us",
+            Counter::PhaseEventsMaxUs => "phase_events_max_us",
+            Counter::PhasePalmMaxUs => "phase_palm_max_us",
+            Counter::PhaseMultitouchMaxUs => "phase_multitouch_max_us",
+            Counter::PhaseFnKeysUs => "phase_fn_keys_us",
+            Counter::PhaseFnKeysMaxUs => "phase_fn_keys_max_us",
+        }
+    }
+
+    /// Returns true for high-water marks, which never decrease but aren't event counts.
+    pub fn is_high_water(&self) -> bool {
+        matches!(
+            self,
+            Counter::PoolHighWaterBytes | Counter::PoolHighWaterEntries | Counter::PhaseModulesMaxUs
+                | Counter::PhaseResponsiveMaxUs | Counter::PhaseEventsMaxUs | Counter::PhasePalmMaxUs
+                | Counter::PhaseMultitouchMaxUs | Counter::PhaseFnKeysMaxUs
+        )
+    }
+
+    /// Returns the counters of the cumulative and of the longest time spent in a phase.
+    pub fn for_phase(phase: Phase) -> (Self, Self) {
+        match phase {
+            Phase::Modules => (Counter::PhaseModulesUs, Counter::PhaseModulesMaxUs),
+            Phase::Responsive => (Counter::PhaseResponsiveUs, Counter::PhaseResponsiveMaxUs),
+            Phase::Events => (Counter::PhaseEventsUs, Counter::PhaseEventsMaxUs),
+            Phase::Palm => (Counter::PhasePalmUs, Counter::PhasePalmMaxUs),
+            Phase::Multitouch => (Counter::PhaseMultitouchUs, Counter::PhaseMultitouchMaxUs),
+            Phase::FnKeys => (Counter::PhaseFnKeysUs, Counter::PhaseFnKeysMaxUs),
+        }
+    }
+
+    /// Returns the counter that tracks devices found of the given type.
+    pub fn for_device_type(device_type: DeviceType) -> Self {
+        match device_type {
+            DeviceType::Touchpad => Counter::TouchpadsFound,
+            DeviceType::Keyboard => Counter::KeyboardsFound,
+            DeviceType::Mouse => Counter::MiceFound,
+            DeviceType::Sensor => Counter::SensorsFound,
+            DeviceType::PointingStick => Counter::PointingSticksFound,
+            DeviceType::Touchscreen => Counter::TouchscreensFound,
+            DeviceType::Unknown => Counter::UnknownFound,
+        }
+    }
+}
+
+/// Set of statistics counters.
+pub struct Stats {
+    counters: [AtomicU64; COUNTER_COUNT],
+}
+
+/// Global statistics instance.
+pub static STATS: Stats = Stats::new();
+
+impl Stats {
+    const fn new() -> Self {
+        Self {
+            counters: [const { AtomicU64::new(0) }; COUNTER_COUNT],
+        }
+    }
+
+    /// Increments a counter by one.
+    pub fn inc(&self, counter: Counter) {
+        self.add(counter, 1);
+    }
+
+    /// Adds `value` to a counter.
+    pub fn add(&self, counter: Counter, value: u64) {
+        self.counters[counter as usize].fetch_add(value, Ordering::Relaxed);
+    }
+
+    /// Raises a high-water mark counter to `value` if it is higher.
+    pub fn max(&self, counter: Counter, value: u64) {
+        self.counters[counter as usize].fetch_max(value, Ordering::Relaxed);
+    }
+
+    /// Returns the current value of a counter.
+    pub fn get(&self, counter: Counter) -> u64 {
+        self.counters[counter as usize].load(Ordering::Relaxed)
+    }
+
+    /// Resets all counters to zero.
+    pub fn reset(&self) {
+        for counter in self.counters.iter() {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Conversion of raw kernel buffers into Rust strings.
+//!
+//! Device names come from firmware and drivers and are not guaranteed to be
+//! UTF-8. Dropping devices whose name isn't valid UTF-8 would hide exactly the
+//! odd devices worth reporting, so names are converted lossily instead: invalid
+//! bytes and control characters are escaped as `\xNN`, which keeps the name
+//! printable, on one line and distinct from other names. Classification
+//! matches the raw bytes (see `matcher`), so it is unaffected by the escaping.
+use alloc::borrow::Cow;
+use alloc::string::String;
+use core::fmt::Write;
+
+/// Returns true if the character has to be escaped to keep logs and reports readable.
+fn needs_escape(c: char) -> bool {
+    c.is_ascii_control()
+}
+
+/// Converts raw bytes into a string, escaping invalid UTF-8 and control characters.
+///
+/// Borrows the bytes if nothing needs escaping, so valid names don't allocate.
+///
+/// # Arguments
+///
+/// * `bytes` - Raw bytes, e.g. a device name read from sysfs
+pub fn escape_lossy(bytes: &[u8]) -> Cow<'_, str> {
+    if let Ok(text) = core::str::from_utf8(bytes) {
+        if !text.chars().any(needs_escape) {
+            return Cow::Borrowed(text);
+        }
+    }
+
+    let mut escaped = String::with_capacity(bytes.len() + 8);
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if needs_escape(c) {
+                let _ = write!(escaped, "\\x{:02x}", c as u32);
+            } else {
+                escaped.push(c);
+            }
+        }
+        for byte in chunk.invalid() {
+            let _ = write!(escaped, "\\x{:02x}", byte);
+        }
+    }
+    Cow::Owned(escaped)
+}
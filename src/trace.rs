@@ -0,0 +1,131 @@
+//! Static tracepoints for verification phases.
+//!
+//! Thin wrappers around the `kernel_trace_*` C shims, which fire the
+//! `driver_verifier` trace events. Tracepoints are nearly free when disabled,
+//! so these are called unconditionally on every scan and verification.
+use crate::input_verifier::DeviceType;
+use crate::shim;
+
+/// Verification phases reported by the `dv_phase` tracepoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Phase {
+    Modules = 0,
+    Responsive = 1,
+    Events = 2,
+    Palm = 3,
+    Multitouch = 4,
+    /// Special function keys of the keyboards, checked once per run.
+    FnKeys = 5,
+}
+
+/// Number of verification phases.
+pub const PHASE_COUNT: usize = 6;
+
+impl Phase {
+    /// All phases, in the order they run.
+    pub const ALL: [Phase; PHASE_COUNT] = [
+        Phase::Modules,
+        Phase::Responsive,
+        Phase::Events,
+        Phase::Multitouch,
+        Phase::Palm,
+        Phase::FnKeys,
+    ];
+
+    /// Returns the name of the phase, as shown by the `dv_phase` tracepoint.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Modules => "modules",
+            Phase::Responsive => "responsive",
+            Phase::Events => "events",
+            Phase::Palm => "palm",
+            Phase::Multitouch => "multitouch",
+            Phase::FnKeys => "fn_keys",
+        }
+    }
+}
+
+/// How a device was classified, reported by the `dv_classify` tracepoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ClassifyMethod {
+    Name = 0,
+    Capabilities = 1,
+}
+
+/// Phase outcome: the phase passed.
+pub const OUTCOME_PASS: i32 = 1;
+/// Phase outcome: the phase ran and failed.
+pub const OUTCOME_FAIL: i32 = 0;
+/// Phase outcome: the phase could not run.
+pub const OUTCOME_ERROR: i32 = -1;
+
+/// Converts a phase result into a tracepoint outcome.
+pub fn outcome<E>(result: &Result<bool, E>) -> i32 {
+    match result {
+        Ok(true) => OUTCOME_PASS,
+        Ok(false) => OUTCOME_FAIL,
+        Err(_) => OUTCOME_ERROR,
+    }
+}
+
+/// Fires the `dv_scan_start` tracepoint.
+pub fn scan_start() {
+    if let Some(kernel_trace_scan_start) = shim::ops().trace_scan_start {
+        unsafe { kernel_trace_scan_start() };
+    }
+}
+
+/// Fires the `dv_scan_end` tracepoint.
+///
+/// # Arguments
+///
+/// * `devices` - Number of devices found
+/// * `result` - 0 if the scan succeeded, -1 otherwise
+pub fn scan_end(devices: u32, result: i32) {
+    if let Some(kernel_trace_scan_end) = shim::ops().trace_scan_end {
+        unsafe { kernel_trace_scan_end(devices, result) };
+    }
+}
+
+/// Fires the `dv_phase` tracepoint.
+///
+/// # Arguments
+///
+/// * `path` - Path of the device being verified
+/// * `phase` - Verification phase that completed
+/// * `outcome` - One of `OUTCOME_PASS`, `OUTCOME_FAIL` or `OUTCOME_ERROR`
+pub fn phase(path: &str, phase: Phase, outcome: i32) {
+    if let Some(kernel_trace_phase) = shim::ops().trace_phase {
+        let path_bytes = path.as_bytes();
+        unsafe { kernel_trace_phase(path_bytes.as_ptr(), path_bytes.len(), phase as u32, outcome) };
+    }
+}
+
+/// Fires the `dv_classify` tracepoint.
+///
+/// # Arguments
+///
+/// * `path` - Path of the classified device
+/// * `device_type` - Resulting device type
+/// * `method` - Whether the name or the capabilities decided the type
+pub fn classify(path: &str, device_type: DeviceType, method: ClassifyMethod) {
+    let Some(kernel_trace_classify) = shim::ops().trace_classify else {
+        return;
+    };
+
+    unsafe {
+        let type_id = match device_type {
+            DeviceType::Touchpad => 0,
+            DeviceType::Keyboard => 1,
+            DeviceType::Mouse => 2,
+            DeviceType::Unknown => 3,
+            DeviceType::Sensor => 4,
+            DeviceType::PointingStick => 5,
+            DeviceType::Touchscreen => 6,
+        };
+        let path_bytes = path.as_bytes();
+        kernel_trace_classify(path_bytes.as_ptr(), path_bytes.len(), type_id, method as u32);
+    }
+}
@@ -0,0 +1,70 @@
+//! TrackPoint (pointing stick) readout.
+//!
+//! Pointing sticks are PS/2 devices driven by psmouse, either on their own
+//! serio port or behind the pass-through port of a Synaptics or ALPS touchpad.
+//! A stick that isn't attached to a serio port through psmouse is handled by a
+//! generic driver and loses its tuning. psmouse's TrackPoint extension exposes
+//! the tuning as attributes of the serio device, which are read into the report
+//! so a stick that "drifts" or "is too slow" can be told apart from a broken one.
+use alloc::string::String;
+
+use crate::kprint;
+
+/// Driver pointing sticks must be bound to for their TrackPoint attributes to exist.
+pub const PSMOUSE_DRIVER: &str = "psmouse";
+
+/// TrackPoint attributes read into the report, as exposed on the serio device.
+pub const SENSITIVITY_ATTR: &str = "sensitivity";
+pub const SPEED_ATTR: &str = "speed";
+pub const INERTIA_ATTR: &str = "inertia";
+pub const PRESS_TO_SELECT_ATTR: &str = "press_to_select";
+
+/// Attachment and tuning of a pointing stick.
+#[derive(Debug, Clone, Default)]
+pub struct TrackpointInfo {
+    pub name: String,
+    pub path: String,
+    /// Serio port the stick is attached to, e.g. "serio2", None if it isn't on a serio port.
+    pub serio_port: Option<String>,
+    /// Driver bound to the serio port.
+    pub driver: Option<String>,
+    /// psmouse protocol, e.g. "TPPS/2" for an IBM/Lenovo TrackPoint.
+    pub protocol: Option<String>,
+    pub sensitivity: Option<u32>,
+    pub speed: Option<u32>,
+    pub inertia: Option<u32>,
+    pub press_to_select: Option<bool>,
+}
+
+impl TrackpointInfo {
+    /// Returns true if the stick is on a serio port driven by psmouse.
+    pub fn attached(&self) -> bool {
+        self.serio_port.is_some() && self.driver.as_deref() == Some(PSMOUSE_DRIVER)
+    }
+
+    /// Returns true if psmouse exposes the TrackPoint tuning attributes.
+    pub fn tunable(&self) -> bool {
+        self.sensitivity.is_some() || self.speed.is_some()
+    }
+
+    /// Writes the readout to the kernel log.
+    pub fn log(&self) {
+        kprint!("  trackpoint:      {} ({})\n", self.name, self.path);
+        match (self.serio_port.as_deref(), self.driver.as_deref()) {
+            (Some(port), Some(driver)) => kprint!("    attached to {} through {}, protocol {}\n", port, driver,
+                                                   self.protocol.as_deref().unwrap_or("-")),
+            (Some(port), None) => kprint!("    on {}, no driver bound\n", port),
+            (None, _) => kprint!("    not on a serio port\n"),
+        }
+        if !self.attached() {
+            kprint!("    not driven by {}, TrackPoint tuning is unavailable\n", PSMOUSE_DRIVER);
+        } else if self.tunable() {
+            let value = |value: Option<u32>| value.map_or(String::from("-"), |value| alloc::format!("{}", value));
+            kprint!("    sensitivity: {}, speed: {}, inertia: {}, press to select: {}\n",
+                    value(self.sensitivity), value(self.speed), value(self.inertia),
+                    self.press_to_select.map_or("-", |enabled| if enabled { "on" } else { "off" }));
+        } else {
+            kprint!("    no TrackPoint attributes, psmouse did not detect a TrackPoint\n");
+        }
+    }
+}
@@ -0,0 +1,453 @@
+//! Device classification following the udev `input_id` builtin.
+//!
+//! Userspace does not use our classification: libinput and the desktop pick
+//! devices by the `ID_INPUT_*` properties udev computes from the capability
+//! bitmaps in sysfs. The same rules are applied here, so a report shows both
+//! views side by side and a device the kernel module treats as a touchpad but
+//! userspace does not (or the other way around) is visible immediately.
+use alloc::vec::Vec;
+
+use crate::input_verifier::DeviceType;
+
+const EV_KEY: usize = 0x01;
+const EV_REL: usize = 0x02;
+const EV_ABS: usize = 0x03;
+const EV_SW: usize = 0x05;
+
+const REL_X: usize = 0x00;
+const REL_Y: usize = 0x01;
+
+const ABS_X: usize = 0x00;
+const ABS_Y: usize = 0x01;
+const ABS_Z: usize = 0x02;
+const ABS_RX: usize = 0x03;
+const ABS_PRESSURE: usize = 0x18;
+const ABS_MISC: usize = 0x28;
+const ABS_MT_SLOT: usize = 0x2f;
+const ABS_MT_POSITION_X: usize = 0x35;
+const ABS_MT_POSITION_Y: usize = 0x36;
+
+const BTN_MISC: usize = 0x100;
+const BTN_0: usize = 0x100;
+const BTN_MOUSE: usize = 0x110;
+const BTN_JOYSTICK: usize = 0x120;
+const BTN_DIGI: usize = 0x140;
+const BTN_TOOL_PEN: usize = 0x140;
+const BTN_TOOL_FINGER: usize = 0x145;
+const BTN_TOUCH: usize = 0x14a;
+const BTN_STYLUS: usize = 0x14b;
+const KEY_OK: usize = 0x160;
+const BTN_DPAD_UP: usize = 0x220;
+const BTN_DPAD_RIGHT: usize = 0x223;
+const KEY_ALS_TOGGLE: usize = 0x230;
+const BTN_TRIGGER_HAPPY1: usize = 0x2c0;
+const BTN_TRIGGER_HAPPY40: usize = 0x2e7;
+
+const INPUT_PROP_POINTER: usize = 0x00;
+const INPUT_PROP_DIRECT: usize = 0x01;
+const INPUT_PROP_BUTTONPAD: usize = 0x02;
+const INPUT_PROP_SEMI_MT: usize = 0x03;
+const INPUT_PROP_TOPBUTTONPAD: usize = 0x04;
+const INPUT_PROP_POINTING_STICK: usize = 0x05;
+const INPUT_PROP_ACCELEROMETER: usize = 0x06;
+
+/// A capability bitmap as exported in sysfs.
+#[derive(Debug, Clone, Default)]
+pub struct Bitmap {
+    /// Words of the bitmap, lowest bits first.
+    words: Vec<usize>,
+}
+
+impl Bitmap {
+    /// Parses a bitmap in the sysfs format.
+    ///
+    /// sysfs prints the bitmap as space separated hex words of the kernel's
+    /// `long` size, most significant word first, with leading zero words left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Contents of a `capabilities/*` or `properties` file
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, ()>` - Parsed bitmap, or Err if a word is not valid hex
+    pub fn parse(text: &str) -> Result<Self, ()> {
+        let mut words = text.split_whitespace()
+            .map(|word| usize::from_str_radix(word, 16).map_err(|_| ()))
+            .collect::<Result<Vec<usize>, ()>>()?;
+        words.reverse();
+        Ok(Self { words })
+    }
+
+    /// Copies a bitmap in the kernel's in-memory format.
+    ///
+    /// # Arguments
+    ///
+    /// * `words` - `long` words of the bitmap, lowest bits first
+    pub fn from_words(words: &[usize]) -> Self {
+        // Leading zero words are left out, like sysfs does
+        let len = words.iter().rposition(|word| *word != 0).map_or(0, |last| last + 1);
+        Self { words: words[..len].to_vec() }
+    }
+
+    /// Returns true if `bit` is set.
+    pub fn test(&self, bit: usize) -> bool {
+        self.words.get(bit / usize::BITS as usize)
+            .is_some_and(|word| word & (1 << (bit % usize::BITS as usize)) != 0)
+    }
+
+    /// Returns true if `bit` is the only bit set.
+    fn is_only(&self, bit: usize) -> bool {
+        self.test(bit) && self.words.iter().map(|word| word.count_ones()).sum::<u32>() == 1
+    }
+
+    /// Returns true if any bit in `range` is set.
+    fn any(&self, range: core::ops::Range<usize>) -> bool {
+        range.into_iter().any(|bit| self.test(bit))
+    }
+}
+
+/// Capability bitmaps of an input device, as read from sysfs or the input core.
+#[derive(Debug, Clone, Default)]
+pub struct InputBits {
+    pub ev: Bitmap,
+    pub key: Bitmap,
+    pub rel: Bitmap,
+    pub abs: Bitmap,
+    pub prop: Bitmap,
+}
+
+impl InputBits {
+    /// Returns true if the device is a sensor rather than a human input device.
+    ///
+    /// Accelerometers are flagged with INPUT_PROP_ACCELEROMETER or, as udev
+    /// assumes, advertise X/Y/Z axes without keys. Other sensors, e.g. lid
+    /// angle or ambient light, report a single ABS_MISC axis and nothing else.
+    pub fn is_sensor(&self) -> bool {
+        UdevClass::classify(self).accelerometer
+            || (self.abs.is_only(ABS_MISC) && !self.ev.test(EV_KEY) && !self.ev.test(EV_REL))
+    }
+
+    /// Returns true if the driver flags the device as a pointing stick.
+    pub fn is_pointing_stick(&self) -> bool {
+        self.prop.test(INPUT_PROP_POINTING_STICK)
+    }
+
+    /// Returns the input properties of the device.
+    pub fn props(&self) -> InputProps {
+        InputProps {
+            pointer: self.prop.test(INPUT_PROP_POINTER),
+            direct: self.prop.test(INPUT_PROP_DIRECT),
+            buttonpad: self.prop.test(INPUT_PROP_BUTTONPAD),
+            semi_mt: self.prop.test(INPUT_PROP_SEMI_MT),
+            topbuttonpad: self.prop.test(INPUT_PROP_TOPBUTTONPAD),
+            pointing_stick: self.prop.test(INPUT_PROP_POINTING_STICK),
+            accelerometer: self.prop.test(INPUT_PROP_ACCELEROMETER),
+        }
+    }
+}
+
+/// The `INPUT_PROP_*` properties a driver sets on a device.
+///
+/// Unlike the capabilities, the properties state what the device is: a
+/// touchpad is an indirect pointer (`pointer`), a touchscreen maps touches to
+/// the screen (`direct`), and a clickpad has its button under the surface
+/// (`buttonpad`) instead of separate physical buttons.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputProps {
+    pub pointer: bool,
+    pub direct: bool,
+    pub buttonpad: bool,
+    /// Only the bounding box of the contacts is reported, not each contact.
+    pub semi_mt: bool,
+    pub topbuttonpad: bool,
+    pub pointing_stick: bool,
+    pub accelerometer: bool,
+}
+
+impl InputProps {
+    /// Returns the names of the properties that are set.
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            (self.pointer, "INPUT_PROP_POINTER"),
+            (self.direct, "INPUT_PROP_DIRECT"),
+            (self.buttonpad, "INPUT_PROP_BUTTONPAD"),
+            (self.semi_mt, "INPUT_PROP_SEMI_MT"),
+            (self.topbuttonpad, "INPUT_PROP_TOPBUTTONPAD"),
+            (self.pointing_stick, "INPUT_PROP_POINTING_STICK"),
+            (self.accelerometer, "INPUT_PROP_ACCELEROMETER"),
+        ]
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, name)| name)
+        .collect()
+    }
+}
+
+/// The `ID_INPUT_*` properties udev would assign to a device.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UdevClass {
+    pub key: bool,
+    pub keyboard: bool,
+    pub mouse: bool,
+    pub touchpad: bool,
+    pub touchscreen: bool,
+    pub tablet: bool,
+    pub tablet_pad: bool,
+    pub joystick: bool,
+    pub pointingstick: bool,
+    pub accelerometer: bool,
+    pub switch: bool,
+}
+
+impl UdevClass {
+    /// Applies the udev `input_id` rules to the capability bitmaps of a device.
+    ///
+    /// # Arguments
+    ///
+    /// * `bits` - Capability bitmaps of the device
+    pub fn classify(bits: &InputBits) -> Self {
+        let mut class = Self::default();
+        let has_keys = bits.ev.test(EV_KEY);
+        let has_abs_coordinates = bits.abs.test(ABS_X) && bits.abs.test(ABS_Y);
+
+        // A device with X/Y/Z axes and no keys can only be an accelerometer
+        if bits.prop.test(INPUT_PROP_ACCELEROMETER)
+            || (!has_keys && has_abs_coordinates && bits.abs.test(ABS_Z)) {
+            class.accelerometer = true;
+            return class;
+        }
+
+        class.classify_pointer(bits, has_abs_coordinates);
+        class.classify_keys(bits);
+        class.switch = bits.ev.test(EV_SW);
+        class
+    }
+
+    /// Sets the pointer properties, following `test_pointers` of `input_id`.
+    fn classify_pointer(&mut self, bits: &InputBits, has_abs_coordinates: bool) {
+        let has_stylus = bits.key.test(BTN_STYLUS);
+        let has_pen = bits.key.test(BTN_TOOL_PEN);
+        let finger_but_no_pen = bits.key.test(BTN_TOOL_FINGER) && !has_pen;
+        let has_mouse_button = bits.key.any(BTN_MOUSE..BTN_JOYSTICK);
+        let has_rel_coordinates = bits.ev.test(EV_REL) && bits.rel.test(REL_X) && bits.rel.test(REL_Y);
+        let is_direct = bits.prop.test(INPUT_PROP_DIRECT);
+        let has_touch = bits.key.test(BTN_TOUCH);
+        let has_pad_buttons = bits.key.test(BTN_0) && has_stylus && !has_pen;
+        let has_joystick = bits.key.any(BTN_JOYSTICK..BTN_DIGI)
+            || bits.key.any(BTN_TRIGGER_HAPPY1..BTN_TRIGGER_HAPPY40 + 1)
+            || bits.key.any(BTN_DPAD_UP..BTN_DPAD_RIGHT + 1)
+            || bits.abs.any(ABS_RX..ABS_PRESSURE);
+
+        // Devices claiming every axis also claim the MT axes without being MT devices
+        let has_mt_coordinates = bits.abs.test(ABS_MT_POSITION_X) && bits.abs.test(ABS_MT_POSITION_Y)
+            && !(bits.abs.test(ABS_MT_SLOT) && bits.abs.test(ABS_MT_SLOT - 1));
+
+        if has_abs_coordinates && bits.ev.test(EV_ABS) {
+            if has_stylus || has_pen {
+                self.tablet = true;
+            } else if finger_but_no_pen && !is_direct {
+                self.touchpad = true;
+            } else if has_mouse_button {
+                self.mouse = true;
+            } else if has_touch || is_direct {
+                self.touchscreen = true;
+            } else if has_joystick {
+                self.joystick = true;
+            }
+        } else if has_joystick {
+            self.joystick = true;
+        }
+
+        if has_mt_coordinates {
+            if has_stylus || has_pen {
+                self.tablet = true;
+            } else if finger_but_no_pen && !is_direct {
+                self.touchpad = true;
+            } else if has_touch || is_direct {
+                self.touchscreen = true;
+            }
+        }
+
+        self.tablet_pad = self.tablet && has_pad_buttons;
+        if !self.tablet && !self.touchpad && !self.joystick && has_mouse_button
+            && (has_rel_coordinates || !has_abs_coordinates) {
+            self.mouse = true;
+        }
+
+        self.pointingstick = bits.prop.test(INPUT_PROP_POINTING_STICK);
+        if self.pointingstick {
+            self.mouse = true;
+        }
+    }
+
+    /// Sets the key properties, following `test_key` of `input_id`.
+    fn classify_keys(&mut self, bits: &InputBits) {
+        self.key = bits.key.any(1..BTN_MISC)
+            || bits.key.any(KEY_OK..BTN_DPAD_UP)
+            || bits.key.any(KEY_ALS_TOGGLE..BTN_TRIGGER_HAPPY1);
+
+        // A keyboard has all of KEY_ESC through KEY_S, the first 32 codes except KEY_RESERVED
+        self.keyboard = (1..32).all(|key| bits.key.test(key));
+    }
+
+    /// Checks whether udev agrees with our classification of a device.
+    ///
+    /// # Arguments
+    ///
+    /// * `device_type` - Our classification of the device
+    pub fn agrees_with(&self, device_type: DeviceType) -> bool {
+        match device_type {
+            DeviceType::Touchpad => self.touchpad,
+            DeviceType::Mouse => self.mouse && !self.touchpad,
+            DeviceType::PointingStick => self.pointingstick,
+            DeviceType::Touchscreen => self.touchscreen,
+            DeviceType::Keyboard => self.keyboard,
+            DeviceType::Sensor | DeviceType::Unknown => !self.touchpad && !self.mouse && !self.keyboard,
+        }
+    }
+
+    /// Returns the names of the properties that are set.
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            (self.key, "ID_INPUT_KEY"),
+            (self.keyboard, "ID_INPUT_KEYBOARD"),
+            (self.mouse, "ID_INPUT_MOUSE"),
+            (self.touchpad, "ID_INPUT_TOUCHPAD"),
+            (self.touchscreen, "ID_INPUT_TOUCHSCREEN"),
+            (self.tablet, "ID_INPUT_TABLET"),
+            (self.tablet_pad, "ID_INPUT_TABLET_PAD"),
+            (self.joystick, "ID_INPUT_JOYSTICK"),
+            (self.pointingstick, "ID_INPUT_POINTINGSTICK"),
+            (self.accelerometer, "ID_INPUT_ACCELEROMETER"),
+            (self.switch, "ID_INPUT_SWITCH"),
+        ]
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, name)| name)
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BTN_LEFT: usize = 0x110;
+    const KEY_ESC: usize = 1;
+
+    fn bitmap(bits: &[usize]) -> Bitmap {
+        let mut words = alloc::vec![0usize; 0x300 / usize::BITS as usize + 1];
+        for bit in bits {
+            words[bit / usize::BITS as usize] |= 1 << (bit % usize::BITS as usize);
+        }
+        Bitmap::from_words(&words)
+    }
+
+    fn bits(ev: &[usize], key: &[usize], rel: &[usize], abs: &[usize], prop: &[usize]) -> InputBits {
+        InputBits { ev: bitmap(ev), key: bitmap(key), rel: bitmap(rel), abs: bitmap(abs), prop: bitmap(prop) }
+    }
+
+    #[test]
+    fn bitmap_parses_the_sysfs_format() {
+        let bitmap = Bitmap::parse("1 3\n").unwrap();
+        assert!(bitmap.test(0) && bitmap.test(1) && !bitmap.test(2));
+        assert!(bitmap.test(usize::BITS as usize));
+        assert!(!bitmap.test(10 * usize::BITS as usize));
+        assert!(!Bitmap::parse("").unwrap().test(0));
+        assert!(Bitmap::parse("1 xyz").is_err());
+    }
+
+    #[test]
+    fn classifies_a_touchpad() {
+        let touchpad = bits(
+            &[EV_KEY, EV_ABS], &[BTN_LEFT, BTN_TOOL_FINGER, BTN_TOUCH], &[],
+            &[ABS_X, ABS_Y, ABS_MT_SLOT, ABS_MT_POSITION_X, ABS_MT_POSITION_Y], &[INPUT_PROP_POINTER],
+        );
+        let class = UdevClass::classify(&touchpad);
+        assert!(class.touchpad && !class.mouse && !class.touchscreen);
+        assert!(class.agrees_with(DeviceType::Touchpad));
+        assert!(!class.agrees_with(DeviceType::Mouse));
+        assert_eq!(class.names(), ["ID_INPUT_TOUCHPAD"]);
+    }
+
+    #[test]
+    fn classifies_a_direct_touch_device_as_touchscreen() {
+        let touchscreen = bits(
+            &[EV_KEY, EV_ABS], &[BTN_TOUCH, BTN_TOOL_FINGER], &[], &[ABS_X, ABS_Y], &[INPUT_PROP_DIRECT],
+        );
+        let class = UdevClass::classify(&touchscreen);
+        assert!(class.touchscreen && !class.touchpad);
+    }
+
+    #[test]
+    fn classifies_mice_and_pointing_sticks() {
+        let mouse = bits(&[EV_KEY, EV_REL], &[BTN_LEFT], &[REL_X, REL_Y], &[], &[]);
+        let class = UdevClass::classify(&mouse);
+        assert!(class.mouse && !class.pointingstick);
+        assert!(class.agrees_with(DeviceType::Mouse));
+
+        let stick = bits(&[EV_KEY, EV_REL], &[BTN_LEFT], &[REL_X, REL_Y], &[], &[INPUT_PROP_POINTING_STICK]);
+        let class = UdevClass::classify(&stick);
+        assert!(class.mouse && class.pointingstick);
+        assert!(stick.is_pointing_stick());
+    }
+
+    #[test]
+    fn classifies_tablets_and_their_pads() {
+        let pen = bits(&[EV_KEY, EV_ABS], &[BTN_TOOL_PEN, BTN_TOUCH, BTN_STYLUS], &[], &[ABS_X, ABS_Y], &[]);
+        let class = UdevClass::classify(&pen);
+        assert!(class.tablet && !class.touchpad && !class.tablet_pad);
+
+        let pad = bits(&[EV_KEY, EV_ABS], &[BTN_0, BTN_STYLUS], &[], &[ABS_X, ABS_Y], &[]);
+        assert!(UdevClass::classify(&pad).tablet_pad);
+    }
+
+    #[test]
+    fn classifies_accelerometers_and_other_sensors() {
+        let accelerometer = bits(&[EV_ABS], &[], &[], &[ABS_X, ABS_Y, ABS_Z], &[]);
+        let class = UdevClass::classify(&accelerometer);
+        assert_eq!(class, UdevClass { accelerometer: true, ..UdevClass::default() });
+        assert!(accelerometer.is_sensor());
+
+        let flagged = bits(&[EV_KEY, EV_ABS], &[BTN_TOUCH], &[], &[ABS_X, ABS_Y], &[INPUT_PROP_ACCELEROMETER]);
+        assert!(UdevClass::classify(&flagged).accelerometer);
+
+        let lid_angle = bits(&[EV_ABS], &[], &[], &[ABS_MISC], &[]);
+        assert!(lid_angle.is_sensor());
+        assert!(UdevClass::classify(&lid_angle).agrees_with(DeviceType::Sensor));
+    }
+
+    #[test]
+    fn classifies_keyboards_and_keys() {
+        let keyboard = bits(&[EV_KEY], &(KEY_ESC..32).collect::<Vec<_>>(), &[], &[], &[]);
+        let class = UdevClass::classify(&keyboard);
+        assert!(class.key && class.keyboard);
+
+        let power_button = bits(&[EV_KEY], &[0x74], &[], &[], &[]);
+        let class = UdevClass::classify(&power_button);
+        assert!(class.key && !class.keyboard);
+        assert!(!class.agrees_with(DeviceType::Keyboard));
+    }
+
+    #[test]
+    fn devices_claiming_every_axis_are_not_multitouch() {
+        let mt_only = bits(
+            &[EV_KEY, EV_ABS], &[BTN_TOOL_FINGER], &[], &[ABS_MT_SLOT, ABS_MT_POSITION_X, ABS_MT_POSITION_Y], &[],
+        );
+        assert!(UdevClass::classify(&mt_only).touchpad);
+
+        let every_axis = bits(
+            &[EV_KEY, EV_ABS], &[BTN_TOOL_FINGER], &[],
+            &[ABS_MT_SLOT - 1, ABS_MT_SLOT, ABS_MT_POSITION_X, ABS_MT_POSITION_Y], &[],
+        );
+        assert!(!UdevClass::classify(&every_axis).touchpad);
+    }
+
+    #[test]
+    fn switches_and_empty_devices() {
+        let lid = bits(&[EV_SW], &[], &[], &[], &[]);
+        assert_eq!(UdevClass::classify(&lid).names(), ["ID_INPUT_SWITCH"]);
+        assert_eq!(UdevClass::classify(&InputBits::default()), UdevClass::default());
+    }
+}
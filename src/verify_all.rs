@@ -0,0 +1,99 @@
+//! Concurrent verification of every scanned device.
+//!
+//! Verifying keyboards, mice and touchpads one after another is slow, since
+//! every device node is opened and may take a while to respond. The C side
+//! runs one work item per device on a workqueue whose `max_active` bounds the
+//! concurrency, and waits on a completion until all of them are done:
+//!
+//! 1. `rust_verify_all_begin` clears the result slot of every device
+//! 2. each work item runs `rust_verify_device` for one device
+//! 3. `rust_verify_all_end` collects the results once every work item finished
+//!
+//! The caller holds the verifier lock for the whole sequence, so the registry
+//! is not rescanned while work items run. Work items only read the verifier
+//! and write the result slot of their device, which has its own lock.
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Outcome of the verification of a single device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceVerdict {
+    /// The device is present and its node responds.
+    Working,
+    /// The device is present but its node does not respond.
+    NotResponsive,
+    /// Another client grabbed the device, so its events can't be checked.
+    Grabbed,
+    /// The device is gone since the scan.
+    Gone,
+    /// The verification could not run.
+    Failed,
+}
+
+impl DeviceVerdict {
+    /// Returns a description of the verdict for the kernel log.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceVerdict::Working => "working",
+            DeviceVerdict::NotResponsive => "not responsive",
+            DeviceVerdict::Grabbed => "grabbed by another client",
+            DeviceVerdict::Gone => "gone",
+            DeviceVerdict::Failed => "verification failed",
+        }
+    }
+}
+
+/// Per-device result slot of the registry, written by one work item at a time.
+pub struct DeviceSlot {
+    locked: AtomicBool,
+    verdict: UnsafeCell<Option<DeviceVerdict>>,
+}
+
+// The verdict is only accessed through a `SlotGuard`, which holds the lock.
+unsafe impl Sync for DeviceSlot {}
+
+impl DeviceSlot {
+    /// Creates an unlocked slot without a verdict.
+    pub const fn new() -> Self {
+        Self { locked: AtomicBool::new(false), verdict: UnsafeCell::new(None) }
+    }
+
+    /// Locks the slot.
+    ///
+    /// Never waits, since work items may run in parallel on other CPUs and
+    /// each device is verified by a single work item.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<SlotGuard>` - Guard giving access to the verdict, None if the slot is busy
+    pub fn lock(&self) -> Option<SlotGuard<'_>> {
+        self.locked.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).ok()?;
+        Some(SlotGuard { slot: self })
+    }
+}
+
+/// Access to the verdict of a locked slot; unlocks the slot when dropped.
+pub struct SlotGuard<'a> {
+    slot: &'a DeviceSlot,
+}
+
+impl Deref for SlotGuard<'_> {
+    type Target = Option<DeviceVerdict>;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.slot.verdict.get() }
+    }
+}
+
+impl DerefMut for SlotGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.slot.verdict.get() }
+    }
+}
+
+impl Drop for SlotGuard<'_> {
+    fn drop(&mut self) {
+        self.slot.locked.store(false, Ordering::Release);
+    }
+}
@@ -0,0 +1,66 @@
+//! Detection of virtualized environments.
+//!
+//! CI farms load the module in VMs, where there is no physical touchpad and
+//! the input devices are emulated: a USB tablet for absolute pointing (QEMU,
+//! VMware, VirtualBox), an emulated PS/2 mouse and keyboard, and paravirtual
+//! devices such as virtio input. Knowing that the kernel runs under a
+//! hypervisor lets the verifier report a missing touchpad as expected and keep
+//! the emulated pointers out of touchpad detection.
+use alloc::string::String;
+
+use crate::kbuf::ShimBuf;
+use crate::kprint;
+use crate::matcher;
+use crate::shim;
+use crate::text;
+
+/// Name fragments of input devices emulated by common hypervisors.
+const VIRTUAL_DEVICE_NAMES: &[&str] = &[
+    "QEMU",
+    "VMware",
+    "VirtualBox",
+    "Virtio",
+    "virtio",
+    "spice vdagent",
+    "Microsoft Vmbus",
+    "Xen Virtual",
+    "Parallels",
+];
+
+/// Name fragments of PS/2 devices, which hypervisors emulate as the default mouse.
+const PS2_EMULATED_NAMES: &[&str] = &[
+    "PS/2 Generic Mouse",
+    "ImPS/2",
+    "ImExPS/2",
+];
+
+/// Returns the name of the hypervisor the kernel runs under.
+///
+/// # Returns
+///
+/// * `Option<String>` - Hypervisor or system vendor name, or None on bare metal
+pub fn detect_hypervisor() -> Option<String> {
+    let kernel_get_hypervisor = shim::ops().get_hypervisor?;
+
+    let mut buffer = ShimBuf::new(64, 0u8);
+    let capacity = buffer.capacity();
+    if unsafe { kernel_get_hypervisor(buffer.as_mut_ptr(), capacity, buffer.reported_mut()) } <= 0 {
+        return None;
+    }
+
+    let name = text::escape_lossy(&buffer.into_vec()).into_owned();
+    kprint!("Running under a hypervisor: {}\n", name);
+    Some(name)
+}
+
+/// Checks if an input device is emulated by a hypervisor.
+///
+/// Only meaningful under a hypervisor; on bare metal a PS/2 mouse is real.
+///
+/// # Arguments
+///
+/// * `name` - Raw name of the input device
+pub fn is_virtual_device(name: &[u8]) -> bool {
+    VIRTUAL_DEVICE_NAMES.iter().chain(PS2_EMULATED_NAMES.iter())
+        .any(|fragment| matcher::contains(name, fragment.as_bytes()))
+}
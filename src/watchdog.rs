@@ -0,0 +1,159 @@
+//! Watchdog detecting suspected touchpad freezes.
+//!
+//! While enabled, the watchdog passively monitors the touchpad and the keyboards
+//! through the event monitor. A freeze is suspected when the touchpad produced
+//! events before, has been silent for at least the configured period, and a
+//! keyboard was used after the touchpad went silent. This is the typical
+//! symptom of an i2c-hid lockup: the rest of the system keeps working while
+//! the touchpad stops reporting.
+//!
+//! The watchdog is driven by periodic calls to `tick` from a C delayed work item.
+use crate::event_monitor::{now_ms, EVENT_MONITOR, WATCHDOG_ACTIVITY_SLOT, WATCHDOG_TOUCHPAD_SLOT};
+use crate::kprint;
+use crate::notify::{notify, Notification};
+
+/// Default silence period after which a freeze is suspected, in milliseconds.
+pub const DEFAULT_FREEZE_MS: u32 = 30000;
+
+/// Watchdog configuration and state.
+pub struct Watchdog {
+    enabled: bool,
+    freeze_ms: u32,
+    reverify: bool,
+    running: bool,
+    /// Time of the last touchpad event the current incident was reported for.
+    reported_event_ms: u64,
+    incidents: u32,
+}
+
+impl Watchdog {
+    /// Creates a disabled watchdog.
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            freeze_ms: DEFAULT_FREEZE_MS,
+            reverify: false,
+            running: false,
+            reported_event_ms: 0,
+            incidents: 0,
+        }
+    }
+
+    /// Configures the watchdog.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether the watchdog should run after the next scan
+    /// * `freeze_ms` - Touchpad silence period after which a freeze is suspected
+    /// * `reverify` - Whether a suspected freeze triggers a re-verification
+    pub fn configure(&mut self, enabled: bool, freeze_ms: u32, reverify: bool) {
+        self.enabled = enabled;
+        self.freeze_ms = freeze_ms;
+        self.reverify = reverify;
+        if !enabled {
+            self.stop();
+        }
+    }
+
+    /// Changes the silence period after which a freeze is suspected, keeping the watchdog running.
+    pub fn set_freeze_ms(&mut self, freeze_ms: u32) {
+        self.freeze_ms = freeze_ms;
+    }
+
+    /// Returns true if a suspected freeze should trigger a re-verification.
+    pub fn reverify(&self) -> bool {
+        self.reverify
+    }
+
+    /// Starts monitoring the touchpad and keyboards.
+    ///
+    /// Keyboards that can't be monitored are skipped; without any keyboard the
+    /// watchdog can't tell a freeze from an idle user and does not start.
+    ///
+    /// # Arguments
+    ///
+    /// * `touchpad_path` - Path to the touchpad device node
+    /// * `keyboard_paths` - Paths to the keyboard device nodes
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ()>` - Ok if monitoring started, Err otherwise
+    pub fn start<'a>(&mut self, touchpad_path: &str, keyboard_paths: impl Iterator<Item = &'a str>) -> Result<(), ()> {
+        self.stop();
+        if !self.enabled {
+            return Ok(());
+        }
+
+        EVENT_MONITOR.attach(WATCHDOG_TOUCHPAD_SLOT, touchpad_path)?;
+
+        let mut keyboards = 0;
+        for path in keyboard_paths {
+            if EVENT_MONITOR.attach(WATCHDOG_ACTIVITY_SLOT, path).is_ok() {
+                keyboards += 1;
+            }
+        }
+
+        if keyboards == 0 {
+            kprint!("Watchdog: no keyboard to monitor, not starting\n");
+            EVENT_MONITOR.detach(WATCHDOG_TOUCHPAD_SLOT);
+            return Err(());
+        }
+
+        kprint!("Watchdog: monitoring {} with {} keyboard(s), freeze after {} ms\n",
+                touchpad_path, keyboards, self.freeze_ms);
+        self.reported_event_ms = 0;
+        self.running = true;
+        Ok(())
+    }
+
+    /// Stops monitoring.
+    pub fn stop(&mut self) {
+        if self.running {
+            EVENT_MONITOR.detach(WATCHDOG_TOUCHPAD_SLOT);
+            EVENT_MONITOR.detach(WATCHDOG_ACTIVITY_SLOT);
+            self.running = false;
+        }
+    }
+
+    /// Checks for a suspected freeze.
+    ///
+    /// Each period of touchpad silence is reported at most once; the watchdog
+    /// re-arms as soon as the touchpad produces events again.
+    ///
+    /// # Arguments
+    ///
+    /// * `touchpad_path` - Path of the monitored touchpad, used for logging
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if a new freeze incident was recorded
+    pub fn tick(&mut self, touchpad_path: &str) -> bool {
+        if !self.running {
+            return false;
+        }
+
+        let now = now_ms();
+        let touchpad_last = EVENT_MONITOR.last_event_ms(WATCHDOG_TOUCHPAD_SLOT);
+        let activity_last = EVENT_MONITOR.last_event_ms(WATCHDOG_ACTIVITY_SLOT);
+
+        // The touchpad must have worked at some point to be considered frozen
+        if touchpad_last == 0 || touchpad_last == self.reported_event_ms {
+            return false;
+        }
+
+        let silent_ms = now.saturating_sub(touchpad_last);
+        let activity_recent = activity_last > touchpad_last
+            && now.saturating_sub(activity_last) < self.freeze_ms as u64;
+
+        if silent_ms < self.freeze_ms as u64 || !activity_recent {
+            return false;
+        }
+
+        self.reported_event_ms = touchpad_last;
+        self.incidents += 1;
+        kprint!("Watchdog: suspected touchpad freeze #{} on {}: silent for {} ms while keyboard is active\n",
+                self.incidents, touchpad_path, silent_ms);
+        notify(Notification::FreezeSuspected, touchpad_path, silent_ms.min(i32::MAX as u64) as i32);
+        true
+    }
+}